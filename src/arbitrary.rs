@@ -0,0 +1,116 @@
+//! [`Arbitrary`] implementations, for fuzzing
+//!
+//! Positions are generated to be legal (see [`Position::validate`]), so that
+//! fuzz inputs exercise move generation rather than being rejected up front.
+
+use ::arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::{
+    bits::{Bitboard, Rank, Square},
+    movegen::king_attacks,
+    position::{
+        Color,
+        Role,
+        Piece,
+        Position,
+        board::Board,
+        castling::Castling,
+    },
+};
+
+impl<'a> Arbitrary<'a> for Square {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Square::new(u.int_in_range(0..=63)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Bitboard {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Bitboard::new(u.arbitrary()?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Color {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(*u.choose(&[Color::White, Color::Black])?)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Role {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Role::try_from(u.int_in_range(0..=5)?).unwrap())
+    }
+}
+
+impl<'a> Arbitrary<'a> for Piece {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Piece(u.arbitrary()?, u.arbitrary()?))
+    }
+}
+
+/// The most pieces of each role a side starts with (other than the king)
+const MAX_ROLE_COUNTS: [usize; 5] = [8, 2, 2, 2, 1];
+
+impl<'a> Arbitrary<'a> for Position {
+    /// Generates a legal position, with no castling rights or en passant
+    /// square
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut placement = [None; Square::COUNT];
+
+        // The kings can't be next to each other
+        let white_king: Square = u.arbitrary()?;
+        let black_kings: Vec<Square> = Square::iter()
+            .filter(|&s| s != white_king && !king_attacks(white_king).contains(s))
+            .collect();
+        let black_king = *u.choose(&black_kings)?;
+        placement[usize::from(white_king)] = Some(Piece(Color::White, Role::King));
+        placement[usize::from(black_king)] = Some(Piece(Color::Black, Role::King));
+
+        // No more pieces than a side starts with, and no pawns on the back
+        // ranks
+        let mut counts = [[0; 5]; 2];
+        for _ in 0..u.int_in_range(0..=30)? {
+            let s: Square = u.arbitrary()?;
+            let Piece(c, r) = u.arbitrary()?;
+            let back_rank = matches!(s.rank(), Rank::First | Rank::Eighth);
+            if placement[usize::from(s)].is_some() ||
+               r == Role::King ||
+               (r == Role::Pawn && back_rank) ||
+               counts[c.index()][r.index()] == MAX_ROLE_COUNTS[r.index()] {
+                continue
+            }
+            counts[c.index()][r.index()] += 1;
+            placement[usize::from(s)] = Some(Piece(c, r));
+        }
+
+        let mut p = Position::from_parts(
+            Board::from_placement(placement),
+            u.arbitrary()?,
+            Castling::new(),
+            None,
+            0,
+            1,
+        );
+
+        // The side not to move can't be in check, and the side to move can be
+        // in at most double check (removing a checker may uncover another)
+        let their_king = p.board.king_square(!p.turn);
+        loop {
+            let attackers = p.attackers(their_king, p.turn);
+            if attackers.is_empty() {
+                break
+            }
+            for s in attackers {
+                p.board_mut().remove(s);
+            }
+        }
+        while p.checkers().count() > 2 {
+            for s in p.checkers().into_iter().skip(2) {
+                p.board_mut().remove(s);
+            }
+        }
+        p.rebuild_zobrist();
+
+        Ok(p)
+    }
+}
@@ -13,6 +13,10 @@ use crate::util::PRINT_ORDER;
 //===========//
 
 /// Used to find the flipped representation of a object (from opposite POV)
+///
+/// Flipping mirrors the ranks, so that a1 and a8 trade places but the files
+/// stay put, as when the board is seen from the other side's point of view.
+/// Anything with colors, like a board or castling rights, also swaps them.
 pub trait Flippable 
 where Self: Sized {
     /// Returns the flipped representation
@@ -31,7 +35,7 @@ where Self: Sized {
 
 /// A row on a chessboard
 #[allow(missing_docs)]
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
 pub enum Rank {
     First = 0,
     Second = 1,
@@ -204,10 +208,89 @@ impl File {
 //========//
 
 /// The coordinates of a square on a chessboard
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
 pub struct Coords(pub File, pub Rank);
 
 
+//===========//
+// Direction //
+//===========//
+
+/// A compass direction on a chessboard, with north pointing towards the 8th
+/// rank
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    North = 0,
+    Northeast,
+    East,
+    Southeast,
+    South,
+    Southwest,
+    West,
+    Northwest
+}
+
+impl Direction {
+    /// Returns an iterator over all of the directions
+    pub fn iter() -> std::array::IntoIter<Direction, 8> {
+        const DIRECTIONS: [Direction; 8] = [
+            Direction::North,
+            Direction::Northeast,
+            Direction::East,
+            Direction::Southeast,
+            Direction::South,
+            Direction::Southwest,
+            Direction::West,
+            Direction::Northwest,
+        ];
+        DIRECTIONS.into_iter()
+    }
+
+    /// Gets the opposite direction
+    #[inline]
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::North => Direction::South,
+            Direction::Northeast => Direction::Southwest,
+            Direction::East => Direction::West,
+            Direction::Southeast => Direction::Northwest,
+            Direction::South => Direction::North,
+            Direction::Southwest => Direction::Northeast,
+            Direction::West => Direction::East,
+            Direction::Northwest => Direction::Southeast,
+        }
+    }
+
+    /// Gets the number of files east and ranks north of one step in the 
+    /// direction (negative going west or south)
+    #[inline]
+    pub const fn offsets(self) -> (i32, i32) {
+        match self {
+            Direction::North => (0, 1),
+            Direction::Northeast => (1, 1),
+            Direction::East => (1, 0),
+            Direction::Southeast => (1, -1),
+            Direction::South => (0, -1),
+            Direction::Southwest => (-1, -1),
+            Direction::West => (-1, 0),
+            Direction::Northwest => (-1, 1),
+        }
+    }
+
+    /// Returns `true` if stepping in the direction increases the square index
+    #[inline]
+    pub(crate) fn is_positive(self) -> bool {
+        matches!(
+            self,
+            Direction::North |
+            Direction::Northeast |
+            Direction::East |
+            Direction::Northwest
+        )
+    }
+}
+
 //========//
 // Square //
 //========//
@@ -227,21 +310,52 @@ pub struct Coords(pub File, pub Rank);
 /// 
 ///      a  b  c  d  e  f  g  h
 /// ```
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, PartialOrd, Ord)]
 pub struct Square(u32);
 
 impl Square {
     /// Total number of squares on a chessboard
     pub const COUNT: usize = 64;
+
+    /// All of the squares, in order
+    /// 
+    /// Unlike [`iter`](Self::iter()), this can be looped over by index in a
+    /// `const fn`, e.g. to build a table at compile time
+    pub const ALL: [Square; Square::COUNT] = {
+        let mut result = [Square::new(0); Square::COUNT];
+        let mut i = 0;
+        while i < Square::COUNT {
+            result[i] = Square::new(i as u32);
+            i += 1;
+        }
+        result
+    };
 }
 
 impl Square {
     /// Creates a new square
+    /// 
+    /// # Precondition
+    /// 
+    /// `value` **must** be less than 64. This is only checked in debug builds, 
+    /// so in release builds an out-of-range value silently produces a corrupt 
+    /// square that will later index out of bounds. Use 
+    /// [`try_new`](Self::try_new()) unless `value` is known to be valid.
     #[inline]
     pub const fn new(value: u32) -> Self {    
         debug_assert!(value < 64);
         Square(value)
     }
+
+    /// Creates a new square, or [`None`] if `value` is not less than 64
+    #[inline]
+    pub const fn try_new(value: u32) -> Option<Self> {
+        if value < 64 {
+            Some(Square(value))
+        } else {
+            None
+        }
+    }
     
     /// Gets rank of square
     #[inline]
@@ -255,18 +369,50 @@ impl Square {
         File::try_from(self.0 % 8).unwrap()
     }
     
+    /// Gets the square's index, for indexing tables (including in a 
+    /// `const fn`, unlike `usize::from`)
+    #[inline]
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    /// Gets the square `files` files to the east and `ranks` ranks to the 
+    /// north (negative to go west or south), or [`None`] if that's off the 
+    /// board
+    #[inline]
+    pub const fn offset(self, files: i32, ranks: i32) -> Option<Square> {
+        let file = (self.0 % 8) as i32 + files;
+        let rank = (self.0 / 8) as i32 + ranks;
+        if 0 <= file && file < 8 && 0 <= rank && rank < 8 {
+            Some(Square((rank * 8 + file) as u32))
+        } else {
+            None
+        }
+    }
+
+    /// Returns an iterator over the squares stepping from the square in 
+    /// direction `dir`, nearest first, until the edge of the board (the 
+    /// square itself isn't included)
+    /// 
+    /// The squares are found as they're iterated, unlike the bitboards of 
+    /// [`RAYS`](crate::movegen::util::RAYS).
+    pub fn ray(self, dir: Direction) -> impl Iterator<Item = Square> {
+        let (files, ranks) = dir.offsets();
+        std::iter::successors(self.offset(files, ranks), move |s| s.offset(files, ranks))
+    }
+
+    /// Gets the number of king steps between two squares, i.e. the larger of
+    /// their rank and file distances
+    #[inline]
+    pub fn distance(self, other: Square) -> u32 {
+        let ranks = (self.0 / 8).abs_diff(other.0 / 8);
+        let files = (self.0 % 8).abs_diff(other.0 % 8);
+        ranks.max(files)
+    }
+    
     /// Returns an iterator over all of the squares
     pub fn iter() -> std::array::IntoIter<Square, {Self::COUNT}> {
-        const SQUARES: [Square; Square::COUNT] = {
-            let mut result = [Square::new(0); Square::COUNT];
-            let mut i = 0;
-            while i < Square::COUNT {
-                result[i] = Square::new(i as u32);
-                i += 1;
-            }
-            result
-        };
-        SQUARES.into_iter()
+        Self::ALL.into_iter()
     }
 }
 
@@ -274,14 +420,22 @@ impl From<Coords> for Square {
     /// Creates a square from a [`Coords`]
     #[inline]
     fn from(Coords(f, r): Coords) -> Self {
-        Square::new(r as u32 * 8 + f as u32)
+        let value = (r as u32)
+            .checked_mul(8)
+            .and_then(|v| v.checked_add(f as u32))
+            .and_then(Square::try_new);
+        match value {
+            Some(s) => s,
+            None => panic!("{:?} is not a valid square", Coords(f, r)),
+        }
     }
 }
 
 impl Flippable for Square {
+    /// Mirrors the square's rank, e.g. c6 to c3
     #[inline]
     fn flipped(&self) -> Self {
-        Square(63 - self.0)
+        Square(self.0 ^ 56)
     }
 }
 
@@ -306,6 +460,14 @@ impl From<Square> for usize {
     }
 }
 
+/// Returns an iterator over all of the squares in the order they are 
+/// displayed, from rank 8 to rank 1 and from file a to h within each rank
+/// (a8, b8, ..., h8, a7, ..., h1)
+pub fn squares_display_order() -> impl Iterator<Item = Square> {
+    PRINT_ORDER.into_iter().flatten().map(|i| Square(i as u32))
+}
+
+
 //==========//
 // Bitboard //
 //==========//
@@ -332,20 +494,20 @@ impl From<Square> for usize {
 /// 
 /// bitboard of knights     bitboard of white pieces   bitboard of white knights
 /// ```
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, PartialOrd, Ord)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, PartialOrd, Ord, Hash)]
 pub struct Bitboard(u64);
 
 
 impl Bitboard {
     const RANK_MASKS: [u64; 8] = [
-        0xFF00_0000_0000_0000,
-        0x00FF_0000_0000_0000,
-        0x0000_FF00_0000_0000,
-        0x0000_00FF_0000_0000,
-        0x0000_0000_FF00_0000,
-        0x0000_0000_00FF_0000,
-        0x0000_0000_0000_FF00,
         0x0000_0000_0000_00FF,
+        0x0000_0000_0000_FF00,
+        0x0000_0000_00FF_0000,
+        0x0000_0000_FF00_0000,
+        0x0000_00FF_0000_0000,
+        0x0000_FF00_0000_0000,
+        0x00FF_0000_0000_0000,
+        0xFF00_0000_0000_0000,
     ];
 
     const FILE_MASKS: [u64; 8] = [
@@ -370,8 +532,67 @@ impl Bitboard {
         Self::RANK_MASKS[0] | Self::RANK_MASKS[7] | 
         Self::FILE_MASKS[0] | Self::FILE_MASKS[7]
     );
+
+    /// A bitboard of all the light squares
+    pub const LIGHT_SQUARES: Bitboard = Bitboard::new(0x55AA_55AA_55AA_55AA);
+
+    /// A bitboard of all the dark squares
+    pub const DARK_SQUARES: Bitboard = Bitboard::new(!0x55AA_55AA_55AA_55AA);
 }    
 
+/// The masks of each file with the files beside it, indexed by file
+///
+/// A pawn is isolated if no friendly pawns are in the mask of its file, 
+/// besides itself.
+pub const ADJACENT_FILES: [u64; 8] = adjacent_files();
+
+/// The masks of the squares in front of a pawn, on its file and the files
+/// beside it, indexed by color index (0 for white, 1 for black) and square
+///
+/// A color `c` pawn on `s` is passed if no enemy pawns are in
+/// `PASSED_PAWN_MASK[c][s]`.
+pub const PASSED_PAWN_MASK: [[u64; 64]; 2] = [
+    passed_pawn_masks(0),
+    passed_pawn_masks(1),
+];
+
+/// Builds [`ADJACENT_FILES`]
+const fn adjacent_files() -> [u64; 8] {
+    let mut masks = [0; 8];
+    let mut f = 0;
+    while f < 8 {
+        let file = Bitboard::FILE_MASKS[f];
+        // Shifting by a file wraps onto the next rank, so mask that off
+        masks[f] = file | 
+            ((file << 1) & !Bitboard::FILE_MASKS[0]) | 
+            ((file >> 1) & !Bitboard::FILE_MASKS[7]);
+        f += 1;
+    }
+    masks
+}
+
+/// Builds the color index `c` masks of [`PASSED_PAWN_MASK`]
+const fn passed_pawn_masks(c: usize) -> [u64; 64] {
+    let adjacent = adjacent_files();
+    let mut masks = [0; 64];
+    let mut s = 0;
+    while s < 64 {
+        let (file, rank) = (s % 8, s / 8);
+        let mut in_front = 0;
+        let mut r = 0;
+        while r < 8 {
+            let ahead = if c == 0 { r > rank } else { r < rank };
+            if ahead {
+                in_front |= Bitboard::RANK_MASKS[r];
+            }
+            r += 1;
+        }
+        masks[s] = adjacent[file] & in_front;
+        s += 1;
+    }
+    masks
+}
+
 
 /// Create methods
 impl Bitboard {
@@ -394,6 +615,72 @@ impl Bitboard {
         Bitboard(Self::FILE_MASKS[f as usize])
     }
 
+    /// Creates a bitboard with the squares in the rank with index `i` set,
+    /// from 0 for the 1st rank to 7 for the 8th
+    /// 
+    /// This is for `const` table code that has only a numeric index. Panics
+    /// if `i` is 8 or more.
+    #[inline]
+    pub const fn rank_index(i: usize) -> Self {
+        Bitboard(Self::RANK_MASKS[i])
+    }
+
+    /// Creates a bitboard with the squares in the file with index `i` set,
+    /// from 0 for the a-file to 7 for the h-file
+    /// 
+    /// This is for `const` table code that has only a numeric index. Panics
+    /// if `i` is 8 or more.
+    #[inline]
+    pub const fn file_index(i: usize) -> Self {
+        Bitboard(Self::FILE_MASKS[i])
+    }
+
+    /// Creates a bitboard with the squares in the rank that is rank `r` from
+    /// the point of view of the color with index `c` (0 for white, 1 for 
+    /// black) set
+    #[inline]
+    const fn relative_rank(c: usize, r: Rank) -> Self {
+        let i = r as usize;
+        Bitboard::rank_index(if c == 0 { i } else { 7 - i })
+    }
+
+    /// Creates a bitboard with the squares in the rank that the pawns of the
+    /// color with index `c` (see [`Color::index`](crate::position::Color::index))
+    /// promote on set
+    #[inline]
+    pub const fn promotion_rank(c: usize) -> Self {
+        Bitboard::relative_rank(c, Rank::Eighth)
+    }
+
+    /// Creates a bitboard with the squares in the rank that the pawns of the
+    /// color with index `c` start on set
+    #[inline]
+    pub const fn start_rank(c: usize) -> Self {
+        Bitboard::relative_rank(c, Rank::Second)
+    }
+
+    /// Creates a bitboard with the squares in the rank of the en passant 
+    /// squares that the pawns of the color with index `c` can capture on set
+    #[inline]
+    pub const fn en_passant_rank(c: usize) -> Self {
+        Bitboard::relative_rank(c, Rank::Sixth)
+    }
+
+    /// Creates a bitboard with the squares in file `f` and the files beside
+    /// it set (see [`ADJACENT_FILES`])
+    #[inline]
+    pub const fn adjacent_files(f: File) -> Self {
+        Bitboard(ADJACENT_FILES[f as usize])
+    }
+
+    /// Creates a bitboard with the squares in front of a pawn of the color 
+    /// with index `c` on `s`, on its file and the files beside it, set (see 
+    /// [`PASSED_PAWN_MASK`])
+    #[inline]
+    pub const fn passed_pawn_mask(c: usize, s: Square) -> Self {
+        Bitboard(PASSED_PAWN_MASK[c][s.index()])
+    }
+
     /// Creates a bitboard with the square `s` set
     #[inline]
     pub const fn square(s: Square) -> Self {
@@ -466,6 +753,142 @@ impl Bitboard {
         })
     }
 
+    /// Returns the `n`th lowest square in the bitboard, counting from 0, or 
+    /// [`None`] if it has no more than `n` squares
+    pub fn nth_square(self, n: u32) -> Option<Square> {
+        if n >= self.count() {
+            return None
+        }
+        let mut value = self.0;
+        for _ in 0..n {
+            value &= value - 1;
+        }
+        Some(Square::new(value.trailing_zeros()))
+    }
+
+    /// Calls `f` on each square in the bitboard, from the lowest to the 
+    /// highest
+    /// 
+    /// This is a tighter loop than iterating, for the hottest paths.
+    #[inline]
+    pub fn for_each_square(self, mut f: impl FnMut(Square)) {
+        let mut value = self.0;
+        while value != 0 {
+            f(Square::new(value.trailing_zeros()));
+            value &= value - 1;
+        }
+    }
+
+    /// Returns the number of squares in the bitboard lower than `s`, which is
+    /// the inverse of [`nth_square`](Self::nth_square()), or [`None`] if `s`
+    /// isn't in the bitboard
+    pub fn square_index(self, s: Square) -> Option<u32> {
+        self.contains(s).then(|| {
+            let below = Bitboard::square(s).0 - 1;
+            (self.0 & below).count_ones()
+        })
+    }
+
+    /// Returns the sum of `weights` over the squares in the bitboard, indexed
+    /// by square, which is the dot product of the bitboard with a
+    /// piece-square table
+    #[inline]
+    pub fn weighted_count(self, weights: &[i32; Square::COUNT]) -> i32 {
+        let mut sum = 0;
+        self.for_each_square(|s| sum += weights[s.index()]);
+        sum
+    }
+
+    /// Returns the squares reachable from the bitboard's squares by
+    /// repeatedly stepping north, south, east or west onto squares in
+    /// `passable`
+    ///
+    /// The starting squares are always included, whether or not they are
+    /// passable themselves
+    pub fn flood_fill(self, passable: Bitboard) -> Bitboard {
+        let not_a = !Self::FILE_MASKS[0];
+        let not_h = !Self::FILE_MASKS[7];
+        let passable = passable.0;
+        let mut filled = self.0;
+        loop {
+            let dilated = filled |
+                (filled << 8) |
+                (filled >> 8) |
+                ((filled << 1) & not_a) |
+                ((filled >> 1) & not_h);
+            let next = filled | (dilated & passable);
+            if next == filled {
+                return Bitboard(filled)
+            }
+            filled = next;
+        }
+    }
+
+    /// Returns a mask of the files with a square set, where bit `i` is set for
+    /// the `i`th file (bit 0 for the a-file)
+    #[inline]
+    pub fn occupied_files(self) -> u8 {
+        let mut b = self.0;
+        b |= b >> 32;
+        b |= b >> 16;
+        b |= b >> 8;
+        b as u8
+    }
+
+    /// Returns a mask of the ranks with a square set, where bit `i` is set for
+    /// the `i`th rank (bit 0 for the 1st rank)
+    #[inline]
+    pub fn occupied_ranks(self) -> u8 {
+        let mut ranks = 0;
+        for (i, mask) in Self::RANK_MASKS.into_iter().enumerate() {
+            if self.0 & mask != 0 {
+                ranks |= 1 << i;
+            }
+        }
+        ranks
+    }
+
+    /// Creates a bitboard with the squares of every file in the mask `files`
+    /// set, where bit `i` stands for the `i`th file (as in 
+    /// [`occupied_files`](Self::occupied_files()))
+    #[inline]
+    pub fn files(files: u8) -> Self {
+        Bitboard((files as u64).wrapping_mul(0x0101_0101_0101_0101))
+    }
+
+    /// Returns the squares one king step away from the bitboard's squares,
+    /// excluding the bitboard's squares themselves
+    pub fn adjacent(self) -> Bitboard {
+        let not_a = !Self::FILE_MASKS[0];
+        let not_h = !Self::FILE_MASKS[7];
+        let b = self.0;
+        let sideways = ((b << 1) & not_a) | ((b >> 1) & not_h);
+        let row = b | sideways;
+        let dilated = sideways | (row << 8) | (row >> 8);
+        Bitboard(dilated & !b)
+    }
+
+    /// Gets the king distance from every square to the nearest of the 
+    /// bitboard's squares, found by repeatedly dilating it with 
+    /// [`adjacent`](Self::adjacent())
+    /// 
+    /// Every distance is [`u8::MAX`] if the bitboard is empty
+    pub fn distance_map_from(self) -> [u8; Square::COUNT] {
+        let mut map = [u8::MAX; Square::COUNT];
+        let mut reached = self;
+        let mut frontier = self;
+        let mut distance = 0;
+        while frontier.is_any() {
+            for s in frontier {
+                map[usize::from(s)] = distance;
+            }
+            frontier = reached.adjacent();
+            reached |= frontier;
+            distance += 1;
+        }
+        map
+    }
+
     /// Returns a vector of all subsets via the [Carry-Rippler trick](https://www.chessprogramming.org/Traversing_Subsets_of_a_Set#All_Subsets_of_any_Set)
     pub fn subsets(self) -> Vec<Bitboard> {
         let set = u64::from(self);
@@ -525,6 +948,24 @@ impl Bitboard {
         self.0 &= !(1 << s.0);
         c
     }
+
+    /// Returns a copy of the bitboard with the square set
+    #[inline]
+    pub const fn with_square(self, s: Square) -> Bitboard {
+        Bitboard(self.0 | 1 << s.0)
+    }
+
+    /// Returns a copy of the bitboard with the square cleared
+    #[inline]
+    pub const fn without_square(self, s: Square) -> Bitboard {
+        Bitboard(self.0 & !(1 << s.0))
+    }
+
+    /// Returns a copy of the bitboard with the square toggled
+    #[inline]
+    pub const fn toggled(self, s: Square) -> Bitboard {
+        Bitboard(self.0 ^ 1 << s.0)
+    }
 }
 
 impl From<Bitboard> for u64 {
@@ -592,9 +1033,10 @@ impl Not for Bitboard {
 }
 
 impl Flippable for Bitboard {
+    /// Mirrors the ranks of the bitboard
     #[inline]
     fn flipped(&self) -> Self {
-        Bitboard(self.0.reverse_bits())
+        Bitboard(self.0.swap_bytes())
     }
 }
 
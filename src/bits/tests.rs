@@ -106,6 +106,21 @@ mod square_tests {
         assert_eq!(s1.0, 42);
     }
 
+    #[test]
+    fn try_new() {
+        assert_eq!(Square::try_new(63), Some(Square::new(63)));
+        assert_eq!(Square::try_new(64), None);
+        assert_eq!(Square::try_new(u32::MAX), None);
+    }
+
+    #[test]
+    fn from_coords() {
+        use crate::bits::{Coords, File, Rank};
+        assert_eq!(Square::from(Coords(File::A, Rank::First)), Square::new(0));
+        assert_eq!(Square::from(Coords(File::C, Rank::Sixth)), Square::new(42));
+        assert_eq!(Square::from(Coords(File::H, Rank::Eighth)), Square::new(63));
+    }
+
     #[test]
     fn rank() {
         use crate::bits::Rank;
@@ -131,7 +146,85 @@ mod square_tests {
     #[test]
     fn flip() {
         use crate::bits::Flippable;
-        assert_eq!(Square::new(42).flipped(), Square::new(21));
+        // c6 and c3 trade places
+        assert_eq!(Square::new(42).flipped(), Square::new(18));
+        assert_eq!(Square::new(18).flipped(), Square::new(42));
+    }
+
+    #[test]
+    fn offset() {
+        assert_eq!(Square::new(0).offset(1, 2), Some(Square::new(17)));
+        assert_eq!(Square::new(0).offset(-1, 0), None);
+        assert_eq!(Square::new(63).offset(0, 1), None);
+        assert_eq!(Square::new(7).offset(1, 0), None);
+        for (i, s) in Square::ALL.into_iter().enumerate() {
+            assert_eq!(s.index(), i);
+        }
+    }
+
+    #[test]
+    fn distance() {
+        assert_eq!(Square::new(0).distance(Square::new(63)), 7);
+        assert_eq!(Square::new(42).distance(Square::new(42)), 0);
+        // c6 to d4 and to h6
+        assert_eq!(Square::new(42).distance(Square::new(27)), 2);
+        assert_eq!(Square::new(42).distance(Square::new(47)), 5);
+    }
+
+    #[test]
+    fn ray() {
+        use crate::bits::Bitboard;
+        use crate::{bits::Direction, movegen::util::RAYS};
+        let north: Vec<String> = Square::new(0).ray(Direction::North).map(|s| s.to_string()).collect();
+        assert_eq!(north, ["a2", "a3", "a4", "a5", "a6", "a7", "a8"]);
+        let southwest: Vec<String> = Square::new(27).ray(Direction::Southwest).map(|s| s.to_string()).collect();
+        assert_eq!(southwest, ["c3", "b2", "a1"]);
+        assert_eq!(Square::new(7).ray(Direction::East).count(), 0);
+        // The same squares as the precomputed rays, which include the origin
+        for s in Square::iter() {
+            for dir in Direction::iter() {
+                let ray = s.ray(dir).fold(Bitboard::EMPTY, |b, s| b.with_square(s));
+                let expected = Bitboard::new(RAYS[dir as usize][s.index()]).without_square(s);
+                assert_eq!(ray, expected, "{} {:?}", s, dir);
+            }
+        }
+    }
+
+    #[test]
+    fn display_order() {
+        use crate::bits::squares_display_order;
+        let squares: Vec<Square> = squares_display_order().collect();
+        assert_eq!(squares.len(), 64);
+        assert_eq!(squares[0].to_string(), "a8");
+        assert_eq!(squares[1].to_string(), "b8");
+        assert_eq!(squares[8].to_string(), "a7");
+        assert_eq!(squares[63].to_string(), "h1");
+    }
+
+    #[test]
+    fn hash_map_key() {
+        use std::collections::HashMap;
+        use crate::bits::{Bitboard, Coords, File, Rank};
+        use crate::position::{Color, Piece, Role};
+
+        let mut pieces = HashMap::new();
+        pieces.insert(Square::new(4), Piece(Color::White, Role::King));
+        pieces.insert(Square::new(60), Piece(Color::Black, Role::King));
+        pieces.insert(Square::new(4), Piece(Color::White, Role::Rook));
+        assert_eq!(pieces.len(), 2);
+        assert_eq!(pieces[&Square::new(4)], Piece(Color::White, Role::Rook));
+        assert_eq!(pieces.get(&Square::new(0)), None);
+
+        let mut counts = HashMap::new();
+        *counts.entry(Piece(Color::Black, Role::Pawn)).or_insert(0) += 1;
+        *counts.entry(Piece(Color::White, Role::Pawn)).or_insert(0) += 1;
+        *counts.entry(Piece(Color::Black, Role::Pawn)).or_insert(0) += 1;
+        assert_eq!(counts[&Piece(Color::Black, Role::Pawn)], 2);
+
+        let mut seen = std::collections::HashSet::new();
+        assert!(seen.insert(Bitboard::new(0xFF)));
+        assert!(!seen.insert(Bitboard::rank(Rank::First)));
+        assert!(seen.insert(Bitboard::square(Square::from(Coords(File::E, Rank::Fourth)))));
     }
 }
 
@@ -139,6 +232,16 @@ mod square_tests {
 mod bitboard_tests{
     use crate::bits::Bitboard;
 
+    #[test]
+    fn rank_and_file() {
+        use crate::bits::{Rank, File, Square};
+        assert!(Bitboard::rank(Rank::First).contains(Square::new(0)));
+        assert!(Bitboard::rank(Rank::Eighth).contains(Square::new(63)));
+        assert!(Bitboard::file(File::A).contains(Square::new(56)));
+        assert!(Bitboard::file(File::H).contains(Square::new(7)));
+        assert_eq!(Bitboard::rank(Rank::Second).count(), 8);
+    }
+
     #[test]
     fn subsets() {
         let b = Bitboard::new(123);
@@ -151,4 +254,202 @@ mod bitboard_tests{
         subsets_slow.sort();
         assert_eq!(subsets, subsets_slow)
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn with_square() {
+        use crate::bits::Square;
+        let b = Bitboard::new(0b1010);
+        let s = Square::new(2);
+        assert!(b.with_square(s).contains(s));
+        assert_eq!(b.with_square(s), Bitboard::new(0b1110));
+        assert_eq!(b.with_square(Square::new(1)), b);
+    }
+
+    #[test]
+    fn without_square() {
+        use crate::bits::Square;
+        let b = Bitboard::new(0b1010);
+        assert!(!b.without_square(Square::new(1)).contains(Square::new(1)));
+        assert_eq!(b.without_square(Square::new(1)), Bitboard::new(0b1000));
+        assert_eq!(b.without_square(Square::new(0)), b);
+    }
+
+    #[test]
+    fn toggled() {
+        use crate::bits::Square;
+        let b = Bitboard::new(0b1010);
+        assert_eq!(b.toggled(Square::new(1)), Bitboard::new(0b1000));
+        assert_eq!(b.toggled(Square::new(0)), Bitboard::new(0b1011));
+        assert_eq!(b.toggled(Square::new(0)).toggled(Square::new(0)), b);
+    }
+
+    #[test]
+    fn flood_fill() {
+        use crate::bits::{Rank, File, Square};
+        // An L-shaped corridor up the a-file and along the 8th rank
+        let corridor = Bitboard::file(File::A) | Bitboard::rank(Rank::Eighth);
+        // h2 is next to a3 by index, but not on the board, and d4 is cut off
+        let passable = corridor.with_square(Square::new(15)).with_square(Square::new(27));
+        let a1 = Bitboard::square(Square::new(0));
+        assert_eq!(a1.flood_fill(passable), corridor);
+
+        // The start square needn't be passable, but nothing else is reached
+        let e5 = Bitboard::square(Square::new(36));
+        assert_eq!(e5.flood_fill(passable), e5);
+        assert_eq!(a1.flood_fill(Bitboard::EMPTY), a1);
+    }
+
+    #[test]
+    fn adjacent() {
+        use crate::bits::Square;
+        // a1 touches a2, b1 and b2
+        assert_eq!(u64::from(Bitboard::square(Square::new(0)).adjacent()), 0x302);
+        // h4 touches g3, h3, g5, h5 and g4, but doesn't wrap onto the a-file
+        assert_eq!(u64::from(Bitboard::square(Square::new(31)).adjacent()), 0xC0_40C0_0000);
+        for s in Square::iter() {
+            let expected = Square::iter().filter(|&t| s.distance(t) == 1).count();
+            assert_eq!(Bitboard::square(s).adjacent().count() as usize, expected);
+        }
+    }
+
+    #[test]
+    fn distance_map_from() {
+        use crate::bits::Square;
+        for s in Square::iter() {
+            let map = Bitboard::square(s).distance_map_from();
+            for t in Square::iter() {
+                assert_eq!(u32::from(map[usize::from(t)]), s.distance(t));
+            }
+        }
+        // a1 and h8 meet in the middle
+        let corners = Bitboard::square(Square::new(0)) | Bitboard::square(Square::new(63));
+        let map = corners.distance_map_from();
+        assert_eq!(map[27], 3);
+        assert_eq!(map[36], 3);
+        assert_eq!(map[7], 7);
+        assert_eq!(Bitboard::EMPTY.distance_map_from(), [u8::MAX; 64]);
+    }
+
+    #[test]
+    fn occupied_files_and_ranks() {
+        use crate::bits::{File, Rank, Square};
+        // A single d4 pawn
+        let d4 = Bitboard::square(Square::new(27));
+        assert_eq!(d4.occupied_files(), 1 << File::D as u8);
+        assert_eq!(d4.occupied_ranks(), 1 << Rank::Fourth as u8);
+        assert_eq!(Bitboard::files(d4.occupied_files()), Bitboard::file(File::D));
+
+        let corners = Bitboard::square(Square::new(0)) | Bitboard::square(Square::new(63));
+        assert_eq!(corners.occupied_files(), 0b1000_0001);
+        assert_eq!(corners.occupied_ranks(), 0b1000_0001);
+        assert_eq!(Bitboard::EMPTY.occupied_files(), 0);
+        assert_eq!(Bitboard::files(0xFF), !Bitboard::EMPTY);
+    }
+
+    #[test]
+    fn nth_square() {
+        use crate::bits::Square;
+        // a1, d4, e5 and h8
+        let b = Bitboard::new(1 | 1 << 27 | 1 << 36 | 1 << 63);
+        assert_eq!(b.nth_square(0), Some(Square::new(0)));
+        assert_eq!(b.nth_square(2), Some(Square::new(36)));
+        assert_eq!(b.nth_square(4), None);
+        assert_eq!(b.square_index(Square::new(63)), Some(3));
+        assert_eq!(b.square_index(Square::new(28)), None);
+        for n in 0..b.count() {
+            assert_eq!(b.square_index(b.nth_square(n).unwrap()), Some(n));
+        }
+        for s in b {
+            assert_eq!(b.nth_square(b.square_index(s).unwrap()), Some(s));
+        }
+    }
+
+    #[test]
+    fn for_each_square() {
+        use crate::bits::Square;
+        let b = Bitboard::new(1 | 1 << 27 | 1 << 36 | 1 << 63);
+        let mut visited = Vec::new();
+        b.for_each_square(|s| visited.push(s));
+        assert_eq!(visited, [0, 27, 36, 63].map(Square::new));
+        let mut reversed: Vec<Square> = b.collect();
+        reversed.reverse();
+        assert_eq!(visited, reversed);
+
+        let mut count = 0;
+        Bitboard::EMPTY.for_each_square(|_| count += 1);
+        (!Bitboard::EMPTY).for_each_square(|_| count += 1);
+        assert_eq!(count, 64);
+    }
+
+    #[test]
+    fn weighted_count() {
+        use crate::bits::Square;
+        let mut weights = [0; Square::COUNT];
+        for (i, w) in weights.iter_mut().enumerate() {
+            *w = i as i32 * 3 - 50;
+        }
+        let b = Bitboard::new(1 << 12 | 1 << 52);
+        assert_eq!(b.weighted_count(&weights), weights[12] + weights[52]);
+        assert_eq!(Bitboard::EMPTY.weighted_count(&weights), 0);
+        let all = !Bitboard::EMPTY;
+        assert_eq!(all.weighted_count(&weights), weights.iter().sum::<i32>());
+    }
+
+    // Checked at compile time
+    const _: () = assert!(Bitboard::rank_index(0).0 == Bitboard::rank(crate::bits::Rank::First).0);
+    const _: () = assert!(Bitboard::file_index(7).0 == Bitboard::file(crate::bits::File::H).0);
+
+    #[test]
+    fn index_masks() {
+        use crate::bits::{File, Rank};
+        for (i, r) in Rank::iter().enumerate() {
+            assert_eq!(Bitboard::rank_index(i), Bitboard::rank(r));
+        }
+        for (i, f) in File::iter().enumerate() {
+            assert_eq!(Bitboard::file_index(i), Bitboard::file(f));
+        }
+    }
+
+    #[test]
+    fn pawn_ranks() {
+        use crate::bits::Rank;
+        use crate::position::Color;
+        assert_eq!(Bitboard::promotion_rank(Color::White.index()), Bitboard::rank(Rank::Eighth));
+        assert_eq!(Bitboard::start_rank(Color::White.index()), Bitboard::rank(Rank::Second));
+        assert_eq!(Bitboard::en_passant_rank(Color::White.index()), Bitboard::rank(Rank::Sixth));
+        assert_eq!(Bitboard::promotion_rank(Color::Black.index()), Bitboard::rank(Rank::First));
+        assert_eq!(Bitboard::start_rank(Color::Black.index()), Bitboard::rank(Rank::Seventh));
+        assert_eq!(Bitboard::en_passant_rank(Color::Black.index()), Bitboard::rank(Rank::Third));
+    }
+
+    #[test]
+    fn adjacent_files() {
+        use crate::bits::{File, ADJACENT_FILES};
+        assert_eq!(Bitboard::adjacent_files(File::A), Bitboard::file(File::A) | Bitboard::file(File::B));
+        assert_eq!(
+            Bitboard::adjacent_files(File::D),
+            Bitboard::file(File::C) | Bitboard::file(File::D) | Bitboard::file(File::E),
+        );
+        assert_eq!(Bitboard::adjacent_files(File::H), Bitboard::file(File::G) | Bitboard::file(File::H));
+        assert!(ADJACENT_FILES[1..7].iter().all(|&m| Bitboard::new(m).count() == 24));
+    }
+
+    #[test]
+    fn passed_pawn_mask() {
+        use crate::bits::Square;
+        use crate::position::Color;
+        // c6-c8, d6-d8 and e6-e8
+        let d5 = Square::new(35);
+        let expected = [42, 43, 44, 50, 51, 52, 58, 59, 60]
+            .into_iter()
+            .fold(Bitboard::EMPTY, |b, s| b.with_square(Square::new(s)));
+        assert_eq!(Bitboard::passed_pawn_mask(Color::White.index(), d5), expected);
+        // c4-c1, d4-d1 and e4-e1
+        assert_eq!(Bitboard::passed_pawn_mask(Color::Black.index(), d5).count(), 12);
+        // Nothing is in front of a pawn on the last rank
+        assert_eq!(Bitboard::passed_pawn_mask(Color::White.index(), Square::new(60)), Bitboard::EMPTY);
+        assert_eq!(Bitboard::passed_pawn_mask(Color::Black.index(), Square::new(4)), Bitboard::EMPTY);
+        // A white a2 pawn's mask is the a- and b-files from the third rank up
+        assert_eq!(Bitboard::passed_pawn_mask(Color::White.index(), Square::new(8)).count(), 12);
+    }
+}
@@ -0,0 +1,330 @@
+//! Evaluating chess positions
+//!
+//! Scores are in centipawns, from white's point of view.
+
+pub mod see;
+pub mod threats;
+mod tests;
+
+use crate::{
+    bits::Bitboard,
+    movegen::{
+        attacks,
+        king_attacks,
+    },
+    position::{
+        Color,
+        Role,
+        Piece,
+        Position,
+        board::Board,
+    },
+};
+
+/// The value of each role in the middlegame and endgame, indexed by
+/// [role](Role::index())
+/// 
+/// The default values are [`Role::value_mg`] and [`Role::value_eg`], where
+/// the king is worth nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PieceValues {
+    /// The values in the middlegame
+    pub mg: [i32; 6],
+    /// The values in the endgame
+    pub eg: [i32; 6],
+}
+
+impl Default for PieceValues {
+    fn default() -> Self {
+        PieceValues {
+            mg: Role::ITER.map(Role::value_mg),
+            eg: Role::ITER.map(Role::value_eg),
+        }
+    }
+}
+
+impl PieceValues {
+    /// Gets the middlegame value of role `r`
+    #[inline]
+    pub fn mg(&self, r: Role) -> i32 {
+        self.mg[r.index()]
+    }
+
+    /// Gets the endgame value of role `r`
+    #[inline]
+    pub fn eg(&self, r: Role) -> i32 {
+        self.eg[r.index()]
+    }
+}
+
+/// The weight of each role in the [game phase](phase()), indexed by 
+/// [role](Role::index())
+pub const PHASE_WEIGHTS: [i32; 6] = [0, 1, 1, 2, 4, 0];
+
+/// The game phase of the starting position, where the score is entirely the
+/// middlegame score
+pub const MAX_PHASE: i32 = 24;
+
+/// The bonus for having bishops on both colors of squares
+pub const BISHOP_PAIR_BONUS: i32 = 50;
+
+/// The number of own pawns at which knights and bishops need no adjustment
+pub const IMBALANCE_PAWN_BASELINE: i32 = 5;
+
+/// The bonus for each knight, per own pawn above the baseline (knights like
+/// closed positions)
+pub const KNIGHT_PAWN_ADJUSTMENT: i32 = 6;
+
+/// The penalty for each bishop, per own pawn above the baseline (bishops like
+/// open positions)
+pub const BISHOP_PAWN_ADJUSTMENT: i32 = 3;
+
+/// The bonus per square a piece can move to, indexed by [role](Role::index())
+/// 
+/// Pawns and kings are left out, and the long-range pieces are weighted less,
+/// as they naturally reach more squares
+pub const MOBILITY_WEIGHTS: [i32; 6] = [0, 4, 3, 2, 1, 0];
+
+/// The [scale factor](scale_factor()) of the endgame score when nothing 
+/// makes the position drawish
+pub const SCALE_NORMAL: i32 = 64;
+
+/// The scale factor of an ending where each side has just a bishop, on 
+/// opposite colors, and pawns
+pub const SCALE_OPPOSITE_BISHOPS: i32 = 16;
+
+/// The scale factor of an ending with opposite-colored bishops and other 
+/// pieces
+pub const SCALE_OPPOSITE_BISHOPS_WITH_PIECES: i32 = 40;
+
+/// The scale factor when the stronger side has no pawns and at most a minor
+/// piece more than its opponent
+pub const SCALE_NO_PAWNS: i32 = 16;
+
+/// Gets the material of the pieces of color `c` besides its king and pawns,
+/// valued by their middlegame `values`
+fn non_pawn_material(board: &Board, c: Color, values: &PieceValues) -> i32 {
+    [Role::Knight, Role::Bishop, Role::Rook, Role::Queen]
+        .into_iter()
+        .map(|r| values.mg(r) * board.piece(Piece(c, r)).count() as i32)
+        .sum()
+}
+
+/// Gets the factor, out of [`SCALE_NORMAL`], that the endgame score of 
+/// `board` is scaled by for how hard it is for the side ahead to win, where
+/// `eg` is the endgame material from white's point of view
+/// 
+/// Endings with opposite-colored bishops are scaled down, as are endings 
+/// where the side ahead has no pawns and not enough pieces to force mate.
+fn endgame_scale(board: &Board, eg: i32, values: &PieceValues) -> i32 {
+    let strong = if eg >= 0 { Color::White } else { Color::Black };
+    let strong_npm = non_pawn_material(board, strong, values);
+    let weak_npm = non_pawn_material(board, !strong, values);
+
+    if board.piece(Piece(strong, Role::Pawn)).is_empty() && 
+       strong_npm - weak_npm <= values.mg(Role::Bishop) {
+        return if strong_npm < values.mg(Role::Rook) { 0 } else { SCALE_NO_PAWNS }
+    }
+
+    let white_bishops = board.piece(Piece(Color::White, Role::Bishop));
+    let black_bishops = board.piece(Piece(Color::Black, Role::Bishop));
+    let opposite_bishops = white_bishops.count() == 1 && 
+        black_bishops.count() == 1 &&
+        (white_bishops & Bitboard::LIGHT_SQUARES).is_any() != 
+        (black_bishops & Bitboard::LIGHT_SQUARES).is_any();
+    if opposite_bishops {
+        let only_bishops = strong_npm == values.mg(Role::Bishop) && 
+            weak_npm == values.mg(Role::Bishop);
+        return if only_bishops { 
+            SCALE_OPPOSITE_BISHOPS 
+        } else { 
+            SCALE_OPPOSITE_BISHOPS_WITH_PIECES 
+        }
+    }
+    SCALE_NORMAL
+}
+
+/// Gets the factor, from 0 to [`SCALE_NORMAL`], that the endgame score of 
+/// `board` is scaled by in the [material](material()), with the pieces 
+/// valued by `values`
+/// 
+/// This is lower in drawish endings: with opposite-colored bishops, or where
+/// the side ahead has no pawns and at most a minor piece more than its 
+/// opponent (0 if it then can't force mate at all).
+pub fn scale_factor(board: &Board, values: &PieceValues) -> i32 {
+    let eg: i32 = Role::ITER_PIECE
+        .into_iter()
+        .map(|r| {
+            values.eg(r) * (board.piece(Piece(Color::White, r)).count() as i32 -
+                            board.piece(Piece(Color::Black, r)).count() as i32)
+        })
+        .sum();
+    endgame_scale(board, eg, values)
+}
+
+/// Gets the material imbalance terms for the pieces of color `c`
+fn side_imbalance(board: &Board, c: Color) -> i32 {
+    let bishops = board.piece(Piece(c, Role::Bishop));
+    let knights = board.piece(Piece(c, Role::Knight)).count() as i32;
+    let pawns = board.piece(Piece(c, Role::Pawn)).count() as i32;
+
+    let mut score = 0;
+    if (bishops & Bitboard::LIGHT_SQUARES).is_any() &&
+       (bishops & Bitboard::DARK_SQUARES).is_any() {
+        score += BISHOP_PAIR_BONUS;
+    }
+    let extra_pawns = pawns - IMBALANCE_PAWN_BASELINE;
+    score += knights * KNIGHT_PAWN_ADJUSTMENT * extra_pawns;
+    score -= bishops.count() as i32 * BISHOP_PAWN_ADJUSTMENT * extra_pawns;
+    score
+}
+
+/// Gets the material imbalance score of `board`, which adjusts the raw
+/// material for the bishop pair and for how knights and bishops fare with the
+/// number of pawns
+pub fn material_imbalance(board: &Board) -> i32 {
+    side_imbalance(board, Color::White) - side_imbalance(board, Color::Black)
+}
+
+/// Gets the mobility score of the pieces of color `c` on `board`, which is 
+/// the number of squares each can move to (ignoring pins and checks), 
+/// weighted by [role](MOBILITY_WEIGHTS)
+pub fn mobility(board: &Board, c: Color) -> i32 {
+    let occ = board.all();
+    let own = board.color(c);
+    [Role::Knight, Role::Bishop, Role::Rook, Role::Queen]
+        .into_iter()
+        .map(|r| {
+            let weight = MOBILITY_WEIGHTS[r.index()];
+            board.piece(Piece(c, r))
+                .into_iter()
+                .map(|s| weight * (attacks(r, c, s, occ) & !own).count() as i32)
+                .sum::<i32>()
+        })
+        .sum()
+}
+
+/// Shifts the squares of `b` one rank towards the side of color `c`'s
+/// opponent, dropping any that fall off the board
+fn forward(b: Bitboard, c: Color) -> Bitboard {
+    match c {
+        Color::White => Bitboard::new(u64::from(b) << 8),
+        Color::Black => Bitboard::new(u64::from(b) >> 8),
+    }
+}
+
+/// Gets the pawns of color `c` shielding its king, which are those on the
+/// square directly in front of the king and the two diagonally in front of it
+pub fn pawn_shield(board: &Board, c: Color) -> Bitboard {
+    let king = board.king_square(c);
+    let ahead = forward(Bitboard::rank(king.rank()), c);
+    king_attacks(king) & ahead & board.piece(Piece(c, Role::Pawn))
+}
+
+/// Gets the zone around the king of color `c` that attackers are counted in
+/// for king safety, which is the king's square and the squares around it,
+/// extended one rank further forward
+pub fn king_zone(board: &Board, c: Color) -> Bitboard {
+    let king = board.king_square(c);
+    let ring = king_attacks(king).with_square(king);
+    ring | forward(ring, c)
+}
+
+/// Gets the game phase of `board`, from [`MAX_PHASE`] with all the pieces on
+/// the board down to 0 with only pawns and kings
+pub fn phase(board: &Board) -> i32 {
+    let phase: i32 = [Role::Knight, Role::Bishop, Role::Rook, Role::Queen]
+        .into_iter()
+        .map(|r| PHASE_WEIGHTS[r.index()] * board.role(r).count() as i32)
+        .sum();
+    phase.min(MAX_PHASE)
+}
+
+/// Gets the material of `board` valued by `values`, blending the middlegame 
+/// and endgame values by the [game phase](phase())
+/// 
+/// The endgame value is first scaled by the [scale factor](scale_factor()).
+pub fn material(board: &Board, values: &PieceValues) -> i32 {
+    let (mut mg, mut eg) = (0, 0);
+    for r in Role::ITER_PIECE {
+        let count = board.piece(Piece(Color::White, r)).count() as i32 -
+                    board.piece(Piece(Color::Black, r)).count() as i32;
+        mg += count * values.mg(r);
+        eg += count * values.eg(r);
+    }
+    let eg = eg * endgame_scale(board, eg, values) / SCALE_NORMAL;
+    let phase = phase(board);
+    (mg * phase + eg * (MAX_PHASE - phase)) / MAX_PHASE
+}
+
+/// Gets the score of `board`, summing the [material](material()) valued by
+/// `values`, the [material imbalance](material_imbalance()) and the 
+/// [mobility](mobility())
+pub fn evaluate(board: &Board, values: &PieceValues) -> i32 {
+    material(board, values) +
+    material_imbalance(board) +
+    mobility(board, Color::White) - mobility(board, Color::Black)
+}
+
+/// # Evaluation methods
+impl Position {
+    /// Gets the [score](evaluate()) of the position, with the pieces valued
+    /// by `values`
+    pub fn evaluate(&self, values: &PieceValues) -> i32 {
+        evaluate(&self.board, values)
+    }
+
+    /// Gets the [scale factor](scale_factor()) of the position's endgame
+    /// score, with the pieces valued by the default [`PieceValues`]
+    pub fn scale_factor(&self) -> i32 {
+        scale_factor(&self.board, &PieceValues::default())
+    }
+
+    /// Gets the [game phase](phase()) of the position, which tapers the
+    /// score from the middlegame to the endgame
+    pub fn game_phase(&self) -> i32 {
+        phase(&self.board)
+    }
+
+    /// Gets the material of each color, indexed by [color](Color::index()),
+    /// with each piece valued by its [role](Role::value())
+    /// 
+    /// This is counted from the board, so it always accounts for captures 
+    /// (including en passant) and promotions.
+    pub fn material_count(&self) -> [i32; 2] {
+        [Color::White, Color::Black].map(|c| {
+            Role::ITER_PIECE
+                .into_iter()
+                .map(|r| r.value() * self.board.piece(Piece(c, r)).count() as i32)
+                .sum()
+        })
+    }
+
+    /// Gets how much more [material](Self::material_count()) the side to 
+    /// move has than its opponent
+    pub fn material_balance(&self) -> i32 {
+        let count = self.material_count();
+        count[self.turn.index()] - count[(!self.turn).index()]
+    }
+
+    /// Gets the [pawns shielding](pawn_shield()) the king of color `c`
+    pub fn pawn_shield(&self, c: Color) -> Bitboard {
+        pawn_shield(&self.board, c)
+    }
+
+    /// Gets the [zone](king_zone()) around the king of color `c`
+    pub fn king_zone(&self, c: Color) -> Bitboard {
+        king_zone(&self.board, c)
+    }
+
+    /// Gets the [mobility](mobility()) score of the pieces of color `c`
+    pub fn mobility(&self, c: Color) -> i32 {
+        mobility(&self.board, c)
+    }
+
+    /// Gets the [material imbalance](material_imbalance()) score of the
+    /// position
+    pub fn material_imbalance(&self) -> i32 {
+        material_imbalance(&self.board)
+    }
+}
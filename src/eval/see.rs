@@ -0,0 +1,162 @@
+//! [Static exchange evaluation](https://www.chessprogramming.org/Static_Exchange_Evaluation)
+//! of the captures on a square
+
+use crate::{
+    bits::{
+        Bitboard,
+        Coords,
+        Square,
+    },
+    eval::PieceValues,
+    movegen::Move,
+    position::{
+        board::Board,
+        Color,
+        Piece,
+        Position,
+        Role,
+    },
+};
+
+/// # Evaluation methods
+impl Position {
+    /// Gets the material the side to move wins with the (legal) move `m`, if
+    /// both sides then keep recapturing on its destination with their least
+    /// valuable piece, each stopping once that no longer gains anything
+    ///
+    /// Pieces are valued by their middlegame `values`. Pins are ignored, and
+    /// castling is always worth 0.
+    pub fn see(&self, m: Move, values: &PieceValues) -> i32 {
+        let (from, to, promotion) = match m {
+            Move::Castle { .. } => return 0,
+            Move::PawnMove { from, to, promotion, .. } => (from, to, promotion),
+            Move::DoublePawnPush { from, to } |
+            Move::Normal { from, to, .. } => (from, to, None),
+        };
+
+        let mut occ = self.board.all().without_square(from);
+        if let Move::PawnMove { en_passant: true, .. } = m {
+            occ.remove(Square::from(Coords(to.file(), from.rank())));
+        }
+
+        let mut gain = m.capture().map_or(0, |r| values.mg(r));
+        if let Some(r) = promotion {
+            gain += values.mg(r) - values.mg(Role::Pawn);
+        }
+        let on_square = promotion.unwrap_or(m.role());
+        self.swap(to, occ, on_square, gain, !self.turn, values)
+    }
+
+    /// Gets the material the side to move wins by capturing the enemy piece on
+    /// `s` with its least valuable attacker, if both sides then keep 
+    /// recapturing as with [`see`](Self::see()), or 0 if it has no capture 
+    /// worth making
+    ///
+    /// This is how much the piece on `s` is hanging by. It's 0 if `s` isn't
+    /// occupied by an enemy piece.
+    pub fn exchange_value_on(&self, s: Square, values: &PieceValues) -> i32 {
+        let Some(Piece(c, r)) = self.board.get(s) else { return 0 };
+        if c == self.turn {
+            return 0
+        }
+        // Starting from the opponent's point of view, having gained nothing,
+        // lets the side to move decline to capture
+        -self.swap(s, self.board.all(), r, 0, self.turn, values)
+    }
+
+    /// Returns `true` if the side to move isn't in check and has no capture
+    /// that wins material by [SEE](Self::see()), with the pieces valued by 
+    /// the default [`PieceValues`]
+    /// 
+    /// A search can stop extending quiet positions, or stand pat in them.
+    pub fn is_quiet(&self) -> bool {
+        let values = PieceValues::default();
+        !self.is_in_check() &&
+        self.generate()
+            .into_iter()
+            .filter(|m| m.is_capture())
+            .all(|m| self.see(m, &values) <= 0)
+    }
+
+    /// Gets the legal captures that win material by [SEE](Self::see()), with
+    /// how much each wins, from the most to the least (with the pieces 
+    /// valued by the default [`PieceValues`])
+    /// 
+    /// These are the candidate tactical shots in the position.
+    pub fn winning_captures(&self) -> Vec<(Move, i32)> {
+        let values = PieceValues::default();
+        let mut captures: Vec<(Move, i32)> = self.generate()
+            .into_iter()
+            .filter(|m| m.is_capture())
+            .map(|m| (m, self.see(m, &values)))
+            .filter(|&(_, see)| see > 0)
+            .collect();
+        captures.sort_by_key(|&(_, see)| std::cmp::Reverse(see));
+        captures
+    }
+
+    /// Plays out the captures on `to`, where the last capture gained `gain` 
+    /// and left a role `on_square` piece there, with `side` to recapture
+    ///
+    /// Returns the gain of the last capture, once each side has stopped 
+    /// recapturing when that's better for it
+    fn swap(
+        &self,
+        to: Square,
+        mut occ: Bitboard,
+        mut on_square: Role,
+        gain: i32,
+        mut side: Color,
+        values: &PieceValues,
+    ) -> i32 {
+        let mut gains = vec![gain];
+        loop {
+            let attackers = self.attackers_to(to, side, occ);
+            let Some((s, r)) = least_valuable_attacker(&self.board, attackers, side) else {
+                break
+            };
+            // The king can only recapture if the square isn't defended
+            let after = occ.without_square(s);
+            if r == Role::King && self.attackers_to(to, !side, after).is_any() {
+                break
+            }
+            gains.push(values.mg(on_square) - gains[gains.len() - 1]);
+            on_square = r;
+            occ = after;
+            side = !side;
+        }
+
+        // Either side may stop recapturing if that's better for it
+        while gains.len() > 1 {
+            let last = gains.pop().unwrap();
+            let prev = gains.last_mut().unwrap();
+            *prev = -(-*prev).max(last);
+        }
+        gains[0]
+    }
+}
+
+/// Gets the least valuable color `by` piece in `attackers`, with its square,
+/// or [`None`] if it has none
+///
+/// Roles are tried from pawn up to king, and the lowest square of a role is
+/// taken first, so that [`see`](Position::see()) and
+/// [`exchange_value_on`](Position::exchange_value_on()) always recapture in
+/// the same order.
+pub fn least_valuable_attacker(
+    board: &Board,
+    attackers: Bitboard,
+    by: Color,
+) -> Option<(Square, Role)> {
+    let attackers = attackers & board.color(by);
+    Role::ITER_PIECE
+        .into_iter()
+        .find_map(|r| {
+            (attackers & board.role(r)).smallest_square().map(|s| (s, r))
+        })
+        .or_else(|| {
+            (attackers & board.king_bitboard(by))
+                .smallest_square()
+                .map(|s| (s, Role::King))
+        })
+}
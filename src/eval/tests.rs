@@ -0,0 +1,395 @@
+#[cfg(test)]
+mod imbalance_tests {
+    use crate::eval::*;
+    use crate::position::Position;
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    #[test]
+    fn symmetric() {
+        assert_eq!(Position::default().material_imbalance(), 0);
+    }
+
+    #[test]
+    fn bishop_pair() {
+        // White has bishops on c1 and f1, black has a bishop and a knight
+        let p = parse("2b1kn2/pppppppp/8/8/8/8/PPPPPPPP/2B1KB2 w - - 0 1");
+        assert!(p.material_imbalance() > 0);
+        let white = BISHOP_PAIR_BONUS - 2 * 3 * BISHOP_PAWN_ADJUSTMENT;
+        let black = 3 * KNIGHT_PAWN_ADJUSTMENT - 3 * BISHOP_PAWN_ADJUSTMENT;
+        assert_eq!(material_imbalance(&p.board), white - black);
+
+        // Two bishops on the same color (a1 and c3) don't make a pair
+        let p = parse("2b1kb2/pppppppp/8/8/8/2B5/PPPPPPPP/B3K3 w - - 0 1");
+        assert_eq!(p.material_imbalance(), -BISHOP_PAIR_BONUS);
+    }
+
+    #[test]
+    fn knights_and_pawns() {
+        // With many pawns, a knight is better than a bishop
+        let p = parse("4kb2/pppppppp/8/8/8/8/PPPPPPPP/4KN2 w - - 0 1");
+        assert_eq!(p.material_imbalance(), 3 * (KNIGHT_PAWN_ADJUSTMENT + BISHOP_PAWN_ADJUSTMENT));
+        // And with few pawns, worse
+        let p = parse("4kb2/pp6/8/8/8/8/PP6/4KN2 w - - 0 1");
+        assert!(p.material_imbalance() < 0);
+    }
+}
+
+#[cfg(test)]
+mod mobility_tests {
+    use crate::eval::*;
+    use crate::position::{Color, Position};
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    #[test]
+    fn symmetric() {
+        let p = Position::default();
+        assert_eq!(p.mobility(Color::White), p.mobility(Color::Black));
+        // Only the knights can move, to 2 squares each
+        assert_eq!(p.mobility(Color::White), 2 * 2 * MOBILITY_WEIGHTS[1]);
+    }
+
+    #[test]
+    fn pieces() {
+        // A knight on a1 reaches 2 squares, and a rook on h1 reaches 6: up to
+        // the black pawn on h5, and across to the white king on e1
+        let p = parse("4k3/8/8/7p/8/8/8/N3K2R w - - 0 1");
+        let expected = 2 * MOBILITY_WEIGHTS[1] + (4 + 2) * MOBILITY_WEIGHTS[3];
+        assert_eq!(p.mobility(Color::White), expected);
+        // The black pawn doesn't count
+        assert_eq!(p.mobility(Color::Black), 0);
+    }
+}
+
+#[cfg(test)]
+mod see_tests {
+    use crate::bits::Square;
+    use crate::eval::*;
+    use crate::movegen::Move;
+    use crate::position::{Position, Role};
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    fn capture(role: Role, from: u32, to: u32, captured: Role) -> Move {
+        Move::Normal { role, from: Square::new(from), to: Square::new(to), capture: Some(captured) }
+    }
+
+    #[test]
+    fn exchanges() {
+        let values = PieceValues::default();
+        // Rxe5, with the pawn undefended
+        let p = parse("4k3/8/8/4p3/8/8/8/4RK2 w - - 0 1");
+        assert_eq!(p.see(capture(Role::Rook, 4, 36, Role::Pawn), &values), values.mg(Role::Pawn));
+        // Nxe5 dxe5
+        let p = parse("4k3/8/3p4/4p3/8/5N2/8/4K3 w - - 0 1");
+        let nxe5 = capture(Role::Knight, 21, 36, Role::Pawn);
+        assert_eq!(p.see(nxe5, &values), values.mg(Role::Pawn) - values.mg(Role::Knight));
+        // Rxe5 Rxe5 Rxe5, with the second white rook behind the first
+        let p = parse("4r1k1/8/8/4p3/8/8/4R3/4RK2 w - - 0 1");
+        let rxe5 = capture(Role::Rook, 12, 36, Role::Pawn);
+        assert_eq!(p.see(rxe5, &values), values.mg(Role::Pawn));
+        // Black can't recapture with the king, as e5 is defended
+        let p = parse("8/8/3k4/4p3/8/8/7B/4RK2 w - - 0 1");
+        assert_eq!(p.see(capture(Role::Rook, 4, 36, Role::Pawn), &values), values.mg(Role::Pawn));
+        // Quiet moves win nothing
+        assert_eq!(p.see(Move::Normal { role: Role::Rook, from: Square::new(4), to: Square::new(3), capture: None }, &values), 0);
+    }
+
+    #[test]
+    fn exchange_value_on() {
+        let values = PieceValues::default();
+        // The knight on e5 is hanging to the rook
+        let p = parse("4k3/8/8/4n3/8/8/8/4RK2 w - - 0 1");
+        assert_eq!(p.exchange_value_on(Square::new(36), &values), values.mg(Role::Knight));
+        // Defended by a pawn, it isn't worth a rook
+        let p = parse("4k3/8/3p4/4n3/8/8/8/4RK2 w - - 0 1");
+        assert_eq!(p.exchange_value_on(Square::new(36), &values), 0);
+        // But it's worth a pawn, which is lost to the recapture
+        let p = parse("4k3/8/3p4/4n3/3P4/8/8/5K2 w - - 0 1");
+        let expected = values.mg(Role::Knight) - values.mg(Role::Pawn);
+        assert_eq!(p.exchange_value_on(Square::new(36), &values), expected);
+        // Empty squares and the side to move's own pieces aren't hanging
+        assert_eq!(p.exchange_value_on(Square::new(35), &values), 0);
+        assert_eq!(p.exchange_value_on(Square::new(27), &values), 0);
+    }
+
+    #[test]
+    fn least_valuable_attacker() {
+        use crate::eval::see::least_valuable_attacker;
+        use crate::position::Color;
+        // A pawn and a rook both attack e5, with a knight of either color
+        // elsewhere
+        let p = parse("4k3/8/5n2/4n3/3P4/5N2/8/4RK2 w - - 0 1");
+        let e5 = Square::new(36);
+        let white = p.attackers(e5, Color::White);
+        assert_eq!(least_valuable_attacker(&p.board, white, Color::White), Some((Square::new(27), Role::Pawn)));
+        // Without the pawn, the knight comes before the rook
+        let no_pawn = white.without_square(Square::new(27));
+        assert_eq!(least_valuable_attacker(&p.board, no_pawn, Color::White), Some((Square::new(21), Role::Knight)));
+        // Only the given color's pieces count
+        assert_eq!(least_valuable_attacker(&p.board, white, Color::Black), None);
+        let kings = p.board.king_bitboard(Color::White);
+        assert_eq!(least_valuable_attacker(&p.board, kings, Color::White), Some((Square::new(5), Role::King)));
+    }
+
+    #[test]
+    fn custom_values() {
+        // Nxe5 dxe5 trades a knight for a pawn, which is less of a loss when
+        // pawns are worth more
+        let p = parse("4k3/8/3p4/4p3/8/5N2/8/4K3 w - - 0 1");
+        let nxe5 = capture(Role::Knight, 21, 36, Role::Pawn);
+        let values = PieceValues::default();
+        let mut doubled = values;
+        doubled.mg[Role::Pawn.index()] *= 2;
+        assert_eq!(p.see(nxe5, &values), 100 - 320);
+        assert_eq!(p.see(nxe5, &doubled), 200 - 320);
+    }
+
+    #[test]
+    fn is_quiet() {
+        assert!(Position::default().is_quiet());
+        // The queen on d5 is hanging to the rook on d1
+        assert!(!parse("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1").is_quiet());
+        // The pawns are locked, with nothing to capture
+        assert!(parse("4k3/8/8/3p4/3P4/8/8/4K3 w - - 0 1").is_quiet());
+        // Trading pawns on d5 gains nothing
+        assert!(parse("4k3/8/4p3/3p4/4P3/8/8/4K3 w - - 0 1").is_quiet());
+        // In check
+        assert!(!parse("4k3/8/8/8/8/8/8/r3K3 w - - 0 1").is_quiet());
+    }
+
+    #[test]
+    fn winning_captures() {
+        // Nxb5 wins the undefended rook, while Rxa7 (Bxa7) and Qxd7+ (Kxd7)
+        // lose material
+        let p = parse("1b2k3/p2p4/8/1r6/8/2N5/8/R2QK3 w - - 0 1");
+        assert_eq!(p.generate().into_iter().filter(|m| m.is_capture()).count(), 3);
+        let nxb5 = capture(Role::Knight, 18, 33, Role::Rook);
+        assert_eq!(p.winning_captures(), vec![(nxb5, Role::Rook.value())]);
+        // Sorted from the most to the least won
+        let p = parse("4k3/8/8/3q4/8/8/5n2/3RK3 w - - 0 1");
+        let values: Vec<i32> = p.winning_captures().into_iter().map(|(_, see)| see).collect();
+        assert_eq!(values, [Role::Queen.value(), Role::Knight.value()]);
+        assert!(Position::default().winning_captures().is_empty());
+    }
+}
+
+#[cfg(test)]
+mod evaluate_tests {
+    use crate::eval::*;
+    use crate::position::{Position, Role};
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    #[test]
+    fn symmetric() {
+        let p = Position::default();
+        assert_eq!(phase(&p.board), MAX_PHASE);
+        assert_eq!(p.evaluate(&PieceValues::default()), 0);
+    }
+
+    #[test]
+    fn material_is_tapered() {
+        let values = PieceValues::default();
+        // With only pawns and kings left, the endgame values apply
+        let p = parse("4k3/8/8/8/8/8/PP6/4K3 w - - 0 1");
+        assert_eq!(phase(&p.board), 0);
+        assert_eq!(material(&p.board, &values), 2 * values.eg(Role::Pawn));
+        // White is a queen up, and at half phase the values are averaged
+        let p = parse("1r2k1r1/8/8/8/8/8/8/QR2KR2 w - - 0 1");
+        assert_eq!(phase(&p.board), 12);
+        let queen = (values.mg(Role::Queen) + values.eg(Role::Queen)) / 2;
+        assert_eq!(material(&p.board, &values), queen);
+    }
+
+    #[test]
+    fn role_values() {
+        let values = PieceValues::default();
+        assert_eq!(values.mg(Role::Knight), Role::Knight.value_mg());
+        assert_eq!(values.eg(Role::Rook), Role::Rook.value_eg());
+        assert_eq!(Role::Queen.value(), Role::Queen.value_mg());
+    }
+
+    #[test]
+    fn knight_is_tapered() {
+        let values = PieceValues::default();
+        // White is a knight up, with the queens on (and a pawn each, so that
+        // the endings aren't scaled down as drawish)
+        let queens = parse("3qk3/4p3/8/8/8/8/4P3/3QKN2 w - - 0 1");
+        assert_eq!(queens.game_phase(), 9);
+        // And with just minors
+        let minors = parse("4kb2/4p3/8/8/8/8/4P3/2B1KN2 w - - 0 1");
+        assert_eq!(minors.game_phase(), 3);
+
+        let knight = |phase: i32| {
+            (Role::Knight.value_mg() * phase + Role::Knight.value_eg() * (MAX_PHASE - phase)) / MAX_PHASE
+        };
+        assert_eq!(material(&queens.board, &values), knight(9));
+        assert_eq!(material(&minors.board, &values), knight(3));
+        assert_ne!(knight(9), knight(3));
+    }
+}
+
+#[cfg(test)]
+mod threats_tests {
+    use crate::bits::{Bitboard, Square};
+    use crate::position::Position;
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    #[test]
+    fn tactical() {
+        // The knight on f4 forks the undefended rook on h5 and the pawn on e6,
+        // which f7 defends, while the bishop on b4 pins the pawn on d2
+        let p = parse("4k3/5p2/4p3/7r/1b3N2/8/3P4/4K3 w - - 0 1");
+        let threats = p.threats();
+        assert_eq!(threats.hanging, Bitboard::square(Square::new(39)));
+        assert_eq!(threats.pinned, Bitboard::square(Square::new(11)));
+        assert!(threats.checkers.is_empty());
+
+        // With black to move, nothing is attacking the knight, and the king
+        // defends the pawn on d2
+        let p = parse("4k3/5p2/4p3/7r/1b3N2/8/3P4/4K3 b - - 0 1");
+        assert!(p.threats().hanging.is_empty());
+    }
+
+    #[test]
+    fn checkers() {
+        let p = parse("4k3/8/8/8/8/8/8/r3K3 w - - 0 1");
+        let threats = p.threats();
+        assert_eq!(threats.checkers, Bitboard::square(Square::new(0)));
+        assert!(threats.hanging.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod king_safety_tests {
+    use crate::bits::{Bitboard, Square};
+    use crate::position::{Color, Position};
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    fn squares(ss: &[u32]) -> Bitboard {
+        ss.iter().fold(Bitboard::EMPTY, |b, &s| b.with_square(Square::new(s)))
+    }
+
+    #[test]
+    fn pawn_shield() {
+        // Both kings castled kingside, with white's pawns on f2, g2 and h2,
+        // and black's h-pawn pushed to h6
+        let p = parse("r4rk1/ppp2pp1/7p/8/8/8/PPP2PPP/R4RK1 w - - 0 1");
+        assert_eq!(p.pawn_shield(Color::White), squares(&[13, 14, 15]));
+        assert_eq!(p.pawn_shield(Color::Black), squares(&[53, 54]));
+
+        // A king in the center, with only the e-pawn in front of it
+        let p = parse("4k3/8/8/8/8/8/3P1P2/4K3 w - - 0 1");
+        assert_eq!(p.pawn_shield(Color::White).count(), 2);
+        let p = parse("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+        assert_eq!(p.pawn_shield(Color::White), squares(&[12]));
+    }
+
+    #[test]
+    fn king_zone() {
+        let p = parse("6k1/8/8/8/8/8/8/6K1 w - - 0 1");
+        // f1 to h1, f2 to h2, and f3 to h3
+        assert_eq!(p.king_zone(Color::White), squares(&[5, 6, 7, 13, 14, 15, 21, 22, 23]));
+        // f8 to h8, f7 to h7, and f6 to h6
+        assert_eq!(p.king_zone(Color::Black), squares(&[61, 62, 63, 53, 54, 55, 45, 46, 47]));
+        // A king on the far edge has no rank further forward
+        let p = parse("6K1/8/8/8/8/8/8/6k1 w - - 0 1");
+        assert_eq!(p.king_zone(Color::White).count(), 6);
+    }
+}
+
+#[cfg(test)]
+mod material_tests {
+    use crate::position::{Position, Role};
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    #[test]
+    fn start_position() {
+        let p = Position::default();
+        let side = 8 * 100 + 2 * 320 + 2 * 330 + 2 * 500 + 900;
+        assert_eq!(p.material_count(), [side, side]);
+        assert_eq!(p.material_balance(), 0);
+    }
+
+    #[test]
+    fn winning_a_knight() {
+        let mut p = parse("4k3/8/8/3n4/8/2N5/8/4K3 w - - 0 1");
+        assert_eq!(p.material_balance(), 0);
+        p.push_uci("c3d5").unwrap();
+        // Black is to move, and down the knight
+        assert_eq!(p.material_balance(), -Role::Knight.value());
+        assert_eq!(p.material_count(), [320, 0]);
+    }
+
+    #[test]
+    fn promotion_and_en_passant() {
+        let mut p = parse("4k3/1P6/8/3pP3/8/8/8/4K3 w - d6 0 1");
+        assert_eq!(p.material_balance(), 100);
+        p.push_uci("e5d6").unwrap();
+        assert_eq!(p.material_count(), [200, 0]);
+        p.push_uci("e8f8").unwrap();
+        p.push_uci("b7b8q").unwrap();
+        assert_eq!(p.material_count(), [1000, 0]);
+        assert_eq!(p.material_balance(), -1000);
+    }
+}
+
+#[cfg(test)]
+mod scale_tests {
+    use crate::eval::*;
+    use crate::position::Position;
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    #[test]
+    fn opposite_bishops() {
+        let values = PieceValues::default();
+        // White is a pawn up, with its bishop on d3 and black's on d8 or c8
+        let opposite = parse("3b2k1/5ppp/8/8/8/3B4/P4PPP/6K1 w - - 0 1");
+        let same = parse("2b3k1/5ppp/8/8/8/3B4/P4PPP/6K1 w - - 0 1");
+        assert_eq!(opposite.scale_factor(), SCALE_OPPOSITE_BISHOPS);
+        assert_eq!(same.scale_factor(), SCALE_NORMAL);
+
+        let (opposite, same) = (opposite.evaluate(&values), same.evaluate(&values));
+        assert!(0 < opposite && 2 * opposite < same, "{} {}", opposite, same);
+
+        let with_rooks = parse("3br1k1/5ppp/8/8/8/3B4/P4PPP/4R1K1 w - - 0 1");
+        assert_eq!(with_rooks.scale_factor(), SCALE_OPPOSITE_BISHOPS_WITH_PIECES);
+    }
+
+    #[test]
+    fn no_pawns() {
+        assert_eq!(parse("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1").scale_factor(), 0);
+        assert_eq!(parse("4kb2/8/8/8/8/8/8/R3K3 w - - 0 1").scale_factor(), SCALE_NO_PAWNS);
+        assert_eq!(parse("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").scale_factor(), SCALE_NORMAL);
+        // The side ahead is the one checked for pawns
+        assert_eq!(parse("2b1k3/8/8/8/8/8/P7/4K3 w - - 0 1").scale_factor(), 0);
+        assert_eq!(parse("4k3/8/8/8/8/8/P7/2B1K3 w - - 0 1").scale_factor(), SCALE_NORMAL);
+    }
+
+    #[test]
+    fn start_position() {
+        assert_eq!(Position::default().scale_factor(), SCALE_NORMAL);
+    }
+}
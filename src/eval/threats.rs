@@ -0,0 +1,36 @@
+//! A summary of the threats in a position, for drawing them in a GUI
+
+use crate::{
+    bits::Bitboard,
+    eval::PieceValues,
+    position::Position,
+};
+
+/// The threats in a position, from the point of view of the side to move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Threats {
+    /// The enemy pieces giving check
+    pub checkers: Bitboard,
+    /// The enemy pieces that the side to move wins material by capturing 
+    /// (see [`exchange_value_on`](Position::exchange_value_on()))
+    pub hanging: Bitboard,
+    /// The pieces of the side to move that are pinned to their king
+    pub pinned: Bitboard,
+}
+
+/// # Evaluation methods
+impl Position {
+    /// Gets the [threats](Threats) in the position, with the pieces valued by
+    /// the default [`PieceValues`]
+    pub fn threats(&self) -> Threats {
+        let values = PieceValues::default();
+        let hanging = self.board.color(!self.turn)
+            .filter(|&s| self.exchange_value_on(s, &values) > 0)
+            .fold(Bitboard::EMPTY, Bitboard::with_square);
+        Threats {
+            checkers: self.checkers(),
+            hanging,
+            pinned: self.pinned(),
+        }
+    }
+}
@@ -3,4 +3,13 @@
 pub mod bits;
 pub mod position;
 pub mod movegen;
+pub mod eval;
+pub mod ordering;
+pub mod search;
 mod util;
+#[cfg(feature = "xboard")]
+pub mod xboard;
+#[cfg(feature = "log-positions")]
+pub mod logging;
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
@@ -0,0 +1,93 @@
+//! Compact one-line forms of moves and positions for logging, with macros
+//! that log them at the right level
+//!
+//! Moves are logged at the `trace` level, since move generation and search
+//! go through so many of them, and positions at the `debug` level.
+
+use std::fmt::Display;
+
+use crate::{
+    eval::PieceValues,
+    movegen::Move,
+    position::{
+        castling::CastlingSide,
+        Color,
+        Piece,
+        Position,
+    },
+};
+
+#[doc(hidden)]
+pub use log;
+
+impl Display for Move {
+    /// Writes the move in UCI notation, e.g. `e2e4` or `e7e8q`, except that
+    /// castling is written as `O-O` or `O-O-O`, since the move doesn't know
+    /// its king's square (see [`Position::uci`] for the exact UCI)
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (from, to, promotion) = match *self {
+            Move::Castle { castling_side: CastlingSide::Kingside } => return write!(f, "O-O"),
+            Move::Castle { castling_side: CastlingSide::Queenside } => return write!(f, "O-O-O"),
+            Move::PawnMove { from, to, promotion, .. } => (from, to, promotion),
+            Move::DoublePawnPush { from, to } |
+            Move::Normal { from, to, .. } => (from, to, None),
+        };
+        write!(f, "{}{}", from, to)?;
+        if let Some(r) = promotion {
+            write!(f, "{}", char::from(Piece(Color::Black, r)))?;
+        }
+        Ok(())
+    }
+}
+
+/// A one-line summary of a position: its FEN string and its
+/// [score](Position::evaluate()) with the default [`PieceValues`], from
+/// white's point of view, given by [`summary`](Position::summary())
+pub struct PositionSummary<'a>(&'a Position);
+
+impl Display for PositionSummary<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let eval = self.0.evaluate(&PieceValues::default());
+        write!(f, "{} (eval {})", self.0.fen(), eval)
+    }
+}
+
+impl Position {
+    /// Gets a one-line summary of the position for logging
+    pub fn summary(&self) -> PositionSummary<'_> {
+        PositionSummary(self)
+    }
+}
+
+/// Logs a [`Move`] at the `trace` level, optionally after a message
+///
+/// ```ignore
+/// log_move!(m);
+/// log_move!("best", m);
+/// ```
+#[macro_export]
+macro_rules! log_move {
+    ($m:expr) => {
+        $crate::logging::log::trace!("move {}", $m)
+    };
+    ($msg:expr, $m:expr) => {
+        $crate::logging::log::trace!("{}: move {}", $msg, $m)
+    };
+}
+
+/// Logs the [summary](crate::position::Position::summary()) of a position
+/// at the `debug` level, optionally after a message
+///
+/// ```ignore
+/// log_position!(p);
+/// log_position!("root", p);
+/// ```
+#[macro_export]
+macro_rules! log_position {
+    ($p:expr) => {
+        $crate::logging::log::debug!("{}", $p.summary())
+    };
+    ($msg:expr, $p:expr) => {
+        $crate::logging::log::debug!("{}: {}", $msg, $p.summary())
+    };
+}
@@ -30,9 +30,9 @@ fn main() {
     // let fen_str = "r1b2r2/p2p1pk1/1pp2bp1/q5N1/7P/P4Q2/4RPP1/1NB2K1R w - - 0 20";
     // let p = Position::from_fen_string(fen_str.to_string()).unwrap();
     // log::debug!("\n{}", p);
-    // log::debug!("{}", p.to_fen_string());
+    // log::debug!("{}", p.fen());
     // assert_eq!(p, p.flipped().flipped());
-    // assert_eq!(fen_str, p.to_fen_string());
+    // assert_eq!(fen_str, p.fen());
 
     // let bh = BuildZobristHasher::new();
 
@@ -48,10 +48,12 @@ fn main() {
 
     if cfg!(feature = "find-magics") {
         log::info!("Finding magic bitboards.");
-    } else if cfg!(feature = "magics") {
-        log::info!("Using magic bitboards for sliding move generation.");
+        print!("{}", patroclus::movegen::magic::dump_magics());
     } else {
-        log::info!("Using normal sliding move generation.");
+        log::info!(
+            "Using {:?} sliding move generation.",
+            patroclus::movegen::SlidingBackend::current()
+        );
     }
 
     log::info!("Goodbye, World!");
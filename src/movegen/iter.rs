@@ -0,0 +1,119 @@
+//! Generating the legal moves of a position lazily, in stages
+
+use crate::{
+    bits::Bitboard,
+    movegen::{
+        Move,
+        MoveList,
+    },
+    position::Position,
+};
+
+/// The kinds of moves, in the order they are generated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    Pawns,
+    Pieces,
+    Castles,
+    King,
+    Done,
+}
+
+/// An iterator over the legal moves of a position, which generates each kind
+/// of move only once the moves before it have been taken (see
+/// [`Position::moves`])
+/// 
+/// The moves come in the same order as from [`generate`](Position::generate()).
+#[derive(Debug, Clone)]
+pub struct MoveIter<'a> {
+    position: &'a Position,
+    checkers: Bitboard,
+    /// The squares that pieces other than the king may move to, or [`None`]
+    /// in double check
+    target: Option<Bitboard>,
+    pinned: Bitboard,
+    stage: Stage,
+    buffer: MoveList,
+    generated: usize,
+}
+
+impl<'a> MoveIter<'a> {
+    fn new(position: &'a Position) -> Self {
+        let checkers = position.checkers();
+        MoveIter {
+            position,
+            checkers,
+            target: position.target(checkers),
+            pinned: position.pinned(),
+            stage: Stage::Pawns,
+            buffer: MoveList::new(),
+            generated: 0,
+        }
+    }
+
+    /// Gets the number of moves generated so far, including those not yet
+    /// taken
+    pub fn generated(&self) -> usize {
+        self.generated
+    }
+
+    /// Generates the moves of the current stage into the buffer, and moves on
+    /// to the next stage
+    fn generate_stage(&mut self) {
+        let p = self.position;
+        self.stage = match self.stage {
+            Stage::Pawns => {
+                if let Some(target) = self.target {
+                    p.gen_pawns(&mut self.buffer, target, self.pinned);
+                }
+                Stage::Pieces
+            }
+            Stage::Pieces => {
+                if let Some(target) = self.target {
+                    p.gen_pieces(&mut self.buffer, target, self.pinned);
+                }
+                Stage::Castles
+            }
+            Stage::Castles => {
+                if self.checkers.is_empty() {
+                    p.gen_castles(&mut self.buffer);
+                }
+                Stage::King
+            }
+            Stage::King => {
+                p.gen_king(&mut self.buffer);
+                Stage::Done
+            }
+            Stage::Done => Stage::Done,
+        };
+        self.generated += self.buffer.len();
+        // Taken from the back, so reversed to keep the generated order
+        self.buffer.reverse();
+    }
+}
+
+impl Iterator for MoveIter<'_> {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Move> {
+        while self.buffer.is_empty() {
+            if self.stage == Stage::Done {
+                return None
+            }
+            self.generate_stage();
+        }
+        self.buffer.pop()
+    }
+}
+
+/// # Generate methods
+impl Position {
+    /// Gets an iterator over the legal moves in the position, which generates
+    /// them as they are taken
+    /// 
+    /// This is cheaper than [`generate`](Self::generate()) when not every 
+    /// move is needed, e.g. when a search cuts off early.
+    pub fn moves(&self) -> MoveIter<'_> {
+        MoveIter::new(self)
+    }
+}
@@ -1,81 +1,251 @@
-use rand::random;
+//! Finding [magic bitboards](https://www.chessprogramming.org/Magic_Bitboards)
+//! for sliding move generation
+//!
+//! The search is driven by a seeded RNG, so the same seed always finds the
+//! same magics and [`dump_magics`] output can be reviewed in version control.
+//!
+//! The moves for every square are packed into one shared table (the "fancy"
+//! layout), with each [`MagicEntry`] holding the offset of its square's moves.
+
+use std::sync::OnceLock;
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
 
 use crate::{
     bits::{
         Bitboard,
         Square,
+        Direction,
     },
-    position::{
-        Role,
-        board::Board
-    },
+    position::Role,
     movegen::{
-        util::*,
+        ray,
+        generate_rook_moves,
+        generate_bishop_moves,
     },
 };
 
+/// The seed used to find the magics, unless another is given
+pub const DEFAULT_SEED: u64 = 0x7061_7472_6F63_6C75;
 
-struct MagicEntry {
-    mask: Bitboard,
-    magic: u64,
-    index_bits: u8,
+/// The magic number and the mask of relevant blockers for a sliding piece on
+/// a square
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MagicEntry {
+    /// The squares whose occupancy affects the piece's moves
+    pub mask: Bitboard,
+    /// The magic multiplier
+    pub magic: u64,
+    /// The number of bits in a table index
+    pub index_bits: u8,
+    /// The index of the square's first move in the shared table
+    pub offset: usize,
 }
 
-struct TableFillError;
+impl MagicEntry {
+    /// Gets the number of moves in the square's part of the shared table
+    #[inline]
+    pub fn table_len(&self) -> usize {
+        1 << self.index_bits
+    }
+}
 
-// const ROOK_MAGICS: [MagicEntry; Square::COUNT] = todo!();
-// const BISHOP_MAGICS: [MagicEntry; Square::COUNT] = todo!();
+/// The rook and bishop magics for every square, with the moves they index
+pub struct MagicTables {
+    /// The rook magics, indexed by square
+    pub rook: Vec<MagicEntry>,
+    /// The bishop magics, indexed by square
+    pub bishop: Vec<MagicEntry>,
+    /// The moves of every rook and then every bishop magic, back to back
+    pub table: Vec<Bitboard>,
+}
 
-// const ROOK_MOVES: [&[Bitboard]; Square::COUNT] = todo!();
-// const BISHOP_MOVES: [&[Bitboard]; Square::COUNT] = todo!();
+impl MagicTables {
+    /// Finds the magics for every square with the RNG seeded by `seed`, and
+    /// packs their moves into one table
+    pub fn new(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut table = Vec::new();
+        let mut find = |r: Role| -> Vec<MagicEntry> {
+            Square::iter()
+                .map(|s| {
+                    let index_bits = find_mask(r, s).count() as u8;
+                    let (mut entry, moves) = find_magic(r, s, index_bits, &mut rng);
+                    entry.offset = table.len();
+                    table.extend(moves);
+                    entry
+                })
+                .collect()
+        };
+        let rook = find(Role::Rook);
+        let bishop = find(Role::Bishop);
+        MagicTables { rook, bishop, table }
+    }
 
-fn find_mask(r: Role, s: Square, board: Board) -> Bitboard {
-    debug_assert!(r != Role::Bishop || r != Role::Rook);
-    Bitboard::EMPTY
+    /// Gets the squares a rook on `s` attacks, given the occupied squares 
+    /// `occ`
+    #[inline]
+    pub fn rook_attacks(&self, s: Square, occ: Bitboard) -> Bitboard {
+        let entry = &self.rook[usize::from(s)];
+        self.table[entry.offset + magic_index(entry, occ)]
+    }
+
+    /// Gets the squares a bishop on `s` attacks, given the occupied squares
+    /// `occ`
+    #[inline]
+    pub fn bishop_attacks(&self, s: Square, occ: Bitboard) -> Bitboard {
+        let entry = &self.bishop[usize::from(s)];
+        self.table[entry.offset + magic_index(entry, occ)]
+    }
+}
+
+static SHARED_TABLES: OnceLock<MagicTables> = OnceLock::new();
+
+/// Gets the magic tables found with the [`DEFAULT_SEED`], finding them on first
+/// use
+pub fn shared_tables() -> &'static MagicTables {
+    SHARED_TABLES.get_or_init(|| MagicTables::new(DEFAULT_SEED))
+}
+
+/// Gets the squares a rook on `s` attacks, given the occupied squares `occ`,
+/// from the [shared tables](shared_tables())
+#[inline]
+pub fn rook_attacks(s: Square, occ: Bitboard) -> Bitboard {
+    shared_tables().rook_attacks(s, occ)
 }
 
+/// Gets the squares a bishop on `s` attacks, given the occupied squares `occ`,
+/// from the [shared tables](shared_tables())
+#[inline]
+pub fn bishop_attacks(s: Square, occ: Bitboard) -> Bitboard {
+    shared_tables().bishop_attacks(s, occ)
+}
+
+/// Gets the relevant blockers for a rook or bishop on `s`, which are the
+/// squares it slides across, except for the last square in each direction
+pub(crate) fn find_mask(r: Role, s: Square) -> Bitboard {
+    debug_assert!(r == Role::Bishop || r == Role::Rook);
+    let diagonal = r == Role::Bishop;
+    let mut mask = Bitboard::EMPTY;
+    for d in Direction::iter() {
+        let is_diagonal = matches!(
+            d,
+            Direction::Northeast |
+            Direction::Southeast |
+            Direction::Southwest |
+            Direction::Northwest
+        );
+        if is_diagonal != diagonal {
+            continue
+        }
+        let squares = ray(d, s);
+        let last = if d.is_positive() {
+            squares.largest_square()
+        } else {
+            squares.smallest_square()
+        };
+        if let Some(last) = last {
+            mask |= squares.without_square(last);
+        }
+    }
+    mask
+}
+
+/// Gets the moves of a rook or bishop on `s`, given the `blockers`
+pub(crate) fn slider_moves(r: Role, s: Square, blockers: Bitboard) -> Bitboard {
+    match r {
+        Role::Rook => generate_rook_moves(s, blockers),
+        Role::Bishop => generate_bishop_moves(s, blockers),
+        _ => unreachable!("{:?} is not a sliding piece", r),
+    }
+}
+
+/// Tries to fill in `table` with the moves for `entry`, where a slot belongs
+/// to the current attempt only if it's tagged with `attempt`
 fn try_make_table(
-    r: Role, 
-    s: Square, 
-    board: Board, 
-    entry: &MagicEntry
-) -> Result<Vec<Bitboard>, TableFillError> {
-    let mut table = vec!(Bitboard::EMPTY; 1 << entry.index_bits);
-    for blockers in entry.mask.subsets() {
-        let moves = todo!();
-        let b = &mut table[magic_index(entry, blockers)];
-        if b.is_empty() {
-            *b = moves;
-        } else if *b != moves {
-            return Err(TableFillError)
+    entry: &MagicEntry,
+    blockers_and_moves: &[(Bitboard, Bitboard)],
+    table: &mut [(u32, Bitboard)],
+    attempt: u32,
+) -> bool {
+    for &(blockers, moves) in blockers_and_moves {
+        let slot = &mut table[magic_index(entry, blockers)];
+        if slot.0 != attempt {
+            *slot = (attempt, moves);
+        } else if slot.1 != moves {
+            return false
         }
     }
-    Ok(table)
+    true
 }
 
-fn find_magic(
-    r: Role, 
-    s: Square, 
-    board: Board, 
-    index_bits: u8
+/// Finds a magic for a rook or bishop on `s` with tables of `index_bits` bits,
+/// drawing candidates from `rng`
+///
+/// Returns the magic with its filled in table of moves
+pub fn find_magic<R: Rng>(
+    r: Role,
+    s: Square,
+    index_bits: u8,
+    rng: &mut R,
 ) -> (MagicEntry, Vec<Bitboard>) {
-    let mask = find_mask(r, s, board);
-    loop {
-        let magic = random::<u64>() & random::<u64>() & random::<u64>();
-        let entry = MagicEntry { mask, magic, index_bits };
-        if let Ok(table) = try_make_table(r, s, board, &entry) {
-            return (entry, table)
+    let mask = find_mask(r, s);
+    // `subsets` skips the empty set, whose moves are never empty
+    let blockers_and_moves: Vec<(Bitboard, Bitboard)> = mask.subsets()
+        .into_iter()
+        .chain([Bitboard::EMPTY])
+        .map(|blockers| (blockers, slider_moves(r, s, blockers)))
+        .collect();
+    // The table is reused between attempts, rather than being cleared
+    let mut table = vec![(0, Bitboard::EMPTY); 1 << index_bits];
+    for attempt in 1.. {
+        // Magics with few bits set work best
+        let magic = rng.gen::<u64>() & rng.gen::<u64>() & rng.gen::<u64>();
+        let entry = MagicEntry { mask, magic, index_bits, offset: 0 };
+        if try_make_table(&entry, &blockers_and_moves, &mut table, attempt) {
+            let moves = table.into_iter()
+                .map(|(a, moves)| if a == attempt { moves } else { Bitboard::EMPTY })
+                .collect();
+            return (entry, moves)
         }
     }
+    unreachable!()
 }
 
+
 fn magic_index(entry: &MagicEntry, blockers: Bitboard) -> usize {
     let blockers = blockers & entry.mask;
     let hash = u64::from(blockers).wrapping_mul(entry.magic);
-    let index = (hash >> (64 - entry.index_bits)) as usize;
-    index
+    (hash >> (64 - entry.index_bits)) as usize
+}
+
+/// Finds the rook and bishop magics with the [`DEFAULT_SEED`], and formats them
+/// as Rust source
+pub fn dump_magics() -> String {
+    dump_magics_with_seed(DEFAULT_SEED)
 }
 
-pub fn dump_magics() {
+/// Finds the rook and bishop magics with the RNG seeded by `seed`, and formats
+/// them as Rust source
+pub fn dump_magics_with_seed(seed: u64) -> String {
+    use std::fmt::Write;
 
-}
\ No newline at end of file
+    let tables = MagicTables::new(seed);
+    let mut out = String::new();
+    for (name, entries) in [("ROOK", &tables.rook), ("BISHOP", &tables.bishop)] {
+        writeln!(out, "const {}_MAGICS: [MagicEntry; Square::COUNT] = [", name).unwrap();
+        for entry in entries {
+            writeln!(
+                out,
+                "    MagicEntry {{ mask: Bitboard::new(0x{:016X}), magic: 0x{:016X}, index_bits: {}, offset: {} }},",
+                u64::from(entry.mask),
+                entry.magic,
+                entry.index_bits,
+                entry.offset
+            ).unwrap();
+        }
+        writeln!(out, "];").unwrap();
+    }
+    writeln!(out, "const SHARED_TABLE_LEN: usize = {};", tables.table.len()).unwrap();
+    out
+}
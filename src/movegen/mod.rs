@@ -1,13 +1,28 @@
+//! Generating the legal moves of a chess position.
+
 pub mod magic;
+pub mod pext;
 pub mod util;
+pub mod perft;
+pub mod san;
+pub mod uci;
+pub mod iter;
+mod tests;
+
+use std::sync::OnceLock;
 
 use crate::{
     bits::{
         Square,
         Bitboard,
+        Coords,
+        File,
+        Direction,
     },
     position::{
+        Color,
         Role,
+        Piece,
         castling::CastlingSide,
         Position,
     },
@@ -16,18 +31,8 @@ use crate::{
     },
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Direction {
-    North = 0,
-    Northeast,
-    East,
-    Southeast,
-    South,
-    Southwest,
-    West,
-    Northwest
-}
-
+/// A chess move
+#[allow(missing_docs)]
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Move {
     PawnMove {
@@ -52,41 +57,979 @@ pub enum Move {
     }
 }
 
-impl Position {
-    pub fn generate(&self) -> Vec<Move> {
-        let movelist = Vec::new();
-        todo!();
-        movelist
+/// # Create methods
+/// 
+/// Captures and promotions are created with [`capturing`](Move::capturing())
+/// and [`promoting`](Move::promoting()), as [`capture`](Move::capture()) and
+/// [`promotion`](Move::promotion()) read those of a move.
+impl Move {
+    /// Creates a move of a role `role` piece from `from` to `to` that doesn't
+    /// capture, which for a pawn is a single push
+    #[inline]
+    pub const fn quiet(role: Role, from: Square, to: Square) -> Move {
+        match role {
+            Role::Pawn => Move::PawnMove { from, to, promotion: None, en_passant: false, capture: None },
+            _ => Move::Normal { role, from, to, capture: None },
+        }
+    }
+
+    /// Creates a move of a role `role` piece from `from` to `to` that captures
+    /// a role `victim` piece (use [`en_passant`](Self::en_passant()) for
+    /// capturing en passant)
+    #[inline]
+    pub const fn capturing(role: Role, from: Square, to: Square, victim: Role) -> Move {
+        match role {
+            Role::Pawn => Move::PawnMove { from, to, promotion: None, en_passant: false, capture: Some(victim) },
+            _ => Move::Normal { role, from, to, capture: Some(victim) },
+        }
+    }
+
+    /// Creates a non-pawn move between the squares with indices `from` and
+    /// `to`, for writing out expected moves in tests
+    #[cfg(test)]
+    pub(crate) fn normal(role: Role, from: u32, to: u32, capture: Option<Role>) -> Move {
+        Move::Normal { role, from: Square::new(from), to: Square::new(to), capture }
+    }
+
+    /// Creates a pawn move from `from` to `to` that promotes to `role`, 
+    /// capturing a role `capture` piece if any
+    #[inline]
+    pub const fn promoting(from: Square, to: Square, role: Role, capture: Option<Role>) -> Move {
+        Move::PawnMove { from, to, promotion: Some(role), en_passant: false, capture }
+    }
+
+    /// Creates a pawn's two-square push from `from` to `to`
+    #[inline]
+    pub const fn double_push(from: Square, to: Square) -> Move {
+        Move::DoublePawnPush { from, to }
+    }
+
+    /// Creates a pawn's capture en passant from `from` to the en passant 
+    /// square `to`
+    #[inline]
+    pub const fn en_passant(from: Square, to: Square) -> Move {
+        Move::PawnMove { from, to, promotion: None, en_passant: true, capture: Some(Role::Pawn) }
+    }
+
+    /// Creates castling on side `side`
+    #[inline]
+    pub const fn castle(side: CastlingSide) -> Move {
+        Move::Castle { castling_side: side }
+    }
+}
+
+impl Move {
+    /// Gets the role of the moving piece (the king, if castling)
+    #[inline]
+    pub fn role(self) -> Role {
+        match self {
+            Move::PawnMove { .. } | Move::DoublePawnPush { .. } => Role::Pawn,
+            Move::Normal { role, .. } => role,
+            Move::Castle { .. } => Role::King,
+        }
+    }
+
+    /// Gets the role of the captured piece, if any
+    #[inline]
+    pub fn capture(self) -> Option<Role> {
+        match self {
+            Move::PawnMove { capture, .. } | Move::Normal { capture, .. } => capture,
+            Move::DoublePawnPush { .. } | Move::Castle { .. } => None,
+        }
+    }
+
+    /// Gets the role that a pawn promotes to, if any
+    #[inline]
+    pub fn promotion(self) -> Option<Role> {
+        match self {
+            Move::PawnMove { promotion, .. } => promotion,
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the move captures a piece
+    #[inline]
+    pub fn is_capture(self) -> bool {
+        self.capture().is_some()
+    }
+
+    /// Returns `true` if the move is an en passant capture
+    #[inline]
+    pub fn is_en_passant(self) -> bool {
+        matches!(self, Move::PawnMove { en_passant: true, .. })
+    }
+
+    /// Returns `true` if the move is a promotion
+    #[inline]
+    pub fn is_promotion(self) -> bool {
+        self.promotion().is_some()
+    }
+
+    /// Returns `true` if the move is castling
+    #[inline]
+    pub fn is_castle(self) -> bool {
+        matches!(self, Move::Castle { .. })
+    }
+
+    /// Reduces the move, made in `pos`, to the squares it moves a piece 
+    /// between and its promotion, where castling moves the king to the g- or
+    /// c-file
+    pub fn canonical(self, pos: &Position) -> CanonicalMove {
+        match self {
+            Move::PawnMove { from, to, promotion, .. } => CanonicalMove { from, to, promotion },
+            Move::DoublePawnPush { from, to } |
+            Move::Normal { from, to, .. } => CanonicalMove { from, to, promotion: None },
+            Move::Castle { castling_side } => {
+                let from = pos.board.king_square(pos.turn);
+                let to_file = match castling_side {
+                    CastlingSide::Kingside => File::G,
+                    CastlingSide::Queenside => File::C,
+                };
+                let to = Square::from(Coords(to_file, from.rank()));
+                CanonicalMove { from, to, promotion: None }
+            }
+        }
+    }
+}
+
+/// A move reduced to the squares it moves a piece between and its promotion
+/// (see [`Move::canonical`])
+/// 
+/// Moves that make the same change to a position have the same canonical 
+/// move, however their roles and captures were filled in. They are ordered
+/// by origin, then destination, then promotion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CanonicalMove {
+    /// The square the piece moves from (the king's, if castling)
+    pub from: Square,
+    /// The square the piece moves to (the king's, if castling)
+    pub to: Square,
+    /// The role that a pawn promotes to, if any
+    pub promotion: Option<Role>,
+}
+
+/// A list of moves
+pub type MoveList = Vec<Move>;
+
+/// The roles a pawn can promote to, in the order promotions are generated
+/// 
+/// The queen comes first, as it is almost always the best promotion, so that
+/// a search tries it first. The underpromotions follow from the most to the
+/// least valuable.
+pub const PROMOTIONS: [Role; 4] = [Role::Queen, Role::Rook, Role::Bishop, Role::Knight];
+
+//=========//
+// Attacks //
+//=========//
+
+/// Gets the squares strictly after `s` on the ray in direction `d`
+#[inline]
+fn ray(d: Direction, s: Square) -> Bitboard {
+    Bitboard::new(RAYS[d as usize][usize::from(s)]).without_square(s)
+}
+
+/// Gets the squares attacked from `s` in direction `d`, up to and including
+/// the first square in `blockers`
+#[inline]
+fn ray_attacks(d: Direction, s: Square, blockers: Bitboard) -> Bitboard {
+    let moves = ray(d, s);
+    let blocker = if d.is_positive() {
+        (moves & blockers).smallest_square()
+    } else {
+        (moves & blockers).largest_square()
+    };
+    match blocker {
+        Some(b) => moves & !ray(d, b),
+        None => moves,
     }
 }
 
 fn generate_rook_moves(s: Square, blockers: Bitboard) -> Bitboard {
-    let mut moves = Bitboard::EMPTY;
-    moves
+    ray_attacks(Direction::North, s, blockers) |
+    ray_attacks(Direction::East, s, blockers) |
+    ray_attacks(Direction::South, s, blockers) |
+    ray_attacks(Direction::West, s, blockers)
 }
 
 fn generate_bishop_moves(s: Square, blockers: Bitboard) -> Bitboard {
-    let mut moves = Bitboard::EMPTY;
+    ray_attacks(Direction::Northeast, s, blockers) |
+    ray_attacks(Direction::Southeast, s, blockers) |
+    ray_attacks(Direction::Southwest, s, blockers) |
+    ray_attacks(Direction::Northwest, s, blockers)
+}
+
+/// A way of generating the moves of sliding pieces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlidingBackend {
+    /// Following rays until they're blocked
+    Classical,
+    /// Looking up moves in [magic](magic) tables
+    Magic,
+    /// Looking up moves in tables indexed by the BMI2 [PEXT](pext) instruction
+    Pext,
+}
+
+static SLIDING_BACKEND: OnceLock<SlidingBackend> = OnceLock::new();
+
+impl SlidingBackend {
+    /// Returns an iterator over all of the backends
+    pub fn iter() -> std::array::IntoIter<SlidingBackend, 3> {
+        [SlidingBackend::Classical, SlidingBackend::Magic, SlidingBackend::Pext].into_iter()
+    }
+
+    /// Returns `true` if the backend can be used on this CPU
+    pub fn is_available(self) -> bool {
+        match self {
+            SlidingBackend::Classical | SlidingBackend::Magic => true,
+            SlidingBackend::Pext => pext::is_available(),
+        }
+    }
+
+    /// Gets the backend chosen by the crate features, preferring PEXT to magics
+    /// where available
+    ///
+    /// The `no-magics` feature overrides `magics`, and forces the classical
+    /// backend
+    pub fn default_for_build() -> SlidingBackend {
+        if cfg!(feature = "no-magics") || !cfg!(feature = "magics") {
+            SlidingBackend::Classical
+        } else if SlidingBackend::Pext.is_available() {
+            SlidingBackend::Pext
+        } else {
+            SlidingBackend::Magic
+        }
+    }
+
+    /// Gets the backend in use, fixing it to the
+    /// [default](Self::default_for_build()) if none has been chosen yet
+    pub fn current() -> SlidingBackend {
+        *SLIDING_BACKEND.get_or_init(SlidingBackend::default_for_build)
+    }
+
+    /// Chooses the backend to use from now on, which may only be done once,
+    /// before any sliding moves are generated
+    ///
+    /// Fails with the backend in use if one has already been chosen, or with
+    /// `self` if it isn't available
+    pub fn select(self) -> Result<(), SlidingBackend> {
+        if !self.is_available() {
+            return Err(self)
+        }
+        SLIDING_BACKEND.set(self).map_err(|_| SlidingBackend::current())
+    }
+
+    /// Gets the squares a rook on `s` attacks, given the occupied squares
+    /// `occ`, using this backend
+    #[inline]
+    pub fn rook_attacks(self, s: Square, occ: Bitboard) -> Bitboard {
+        match self {
+            SlidingBackend::Classical => generate_rook_moves(s, occ),
+            SlidingBackend::Magic => magic::rook_attacks(s, occ),
+            SlidingBackend::Pext => pext::rook_attacks(s, occ),
+        }
+    }
+
+    /// Gets the squares a bishop on `s` attacks, given the occupied squares
+    /// `occ`, using this backend
+    #[inline]
+    pub fn bishop_attacks(self, s: Square, occ: Bitboard) -> Bitboard {
+        match self {
+            SlidingBackend::Classical => generate_bishop_moves(s, occ),
+            SlidingBackend::Magic => magic::bishop_attacks(s, occ),
+            SlidingBackend::Pext => pext::bishop_attacks(s, occ),
+        }
+    }
+}
+
+/// Gets the squares a rook on `s` attacks, given the occupied squares `occ`
+#[inline]
+pub fn rook_attacks(s: Square, occ: Bitboard) -> Bitboard {
+    SlidingBackend::current().rook_attacks(s, occ)
+}
+
+/// Gets the squares a bishop on `s` attacks, given the occupied squares `occ`
+#[inline]
+pub fn bishop_attacks(s: Square, occ: Bitboard) -> Bitboard {
+    SlidingBackend::current().bishop_attacks(s, occ)
+}
+
+/// Gets the squares a queen on `s` attacks, given the occupied squares `occ`
+#[inline]
+pub fn queen_attacks(s: Square, occ: Bitboard) -> Bitboard {
+    rook_attacks(s, occ) | bishop_attacks(s, occ)
+}
+
+/// Gets the squares a knight on `s` attacks
+#[inline]
+pub fn knight_attacks(s: Square) -> Bitboard {
+    Bitboard::new(KNIGHT_MOVE[usize::from(s)])
+}
+
+/// Gets the squares a king on `s` attacks
+#[inline]
+pub fn king_attacks(s: Square) -> Bitboard {
+    Bitboard::new(KING_MOVE[usize::from(s)])
+}
+
+/// Gets the squares a color `c` pawn on `s` attacks
+#[inline]
+pub fn pawn_attacks(c: Color, s: Square) -> Bitboard {
+    match c {
+        Color::White => Bitboard::new(PAWN_ATTACK_UP[usize::from(s)]),
+        Color::Black => Bitboard::new(PAWN_ATTACK_DOWN[usize::from(s)]),
+    }
+}
+
+/// Gets the squares a color `c` pawn on `s` can push to, ignoring blockers
+#[inline]
+fn pawn_pushes(c: Color, s: Square) -> Bitboard {
+    match c {
+        Color::White => Bitboard::new(PAWN_PUSH_UP[usize::from(s)]),
+        Color::Black => Bitboard::new(PAWN_PUSH_DOWN[usize::from(s)]),
+    }
+}
+
+/// Gets the squares a color `c` piece of role `r` on `s` attacks, given the
+/// occupied squares `occ`
+#[inline]
+pub fn attacks(r: Role, c: Color, s: Square, occ: Bitboard) -> Bitboard {
+    match r {
+        Role::Pawn => pawn_attacks(c, s),
+        Role::Knight => knight_attacks(s),
+        Role::Bishop => bishop_attacks(s, occ),
+        Role::Rook => rook_attacks(s, occ),
+        Role::Queen => queen_attacks(s, occ),
+        Role::King => king_attacks(s),
+    }
+}
+
+/// Gets the squares strictly between `a` and `b` if they share a rank, file or
+/// diagonal, otherwise returns an empty bitboard
+pub fn between(a: Square, b: Square) -> Bitboard {
+    for d in Direction::iter() {
+        if ray(d, a).contains(b) {
+            return ray(d, a) & !Bitboard::new(RAYS[d as usize][usize::from(b)])
+        }
+    }
+    Bitboard::EMPTY
+}
+
+/// Gets the whole rank, file or diagonal through both `a` and `b`, otherwise
+/// returns an empty bitboard
+pub fn line(a: Square, b: Square) -> Bitboard {
+    for d in Direction::iter() {
+        if ray(d, a).contains(b) {
+            return Bitboard::new(RAYS[d as usize][usize::from(a)]) | ray(d.opposite(), a)
+        }
+    }
+    Bitboard::EMPTY
+}
+
+//=================//
+// Move generation //
+//=================//
+
+/// # Attack methods
+impl Position {
+    /// Gets the color `by` pieces attacking the square `s`, treating only the
+    /// squares in `occ` as occupied
+    ///
+    /// Pieces that are not in `occ` are ignored, which allows for looking
+    /// through pieces that are (hypothetically) moved or captured
+    pub fn attackers_to(&self, s: Square, by: Color, occ: Bitboard) -> Bitboard {
+        let board = &self.board;
+        let pieces = board.color(by);
+        let diagonal = board.role(Role::Bishop) | board.role(Role::Queen);
+        let orthogonal = board.role(Role::Rook) | board.role(Role::Queen);
+        let attackers =
+            (pawn_attacks(!by, s) & board.role(Role::Pawn)) |
+            (knight_attacks(s) & board.role(Role::Knight)) |
+            (king_attacks(s) & board.king_bitboard(by)) |
+            (bishop_attacks(s, occ) & diagonal) |
+            (rook_attacks(s, occ) & orthogonal);
+        attackers & pieces & occ
+    }
+
+    /// Gets the color `by` pieces attacking the square `s` on the board as it
+    /// is (see [`attackers_to`](Self::attackers_to()) to look through pieces)
+    #[inline]
+    pub fn attackers(&self, s: Square, by: Color) -> Bitboard {
+        self.attackers_to(s, by, self.board.all())
+    }
+
+    /// Gets the squares attacked by color `c`'s pieces, whether or not they
+    /// are occupied
+    /// 
+    /// This is computed afresh each time (see 
+    /// [`attacks_by`](Self::attacks_by()) for the cached squares).
+    pub fn attacked_squares(&self, c: Color) -> Bitboard {
+        let board = &self.board;
+        let occ = board.all();
+        let mut attacked = king_attacks(board.king_square(c));
+        for r in Role::ITER_PIECE {
+            board.piece(Piece(c, r)).for_each_square(|s| attacked |= attacks(r, c, s, occ));
+        }
+        attacked
+    }
+
+    /// Gets the enemy pieces giving check to the king of the side to move
+    /// 
+    /// Attacks are symmetric, so these are the enemy pieces that a piece of 
+    /// each role on the king's square would attack (leaving out the enemy 
+    /// king, which can't give check).
+    pub fn checkers(&self) -> Bitboard {
+        let us = self.turn;
+        let board = &self.board;
+        let king = board.king_square(us);
+        let occ = board.all();
+        let diagonal = board.role(Role::Bishop) | board.role(Role::Queen);
+        let orthogonal = board.role(Role::Rook) | board.role(Role::Queen);
+        let checkers =
+            (pawn_attacks(us, king) & board.role(Role::Pawn)) |
+            (knight_attacks(king) & board.role(Role::Knight)) |
+            (bishop_attacks(king, occ) & diagonal) |
+            (rook_attacks(king, occ) & orthogonal);
+        checkers & board.color(!us)
+    }
+
+    /// Returns `true` if the side to move is in check
+    /// 
+    /// Like [`checkers`](Self::checkers()), but stops at the first role found
+    /// checking, and only looks along the lines of roles the opponent has.
+    pub fn is_in_check(&self) -> bool {
+        let us = self.turn;
+        let board = &self.board;
+        let king = board.king_square(us);
+        let enemies = board.color(!us);
+        let diagonal = (board.role(Role::Bishop) | board.role(Role::Queen)) & enemies;
+        let orthogonal = (board.role(Role::Rook) | board.role(Role::Queen)) & enemies;
+        (pawn_attacks(us, king) & board.role(Role::Pawn) & enemies).is_any() ||
+        (knight_attacks(king) & board.role(Role::Knight) & enemies).is_any() ||
+        (diagonal.is_any() && (bishop_attacks(king, board.all()) & diagonal).is_any()) ||
+        (orthogonal.is_any() && (rook_attacks(king, board.all()) & orthogonal).is_any())
+    }
+
+    /// Gets the pieces of the side to move that are pinned to their king
+    pub fn pinned(&self) -> Bitboard {
+        let us = self.turn;
+        let king = self.board.king_square(us);
+        let occ = self.board.all();
+        let them = self.board.color(!us);
+        let diagonal = self.board.role(Role::Bishop) | self.board.role(Role::Queen);
+        let orthogonal = self.board.role(Role::Rook) | self.board.role(Role::Queen);
+
+        // Enemy sliders that would attack the king if our pieces weren't there
+        let snipers = them & (
+            (bishop_attacks(king, them) & diagonal) |
+            (rook_attacks(king, them) & orthogonal)
+        );
+
+        let mut pinned = Bitboard::EMPTY;
+        for sniper in snipers {
+            let blockers = between(king, sniper) & occ;
+            if blockers.is_singular() {
+                pinned |= blockers & self.board.color(us);
+            }
+        }
+        pinned
+    }
+
+    /// Gets the sliders of the same color as the piece on `mover_from` that 
+    /// would attack more squares if it moved away, i.e. the pieces that could
+    /// make a discovered attack
+    /// 
+    /// Returns an empty bitboard if `mover_from` is empty
+    pub fn discovered_attackers(&self, mover_from: Square) -> Bitboard {
+        let Some(Piece(c, _)) = self.board.get(mover_from) else {
+            return Bitboard::EMPTY
+        };
+        let occ = self.board.all();
+        let vacated = occ.without_square(mover_from);
+        let sliders = self.board.sliders(c).without_square(mover_from);
+        let mut discovered = Bitboard::EMPTY;
+        for s in sliders {
+            let r = self.board.get(s).unwrap().1;
+            if attacks(r, c, s, vacated) != attacks(r, c, s, occ) {
+                discovered.insert(s);
+            }
+        }
+        discovered
+    }
+
+    /// Returns `true` if the (legal) move `m` gives check to the opponent
+    pub fn gives_check(&self, m: Move) -> bool {
+        let us = self.turn;
+        let king = self.board.king_square(!us);
+        let (vacated, occupied, captured) = self.move_squares(m);
+        let occ = ((self.board.all() & !vacated).without_square(captured)) | occupied;
+
+        // Checks by the moved piece itself
+        let direct = match m {
+            Move::Castle { castling_side } => {
+                let (_, _, _, r_to) = self.castling_squares(us, castling_side);
+                rook_attacks(r_to, occ).contains(king)
+            }
+            Move::PawnMove { to, promotion: Some(r), .. } => {
+                attacks(r, us, to, occ).contains(king)
+            }
+            _ => {
+                let to = occupied.smallest_square().unwrap();
+                attacks(m.role(), us, to, occ).contains(king)
+            }
+        };
+
+        // Discovered checks, by pieces that haven't moved
+        direct || (self.attackers_to(king, us, occ) & !occupied).is_any()
+    }
+}
+
+/// # Generate methods
+impl Position {
+    /// Generates all of the legal moves in the position
+    pub fn generate(&self) -> MoveList {
+        let mut movelist = MoveList::new();
+
+        let checkers = self.checkers();
+        if let Some(target) = self.target(checkers) {
+            let pinned = self.pinned();
+            self.gen_pawns(&mut movelist, target, pinned);
+            self.gen_pieces(&mut movelist, target, pinned);
+            if checkers.is_empty() {
+                self.gen_castles(&mut movelist);
+            }
+        }
+        self.gen_king(&mut movelist);
+
+        movelist
+    }
 
-    let ne_b = Bitboard::new(RAYS[Direction::Northeast as usize][usize::from(s)]);
-    for s in ne_b.into_iter().rev() {
+    /// Generates all of the legal moves in the position, sorted by their
+    /// [canonical moves](Move::canonical()): by origin, then destination, 
+    /// then promotion (with the knight first)
+    /// 
+    /// Unlike [`generate`](Self::generate()), the order doesn't depend on 
+    /// how the moves are generated.
+    pub fn legal_moves_sorted(&self) -> MoveList {
+        let mut movelist = self.generate();
+        movelist.sort_by_key(|m| m.canonical(self));
+        movelist
+    }
+
+    /// Generates the legal pawn moves in the position into `movelist`
+    pub fn gen_pawn_moves(&self, movelist: &mut MoveList) {
+        if let Some(target) = self.target(self.checkers()) {
+            self.gen_pawns(movelist, target, self.pinned());
+        }
+    }
+
+    /// Gets the squares that a piece other than the king may move to, given
+    /// the `checkers` of the king, which are the blocking squares and the 
+    /// checker when in check, or [`None`] in double check, where only the king
+    /// can move
+    fn target(&self, checkers: Bitboard) -> Option<Bitboard> {
+        let us = self.turn;
+        let king = self.board.king_square(us);
+        match checkers.count() {
+            0 => Some(!self.board.color(us)),
+            1 => {
+                let checker = checkers.smallest_square().unwrap();
+                Some(between(king, checker) | Bitboard::square(checker))
+            }
+            _ => None,
+        }
+    }
+
+    /// Generates the legal moves that leave the piece on `threatened`
+    /// unattacked, either by moving it to safety or by capturing or blocking
+    /// its attackers
+    ///
+    /// Returns no moves if `threatened` is not occupied by a piece of the side
+    /// to move
+    pub fn moves_escaping(&self, threatened: Square) -> MoveList {
+        if !self.board.color(self.turn).contains(threatened) {
+            return MoveList::new()
+        }
+        let them = !self.turn;
+        self.generate()
+            .into_iter()
+            .filter(|&m| {
+                let (vacated, occupied, captured) = self.move_squares(m);
+                let occ = (self.board.all() & !vacated).without_square(captured) | occupied;
+                let enemies = self.board.color(them).without_square(captured);
+                let s = match m {
+                    Move::Castle { castling_side } => {
+                        let (k_from, k_to, r_from, r_to) =
+                            self.castling_squares(self.turn, castling_side);
+                        if threatened == k_from { k_to }
+                        else if threatened == r_from { r_to }
+                        else { threatened }
+                    }
+                    _ if vacated.contains(threatened) => occupied.smallest_square().unwrap(),
+                    _ => threatened,
+                };
+                (self.attackers_to(s, them, occ) & enemies).is_empty()
+            })
+            .collect()
+    }
+
+    /// Gets the legal move with the [canonical move](Move::canonical()) `cm`,
+    /// with its role and capture filled in, if there is one
+    pub fn find_move(&self, cm: CanonicalMove) -> Option<Move> {
+        self.generate()
+            .into_iter()
+            .find(|&m| m.canonical(self) == cm)
+    }
+
+    /// Gets the destinations of the legal moves from `from`, where castling
+    /// moves the king to the g- or c-file (as in its
+    /// [canonical move](Move::canonical()))
+    pub fn legal_targets(&self, from: Square) -> Bitboard {
+        self.generate()
+            .into_iter()
+            .map(|m| m.canonical(self))
+            .filter(|cm| cm.from == from)
+            .fold(Bitboard::EMPTY, |targets, cm| targets.with_square(cm.to))
+    }
+
+    /// Gets the [legal targets](Self::legal_targets()) from every square at 
+    /// once, indexed by origin
+    /// 
+    /// The moves are generated once, so a GUI can keep the table for the 
+    /// position and answer which squares a piece may move to in O(1).
+    pub fn legal_targets_all(&self) -> [Bitboard; 64] {
+        let mut targets = [Bitboard::EMPTY; 64];
+        for m in self.generate() {
+            let cm = m.canonical(self);
+            targets[cm.from.index()].insert(cm.to);
+        }
+        targets
+    }
+
+    /// Gets the legal move if there is exactly one, or [`None`] if there are
+    /// none or several
+    /// 
+    /// The moves are generated in stages, the king's first, then the other
+    /// pieces' and the pawns', and the later stages are skipped once more
+    /// than one move has been found, so this is cheaper than counting every
+    /// move when playing out forced lines.
+    pub fn only_move(&self) -> Option<Move> {
+        let mut movelist = MoveList::new();
+        self.gen_king(&mut movelist);
+        if movelist.len() > 1 {
+            return None
+        }
+
+        let checkers = self.checkers();
+        if let Some(target) = self.target(checkers) {
+            let pinned = self.pinned();
+            self.gen_pieces(&mut movelist, target, pinned);
+            if movelist.len() > 1 {
+                return None
+            }
+            self.gen_pawns(&mut movelist, target, pinned);
+            if movelist.len() > 1 {
+                return None
+            }
+            if checkers.is_empty() {
+                self.gen_castles(&mut movelist);
+            }
+        }
+
+        match movelist[..] {
+            [m] => Some(m),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if `m` is a legal move in the position
+    ///
+    /// The move is validated on its own rather than searched for among the
+    /// generated moves, so this is cheap enough for checking every move that
+    /// comes from an untrusted source. Its role and capture must be filled in
+    /// as generated, whereas [`find_move`](Self::find_move()) matches a move
+    /// however they were filled in.
+    pub fn is_legal(&self, m: Move) -> bool {
+        let us = self.turn;
+        let them = !us;
+        let occ = self.board.all();
+
+        let pseudo_legal = match m {
+            Move::Castle { castling_side } => {
+                return self.castle_is_legal(castling_side)
+            }
+            Move::Normal { role, from, to, capture } => {
+                role != Role::Pawn &&
+                self.board.get(from) == Some(Piece(us, role)) &&
+                attacks(role, us, from, occ).contains(to) &&
+                self.captures_on(to, capture)
+            }
+            Move::DoublePawnPush { from, to } => {
+                let skipped = between(from, to);
+                self.board.get(from) == Some(Piece(us, Role::Pawn)) &&
+                pawn_pushes(us, from).contains(to) &&
+                skipped.is_any() &&
+                ((skipped | Bitboard::square(to)) & occ).is_empty()
+            }
+            Move::PawnMove { from, to, promotion, en_passant, capture } => {
+                let last_rank = Bitboard::promotion_rank(us.index()).contains(to);
+                let promotes = match promotion {
+                    None => !last_rank,
+                    Some(Role::Pawn | Role::King) => false,
+                    Some(_) => last_rank,
+                };
+                let reaches = if en_passant {
+                    self.en_passant == Some(to) &&
+                    capture == Some(Role::Pawn) &&
+                    pawn_attacks(us, from).contains(to)
+                } else if capture.is_some() {
+                    pawn_attacks(us, from).contains(to) && self.captures_on(to, capture)
+                } else {
+                    pawn_pushes(us, from).contains(to) &&
+                    between(from, to).is_empty() &&
+                    !occ.contains(to)
+                };
+                self.board.get(from) == Some(Piece(us, Role::Pawn)) && promotes && reaches
+            }
+        };
+        if !pseudo_legal {
+            return false
+        }
 
+        // The move mustn't leave the king attacked
+        let (vacated, occupied, captured) = self.move_squares(m);
+        let occ = ((occ & !vacated).without_square(captured)) | occupied;
+        let enemies = self.board.color(them).without_square(captured);
+        let king = match m {
+            Move::Normal { role: Role::King, to, .. } => to,
+            _ => self.board.king_square(us),
+        };
+        (self.attackers_to(king, them, occ) & enemies).is_empty()
     }
 
-    let se_b = Bitboard::new(RAYS[Direction::Northeast as usize][usize::from(s)]);
-    for s in se_b.into_iter() {
+    /// Returns `true` if moving to `to` captures a piece of role `capture`
+    /// (or nothing, if `None`) that the side to move may take
+    fn captures_on(&self, to: Square, capture: Option<Role>) -> bool {
+        match self.board.get(to) {
+            None => capture.is_none(),
+            Some(Piece(c, r)) => c != self.turn && r != Role::King && capture == Some(r),
+        }
+    }
+
+    /// Gets the squares vacated and occupied by the side to move when making
+    /// the move `m`, and the square of the captured piece (or the destination
+    /// square, if nothing is captured)
+    fn move_squares(&self, m: Move) -> (Bitboard, Bitboard, Square) {
+        match m {
+            Move::PawnMove { from, to, en_passant: true, .. } => {
+                let captured = Square::from(Coords(to.file(), from.rank()));
+                (Bitboard::square(from), Bitboard::square(to), captured)
+            }
+            Move::PawnMove { from, to, .. } |
+            Move::DoublePawnPush { from, to } |
+            Move::Normal { from, to, .. } => {
+                (Bitboard::square(from), Bitboard::square(to), to)
+            }
+            Move::Castle { castling_side } => {
+                let (k_from, k_to, r_from, r_to) =
+                    self.castling_squares(self.turn, castling_side);
+                (
+                    Bitboard::square(k_from) | Bitboard::square(r_from),
+                    Bitboard::square(k_to) | Bitboard::square(r_to),
+                    k_to,
+                )
+            }
+        }
+    }
+
+    /// Gets the king's origin and destination squares, followed by the rook's
+    /// origin and destination squares, for color `c` castling on side `cs`
+    ///
+    /// # Precondition
+    ///
+    /// Color `c` must have the castling right for side `cs`
+    pub fn castling_squares(
+        &self,
+        c: Color,
+        cs: CastlingSide
+    ) -> (Square, Square, Square, Square) {
+        let k_from = self.board.king_square(c);
+        let back_rank = k_from.rank();
+        let rook_file = self.castling.rook_file(c, cs);
+        debug_assert!(rook_file.is_some());
+        let rook_file = rook_file.unwrap_or(cs.standard_rook_file());
+        let (k_to_file, r_to_file) = match cs {
+            CastlingSide::Kingside => (File::G, File::F),
+            CastlingSide::Queenside => (File::C, File::D),
+        };
+        (
+            k_from,
+            Square::from(Coords(k_to_file, back_rank)),
+            Square::from(Coords(rook_file, back_rank)),
+            Square::from(Coords(r_to_file, back_rank)),
+        )
+    }
+
+    /// Generates the legal pawn moves to squares in `target` (en passant is
+    /// checked separately, since it removes two pieces from the board)
+    fn gen_pawns(&self, movelist: &mut MoveList, target: Bitboard, pinned: Bitboard) {
+        let us = self.turn;
+        let king = self.board.king_square(us);
+        let occ = self.board.all();
+        let enemies = self.board.color(!us);
+        let pawns = self.board.piece(Piece(us, Role::Pawn));
+        let promotions = Bitboard::promotion_rank(us.index());
+
+        for from in pawns {
+            let allowed = if pinned.contains(from) {
+                target & line(king, from)
+            } else {
+                target
+            };
+
+            // Pushes
+            let pushes = pawn_pushes(us, from);
+            let (files, ranks) = us.forward().offsets();
+            let single = from.offset(files, ranks);
+            if let Some(single) = single.filter(|&s| !occ.contains(s)) {
+                if allowed.contains(single) {
+                    push_pawn_move(movelist, from, single, None, promotions);
+                }
+                for to in pushes.without_square(single) & !occ & allowed {
+                    movelist.push(Move::DoublePawnPush { from, to });
+                }
+            }
+
+            // Captures
+            for to in pawn_attacks(us, from) & enemies & allowed {
+                push_pawn_move(movelist, from, to, self.board.get(to).map(|p| p.1), promotions);
+            }
+        }
+
+        // En passant, checked with both pawns taken off the board, since
+        // together they may shield the king along the rank where neither is
+        // pinned on its own
+        if let Some(ep) = self.en_passant {
+            for from in pawn_attacks(!us, ep) & pawns {
+                let captured = Square::from(Coords(ep.file(), from.rank()));
+                let occ = occ
+                    .without_square(from)
+                    .without_square(captured)
+                    .with_square(ep);
+                if self.attackers_to(king, !us, occ).is_empty() {
+                    movelist.push(Move::PawnMove {
+                        from,
+                        to: ep,
+                        promotion: None,
+                        en_passant: true,
+                        capture: Some(Role::Pawn),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Generates the legal knight, bishop, rook and queen moves to squares in
+    /// `target`
+    fn gen_pieces(&self, movelist: &mut MoveList, target: Bitboard, pinned: Bitboard) {
+        let us = self.turn;
+        let king = self.board.king_square(us);
+        let occ = self.board.all();
 
+        for role in [Role::Knight, Role::Bishop, Role::Rook, Role::Queen] {
+            for from in self.board.piece(Piece(us, role)) {
+                let allowed = if pinned.contains(from) {
+                    target & line(king, from)
+                } else {
+                    target
+                };
+                for to in attacks(role, us, from, occ) & allowed {
+                    let capture = self.board.get(to).map(|p| p.1);
+                    movelist.push(Move::Normal { role, from, to, capture });
+                }
+            }
+        }
     }
 
-    let sw_b = Bitboard::new(RAYS[Direction::Northeast as usize][usize::from(s)]);
-    for s in sw_b.into_iter() {
+    /// Generates the legal (non-castling) king moves
+    fn gen_king(&self, movelist: &mut MoveList) {
+        let us = self.turn;
+        let from = self.board.king_square(us);
+        // The king can't hide from a slider by stepping along its ray
+        let occ = self.board.all().without_square(from);
 
+        for to in king_attacks(from) & !self.board.color(us) {
+            if self.attackers_to(to, !us, occ).is_empty() {
+                let capture = self.board.get(to).map(|p| p.1);
+                movelist.push(Move::Normal { role: Role::King, from, to, capture });
+            }
+        }
     }
-    
-    let nw_b = Bitboard::new(RAYS[Direction::Northeast as usize][usize::from(s)]);
-    for s in nw_b.into_iter() {
-        
+
+    /// Generates the legal castling moves, which need the castling right, an
+    /// empty path for the king and rook, the rook on its square, and the king
+    /// to not be in check or pass through or land on an attacked square
+    fn gen_castles(&self, movelist: &mut MoveList) {
+        for castling_side in [CastlingSide::Kingside, CastlingSide::Queenside] {
+            if self.castle_is_legal(castling_side) {
+                movelist.push(Move::Castle { castling_side });
+            }
+        }
+    }
+
+    /// Returns `true` if the side to move has the right to castle on side
+    /// `castling_side`, and the king and rook can get there safely
+    fn castle_is_legal(&self, castling_side: CastlingSide) -> bool {
+        let us = self.turn;
+        if !self.castling.get(us, castling_side) {
+            return false
+        }
+        let (k_from, k_to, r_from, r_to) = self.castling_squares(us, castling_side);
+        if self.board.get(r_from) != Some(Piece(us, Role::Rook)) {
+            return false
+        }
+
+        let k_path = between(k_from, k_to) | Bitboard::square(k_to);
+        let r_path = between(r_from, r_to) | Bitboard::square(r_to);
+        let occ = self.board.all()
+            .without_square(k_from)
+            .without_square(r_from);
+        if ((k_path | r_path) & occ).is_any() {
+            return false
+        }
+
+        // The king may not castle out of, through or into check
+        k_path.with_square(k_from)
+            .into_iter()
+            .all(|s| self.attackers_to(s, !us, occ).is_empty())
     }
+}
 
-    moves
-}
\ No newline at end of file
+/// Pushes a pawn move to `to`, expanding it into every promotion (in the order
+/// of [`PROMOTIONS`]) if `to` is on the `promotions` rank
+fn push_pawn_move(
+    movelist: &mut MoveList,
+    from: Square,
+    to: Square,
+    capture: Option<Role>,
+    promotions: Bitboard,
+) {
+    if promotions.contains(to) {
+        for promotion in PROMOTIONS {
+            movelist.push(Move::PawnMove {
+                from,
+                to,
+                promotion: Some(promotion),
+                en_passant: false,
+                capture
+            });
+        }
+    } else {
+        movelist.push(Move::PawnMove {
+            from,
+            to,
+            promotion: None,
+            en_passant: false,
+            capture
+        });
+    }
+}
@@ -0,0 +1,141 @@
+//! [Perft](https://www.chessprogramming.org/Perft), for testing and debugging
+//! move generation
+
+use crate::position::{
+    Position,
+    board,
+};
+
+/// A breakdown of the leaf nodes of a perft search, in the format of the
+/// [published tables](https://www.chessprogramming.org/Perft_Results)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PerftStats {
+    /// The number of leaf nodes
+    pub nodes: u64,
+    /// The number of leaf moves that capture (including en passant)
+    pub captures: u64,
+    /// The number of leaf moves that capture en passant
+    pub en_passants: u64,
+    /// The number of leaf moves that castle
+    pub castles: u64,
+    /// The number of leaf moves that promote
+    pub promotions: u64,
+    /// The number of leaf moves that give check
+    pub checks: u64,
+    /// The number of leaf moves that give checkmate
+    pub checkmates: u64,
+}
+
+impl std::ops::AddAssign for PerftStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.nodes += rhs.nodes;
+        self.captures += rhs.captures;
+        self.en_passants += rhs.en_passants;
+        self.castles += rhs.castles;
+        self.promotions += rhs.promotions;
+        self.checks += rhs.checks;
+        self.checkmates += rhs.checkmates;
+    }
+}
+
+/// # Perft methods
+impl Position {
+    /// Counts the leaf nodes of the legal move tree of depth `depth`
+    /// 
+    /// The moves at the last ply are counted without being made ("bulk 
+    /// counting"); see [`perft_naive`](Self::perft_naive()) to make every 
+    /// move.
+    pub fn perft(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let moves = self.generate();
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        let mut nodes = 0;
+        for m in moves {
+            let undo = self.make_move(m);
+            nodes += self.perft(depth - 1);
+            self.unmake_move(undo);
+        }
+        nodes
+    }
+
+    /// Counts the leaf nodes of the legal move tree of depth `depth` like
+    /// [`perft`](Self::perft()), but making and unmaking every move down to
+    /// the leaves rather than counting the moves at the last ply
+    /// 
+    /// This visits every node, for profiling or for checking
+    /// [`make_move`](Self::make_move()) at the leaves too.
+    pub fn perft_naive(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        let mut nodes = 0;
+        for m in self.generate() {
+            let undo = self.make_move(m);
+            nodes += self.perft_naive(depth - 1);
+            self.unmake_move(undo);
+        }
+        nodes
+    }
+
+    /// Counts the leaf nodes of the legal move tree of depth `depth` like
+    /// [`perft`](Self::perft()), but without 
+    /// [verifying](board::Board::debug_verify()) the board after every update
+    /// in debug builds
+    pub fn perft_unchecked(&mut self, depth: u32) -> u64 {
+        board::without_verification(|| self.perft(depth))
+    }
+
+    /// Counts the leaf nodes of the legal move tree of depth `depth` like
+    /// [`perft`](Self::perft()), searching the subtree of each root move on
+    /// its own thread with its own copy of the position
+    #[cfg(feature = "rayon")]
+    pub fn par_perft(&self, depth: u32) -> u64 {
+        use rayon::prelude::*;
+
+        if depth <= 1 {
+            return self.clone_fresh().perft(depth);
+        }
+        self.generate()
+            .into_par_iter()
+            .map(|m| {
+                let mut p = self.clone_fresh();
+                p.make_move(m);
+                p.perft(depth - 1)
+            })
+            .sum()
+    }
+
+    /// Counts the leaf nodes of the legal move tree of depth `depth`, broken
+    /// down by the kind of move that reached them
+    pub fn perft_stats(&mut self, depth: u32) -> PerftStats {
+        let mut stats = PerftStats::default();
+        if depth == 0 {
+            stats.nodes = 1;
+            return stats;
+        }
+        for m in self.generate() {
+            if depth == 1 {
+                stats.nodes += 1;
+                stats.captures += m.is_capture() as u64;
+                stats.en_passants += m.is_en_passant() as u64;
+                stats.castles += m.is_castle() as u64;
+                stats.promotions += m.is_promotion() as u64;
+                if self.gives_check(m) {
+                    stats.checks += 1;
+                    let undo = self.make_move(m);
+                    stats.checkmates += self.generate().is_empty() as u64;
+                    self.unmake_move(undo);
+                }
+            } else {
+                let undo = self.make_move(m);
+                stats += self.perft_stats(depth - 1);
+                self.unmake_move(undo);
+            }
+        }
+        stats
+    }
+}
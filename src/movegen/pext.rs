@@ -0,0 +1,134 @@
+//! Sliding move generation indexed by
+//! [PEXT](https://www.chessprogramming.org/BMI2#PEXTBitboards), on CPUs with
+//! BMI2
+
+use std::sync::OnceLock;
+
+use crate::{
+    bits::{
+        Bitboard,
+        Square,
+    },
+    position::Role,
+    movegen::magic::{find_mask, slider_moves},
+};
+
+/// Extracts the bits of `value` selected by `mask` into the low bits, in
+/// software
+fn pext_software(value: u64, mask: u64) -> u64 {
+    let mut result = 0;
+    let mut bit = 1;
+    let mut mask = mask;
+    while mask != 0 {
+        let lowest = mask & mask.wrapping_neg();
+        if value & lowest != 0 {
+            result |= bit;
+        }
+        bit <<= 1;
+        mask &= mask - 1;
+    }
+    result
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "bmi2")]
+unsafe fn pext_bmi2(value: u64, mask: u64) -> u64 {
+    std::arch::x86_64::_pext_u64(value, mask)
+}
+
+/// Returns `true` if the CPU has a PEXT instruction
+pub fn is_available() -> bool {
+    #[cfg(target_arch = "x86_64")]
+    {
+        is_x86_feature_detected!("bmi2")
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        false
+    }
+}
+
+#[inline]
+fn pext(value: u64, mask: u64) -> u64 {
+    #[cfg(target_arch = "x86_64")]
+    {
+        // SAFETY: the tables are only built once BMI2 is known to be available
+        unsafe { pext_bmi2(value, mask) }
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        pext_software(value, mask)
+    }
+}
+
+/// The rook and bishop masks for every square, with the moves they index
+pub struct PextTables {
+    rook: Vec<(Bitboard, usize)>,
+    bishop: Vec<(Bitboard, usize)>,
+    table: Vec<Bitboard>,
+}
+
+impl PextTables {
+    fn new() -> Self {
+        let mut table = Vec::new();
+        let mut build = |r: Role| -> Vec<(Bitboard, usize)> {
+            Square::iter()
+                .map(|s| {
+                    let mask = find_mask(r, s);
+                    let offset = table.len();
+                    table.resize(offset + (1 << mask.count()), Bitboard::EMPTY);
+                    for blockers in mask.subsets().into_iter().chain([Bitboard::EMPTY]) {
+                        let index = pext_software(u64::from(blockers), u64::from(mask));
+                        table[offset + index as usize] = slider_moves(r, s, blockers);
+                    }
+                    (mask, offset)
+                })
+                .collect()
+        };
+        let rook = build(Role::Rook);
+        let bishop = build(Role::Bishop);
+        PextTables { rook, bishop, table }
+    }
+
+    /// Gets the squares a rook on `s` attacks, given the occupied squares
+    /// `occ`
+    #[inline]
+    pub fn rook_attacks(&self, s: Square, occ: Bitboard) -> Bitboard {
+        let (mask, offset) = self.rook[usize::from(s)];
+        self.table[offset + pext(u64::from(occ), u64::from(mask)) as usize]
+    }
+
+    /// Gets the squares a bishop on `s` attacks, given the occupied squares
+    /// `occ`
+    #[inline]
+    pub fn bishop_attacks(&self, s: Square, occ: Bitboard) -> Bitboard {
+        let (mask, offset) = self.bishop[usize::from(s)];
+        self.table[offset + pext(u64::from(occ), u64::from(mask)) as usize]
+    }
+}
+
+static SHARED_TABLES: OnceLock<PextTables> = OnceLock::new();
+
+/// Gets the PEXT tables, building them on first use
+///
+/// # Panics
+///
+/// Panics if the CPU has no PEXT instruction (see [`is_available`])
+pub fn shared_tables() -> &'static PextTables {
+    SHARED_TABLES.get_or_init(|| {
+        assert!(is_available(), "PEXT is not available on this CPU");
+        PextTables::new()
+    })
+}
+
+/// Gets the squares a rook on `s` attacks, given the occupied squares `occ`
+#[inline]
+pub fn rook_attacks(s: Square, occ: Bitboard) -> Bitboard {
+    shared_tables().rook_attacks(s, occ)
+}
+
+/// Gets the squares a bishop on `s` attacks, given the occupied squares `occ`
+#[inline]
+pub fn bishop_attacks(s: Square, occ: Bitboard) -> Bitboard {
+    shared_tables().bishop_attacks(s, occ)
+}
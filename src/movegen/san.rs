@@ -0,0 +1,243 @@
+//! Formatting moves in [Standard Algebraic Notation](https://www.chessprogramming.org/Algebraic_Chess_Notation#Standard_Algebraic_Notation_.28SAN.29)
+
+use std::fmt::Display;
+
+use crate::{
+    bits::{File, Rank, Square},
+    movegen::{Move, PROMOTIONS, uci::parse_square},
+    position::{
+        Color,
+        Piece,
+        Position,
+        Role,
+        castling::CastlingSide,
+    },
+};
+
+/// An error from parsing a move in SAN
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SanError {
+    /// The string isn't formatted as SAN
+    Syntax,
+    /// The string is SAN, but not for any legal move in the position
+    Illegal,
+}
+
+impl Display for SanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SanError::Syntax => write!(f, "Invalid SAN"),
+            SanError::Illegal => write!(f, "Illegal move"),
+        }
+    }
+}
+
+/// Strips the check, checkmate and annotation suffixes from `san`, and spells
+/// castling with letters rather than zeros and pieces with letters rather
+/// than figurines (dropping those of pawns, which SAN leaves out)
+fn normalize(san: &str) -> String {
+    san.trim_end_matches(['+', '#', '!', '?'])
+        .chars()
+        .filter_map(|ch| match (ch, Role::from_figurine(ch)) {
+            ('0', _) => Some('O'),
+            (_, Some(Role::Pawn)) => None,
+            (_, Some(r)) => Some(role_char(r)),
+            (_, None) => Some(ch),
+        })
+        .collect()
+}
+
+/// Gets the uppercase letter of a (non-pawn) role
+fn role_char(r: Role) -> char {
+    char::from(Piece(Color::White, r))
+}
+
+/// The parts of a (non-castling) move in SAN, e.g. `Nbxd7` or `e8=Q`
+struct SanParts {
+    role: Role,
+    file: Option<File>,
+    rank: Option<Rank>,
+    capture: bool,
+    to: Square,
+    promotion: Option<Role>,
+}
+
+impl SanParts {
+    /// Reads the parts of a [normalized](normalize()) move in SAN
+    fn parse(san: &str) -> Option<SanParts> {
+        let (body, promotion) = match san.split_once('=') {
+            Some((body, p)) => {
+                let mut chs = p.chars();
+                let r = Piece::try_from(chs.next()?).ok()?.1;
+                if chs.next().is_some() || !PROMOTIONS.contains(&r) {
+                    return None
+                }
+                (body, Some(r))
+            }
+            None => (san, None),
+        };
+        let (role, rest) = match body.chars().next()? {
+            ch @ ('K' | 'Q' | 'R' | 'B' | 'N') => (Piece::try_from(ch).ok()?.1, &body[1..]),
+            _ => (Role::Pawn, body),
+        };
+        let (rest, to) = rest.split_at(rest.len().checked_sub(2)?);
+        let to = parse_square(to)?;
+        let (rest, capture) = match rest.strip_suffix('x') {
+            Some(rest) => (rest, true),
+            None => (rest, false),
+        };
+        let (file, rank) = match *rest.as_bytes() {
+            [] => (None, None),
+            [ch] => match File::try_from(ch as char) {
+                Ok(f) => (Some(f), None),
+                Err(_) => (None, Some(Rank::try_from(ch as char).ok()?)),
+            },
+            [f, r] => (Some(File::try_from(f as char).ok()?), Some(Rank::try_from(r as char).ok()?)),
+            _ => return None,
+        };
+        // Pawns are only told apart by file, when capturing
+        if role == Role::Pawn && (rank.is_some() || file.is_some() != capture) {
+            return None
+        }
+        Some(SanParts { role, file, rank, capture, to, promotion })
+    }
+
+    /// Returns `true` if the legal move `m`, in `pos`, has these parts
+    fn matches(&self, pos: &Position, m: Move) -> bool {
+        let cm = m.canonical(pos);
+        !m.is_castle() &&
+        m.role() == self.role &&
+        m.is_capture() == self.capture &&
+        cm.to == self.to &&
+        cm.promotion == self.promotion &&
+        self.file.is_none_or(|f| cm.from.file() == f) &&
+        self.rank.is_none_or(|r| cm.from.rank() == r)
+    }
+}
+
+/// # Notation methods
+impl Position {
+    /// Formats the (legal) move `m` in SAN, e.g. `Nbd7`, `exd6`, `e8=Q+` or
+    /// `O-O-O#`
+    pub fn san(&self, m: Move) -> String {
+        let mut san = match m {
+            Move::Castle { castling_side: CastlingSide::Kingside } => "O-O".to_string(),
+            Move::Castle { castling_side: CastlingSide::Queenside } => "O-O-O".to_string(),
+            Move::PawnMove { from, to, promotion, capture, .. } => {
+                let mut san = String::new();
+                if capture.is_some() {
+                    san.push(char::from(from.file()));
+                    san.push('x');
+                }
+                san.push_str(&to.to_string());
+                if let Some(r) = promotion {
+                    san.push('=');
+                    san.push(role_char(r));
+                }
+                san
+            }
+            Move::DoublePawnPush { to, .. } => to.to_string(),
+            Move::Normal { role, from, to, capture } => {
+                let mut san = String::from(role_char(role));
+                san.push_str(&self.disambiguation(role, from, to));
+                if capture.is_some() {
+                    san.push('x');
+                }
+                san.push_str(&to.to_string());
+                san
+            }
+        };
+
+        if self.gives_check(m) {
+            let mut after = self.clone_fresh();
+            after.make_move(m);
+            san.push(if after.generate().is_empty() { '#' } else { '+' });
+        }
+        san
+    }
+
+    /// Gets the part of the origin square needed to tell a `role` move from
+    /// `from` to `to` apart from the other legal moves to `to`
+    /// 
+    /// Only legal moves count, so a pinned piece that can't reach `to` needs
+    /// no telling apart.
+    fn disambiguation(&self, role: Role, from: Square, to: Square) -> String {
+        let others: Vec<Square> = self.generate()
+            .into_iter()
+            .filter_map(|m| match m {
+                Move::Normal { role: r, from: f, to: t, .. }
+                    if r == role && t == to && f != from => Some(f),
+                _ => None,
+            })
+            .collect();
+        if others.is_empty() {
+            String::new()
+        } else if others.iter().all(|s| s.file() != from.file()) {
+            char::from(from.file()).to_string()
+        } else if others.iter().all(|s| s.rank() != from.rank()) {
+            char::from(from.rank()).to_string()
+        } else {
+            from.to_string()
+        }
+    }
+
+    /// Formats a sequence of (legal) moves from the position in SAN, with
+    /// move numbers, e.g. `1. e4 e5 2. Nf3` or `1... e5 2. Nf3`
+    pub fn san_line(&self, moves: &[Move]) -> String {
+        let mut p = self.clone();
+        let mut line = Vec::with_capacity(moves.len());
+        for (i, &m) in moves.iter().enumerate() {
+            match p.turn {
+                Color::White => line.push(format!("{}.", p.fullmove)),
+                Color::Black if i == 0 => line.push(format!("{}...", p.fullmove)),
+                Color::Black => (),
+            }
+            line.push(p.san(m));
+            p.make_move(m);
+        }
+        line.join(" ")
+    }
+
+    /// Parses `san` as a legal move in the position
+    ///
+    /// The check and checkmate suffixes are optional, and castling may be 
+    /// written with zeros (e.g. `0-0`). Pieces may be written with figurines
+    /// of either color (e.g. `♘f3`), as [`Role::from_figurine`] reads them.
+    ///
+    /// The move is the one [`generate`](Self::generate()) gives, so its 
+    /// capture, en passant and promotion are always filled in as for any 
+    /// generated move. A move that more than one legal move fits, e.g. `Nd2`
+    /// with knights on b1 and f1, is illegal.
+    pub fn parse_san(&self, san: &str) -> Result<Move, SanError> {
+        let san = normalize(san);
+        let is_san_char = |ch: char| "KQRBNabcdefgh12345678xO=-".contains(ch);
+        if !(2..=7).contains(&san.len()) || !san.chars().all(is_san_char) {
+            return Err(SanError::Syntax)
+        }
+        let castling_side = match san.as_str() {
+            "O-O" => Some(CastlingSide::Kingside),
+            "O-O-O" => Some(CastlingSide::Queenside),
+            _ => None,
+        };
+        let moves = self.generate().into_iter();
+        let mut candidates: Vec<Move> = match castling_side {
+            Some(castling_side) => moves.filter(|&m| m == Move::Castle { castling_side }).collect(),
+            None => {
+                let parts = SanParts::parse(&san).ok_or(SanError::Syntax)?;
+                moves.filter(|&m| parts.matches(self, m)).collect()
+            }
+        };
+        match candidates.len() {
+            1 => Ok(candidates.pop().unwrap()),
+            _ => Err(SanError::Illegal),
+        }
+    }
+
+    /// Parses `san` as a legal move in the position (see 
+    /// [`parse_san`](Self::parse_san())) and makes it, returning the move
+    pub fn push_san(&mut self, san: &str) -> Result<Move, SanError> {
+        let m = self.parse_san(san)?;
+        self.make_move(m);
+        Ok(m)
+    }
+}
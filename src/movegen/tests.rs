@@ -0,0 +1,1347 @@
+#[cfg(test)]
+mod attack_tests {
+    use crate::bits::{Bitboard, Square};
+    use crate::movegen::*;
+
+    #[test]
+    fn rook_attacks_blocked() {
+        // Rook on d4, blockers on d6 and f4
+        let occ = Bitboard::square(Square::new(43)) | Bitboard::square(Square::new(29));
+        let attacks = rook_attacks(Square::new(27), occ);
+        assert!(attacks.contains(Square::new(43)));
+        assert!(!attacks.contains(Square::new(51)));
+        assert!(attacks.contains(Square::new(29)));
+        assert!(!attacks.contains(Square::new(30)));
+        assert_eq!(attacks.count(), 2 + 2 + 3 + 3);
+    }
+
+    #[test]
+    fn bishop_attacks_empty() {
+        assert_eq!(bishop_attacks(Square::new(0), Bitboard::EMPTY).count(), 7);
+        assert_eq!(bishop_attacks(Square::new(27), Bitboard::EMPTY).count(), 13);
+    }
+
+    #[test]
+    fn between_squares() {
+        assert_eq!(between(Square::new(0), Square::new(3)), Bitboard::new(0b0110));
+        assert_eq!(between(Square::new(3), Square::new(0)), Bitboard::new(0b0110));
+        assert_eq!(between(Square::new(0), Square::new(63)).count(), 6);
+        assert_eq!(between(Square::new(0), Square::new(1)), Bitboard::EMPTY);
+        assert_eq!(between(Square::new(0), Square::new(10)), Bitboard::EMPTY);
+    }
+
+    #[test]
+    fn line_squares() {
+        assert_eq!(line(Square::new(9), Square::new(18)).count(), 8);
+        assert_eq!(line(Square::new(2), Square::new(5)), Bitboard::new(0xFF));
+        assert_eq!(line(Square::new(0), Square::new(10)), Bitboard::EMPTY);
+    }
+}
+
+#[cfg(test)]
+mod attackers_tests {
+    use crate::bits::{Bitboard, Square};
+    use crate::position::{Color, Position};
+
+    #[test]
+    fn current_occupancy() {
+        // Kiwipete
+        let p: Position = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1".parse().unwrap();
+        for s in (0..64).map(Square::new) {
+            for c in [Color::White, Color::Black] {
+                assert_eq!(p.attackers(s, c), p.attackers_to(s, c, p.board.all()));
+            }
+        }
+        // Only the knight on e5 attacks f7, until the queen on f3 looks 
+        // through the knight on f6
+        let f7 = Square::new(53);
+        let (e5, f3, f6) = (Square::new(36), Square::new(21), Square::new(45));
+        assert_eq!(p.attackers(f7, Color::White), Bitboard::square(e5));
+        let occ = p.board.all().without_square(f6);
+        assert_eq!(p.attackers_to(f7, Color::White, occ), Bitboard::square(e5).with_square(f3));
+    }
+}
+
+#[cfg(test)]
+mod attacks_by_tests {
+    use crate::bits::{Bitboard, Square};
+    use crate::position::{Color, Position};
+
+    #[test]
+    fn matches_attackers() {
+        let p: Position = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1".parse().unwrap();
+        for c in [Color::White, Color::Black] {
+            let expected = Square::iter()
+                .filter(|&s| p.attackers(s, c).is_any())
+                .fold(Bitboard::EMPTY, |b, s| b.with_square(s));
+            assert_eq!(p.attacked_squares(c), expected);
+            assert_eq!(p.attacks_by(c), expected);
+            // Again, from the cache
+            assert_eq!(p.attacks_by(c), expected);
+        }
+    }
+
+    #[test]
+    fn cleared_by_moves() {
+        let mut p = Position::default();
+        let before = p.attacks_by(Color::White);
+        let undo = p.make_move(p.parse_uci("e2e4").unwrap());
+        // The bishop and queen see out past e2
+        assert_ne!(p.attacks_by(Color::White), before);
+        assert_eq!(p.attacks_by(Color::White), p.attacked_squares(Color::White));
+        assert_eq!(p.attacks_by(Color::Black), p.attacked_squares(Color::Black));
+        p.unmake_move(undo);
+        assert_eq!(p.attacks_by(Color::White), before);
+
+        // Editing the board through the position clears the cache
+        p.board_mut().remove(Square::new(11));
+        assert_eq!(p.attacks_by(Color::White), p.attacked_squares(Color::White));
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(debug_assertions)]
+    fn stale_cache_is_caught() {
+        let mut p = Position::default();
+        p.attacks_by(Color::White);
+        p.board.remove(Square::new(11));
+        p.attacks_by(Color::White);
+    }
+}
+
+#[cfg(test)]
+mod check_tests {
+    use crate::position::Position;
+
+    /// Asserts that the checkers in every position within `depth` moves of
+    /// `p` are the king's attackers
+    fn assert_checkers(p: &mut Position, depth: u32) {
+        let king = p.board.king_square(p.turn);
+        let attackers = p.attackers_to(king, !p.turn, p.board.all());
+        assert_eq!(p.checkers(), attackers, "{}", p.fen());
+        assert_eq!(p.is_in_check(), attackers.is_any(), "{}", p.fen());
+        if depth == 0 {
+            return
+        }
+        for m in p.generate() {
+            let undo = p.make_move(m);
+            assert_checkers(p, depth - 1);
+            p.unmake_move(undo);
+        }
+    }
+
+    #[test]
+    fn match_attackers() {
+        for (fen, depth) in [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 3),
+            ("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 2),
+            ("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", 3),
+            ("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", 2),
+            ("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", 2),
+        ] {
+            assert_checkers(&mut fen.parse().unwrap(), depth);
+        }
+    }
+
+    #[test]
+    fn double_check() {
+        // The knight on f6 and the rook on e1 both check the king on e8
+        let p: Position = "4k3/8/5N2/8/8/8/8/4RK2 b - - 0 1".parse().unwrap();
+        assert_eq!(p.checkers().count(), 2);
+        assert!(p.is_in_check());
+    }
+}
+
+#[cfg(test)]
+mod discovered_tests {
+    use crate::bits::{Bitboard, Square};
+    use crate::position::Position;
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    #[test]
+    fn discovered_attackers() {
+        // The knight on d3 blocks the bishop on b1 and the rook on d1, but
+        // not the queen on h4
+        let p = parse("4k3/8/8/8/7Q/3N4/8/1B1RK3 w - - 0 1");
+        let expected = Bitboard::square(Square::new(1)) | Bitboard::square(Square::new(3));
+        assert_eq!(p.discovered_attackers(Square::new(19)), expected);
+        // The queen's moves don't uncover anything
+        assert_eq!(p.discovered_attackers(Square::new(31)), Bitboard::EMPTY);
+        // Nor does an empty square
+        assert_eq!(p.discovered_attackers(Square::new(20)), Bitboard::EMPTY);
+        // Black's pieces aren't white's to discover
+        let p = parse("4k3/8/8/8/8/3n4/8/1B1RK3 w - - 0 1");
+        assert_eq!(p.discovered_attackers(Square::new(19)), Bitboard::EMPTY);
+    }
+}
+
+#[cfg(test)]
+mod generate_tests {
+    use crate::bits::Square;
+    use crate::movegen::{Move, pawn_attacks};
+    use crate::position::{Piece, Position, Role};
+    use crate::position::castling::CastlingSide;
+
+    fn count(fen: &str) -> usize {
+        Position::from_fen_string(fen.to_string()).unwrap().generate().len()
+    }
+
+    #[test]
+    fn start_position() {
+        assert_eq!(Position::default().generate().len(), 20);
+    }
+
+    #[test]
+    fn standard_positions() {
+        // https://www.chessprogramming.org/Perft_Results
+        assert_eq!(count("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"), 48);
+        assert_eq!(count("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"), 14);
+        assert_eq!(count("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1"), 6);
+        assert_eq!(count("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8"), 44);
+        assert_eq!(count("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10"), 46);
+    }
+
+    #[test]
+    fn checkmate_and_stalemate() {
+        assert_eq!(count("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3"), 0);
+        assert_eq!(count("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"), 0);
+    }
+
+    #[test]
+    fn double_check() {
+        // Only king moves are legal
+        let p = Position::from_fen_string("4k3/8/8/8/1b6/8/4r3/R3K2R w KQ - 0 1".to_string()).unwrap();
+        assert_eq!(p.checkers().count(), 2);
+        assert!(p.generate().iter().all(|m| matches!(m, Move::Normal { role: crate::position::Role::King, .. })));
+    }
+
+    #[test]
+    fn pinned_pieces() {
+        let p = Position::from_fen_string("4k3/4r3/8/8/8/8/4N3/4K3 w - - 0 1".to_string()).unwrap();
+        assert_eq!(p.pinned(), crate::bits::Bitboard::square(Square::new(12)));
+        assert!(p.generate().iter().all(|m| m.role() == crate::position::Role::King));
+    }
+
+    #[test]
+    fn en_passant() {
+        let p = Position::from_fen_string("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1".to_string()).unwrap();
+        assert!(p.generate().iter().any(|m| m.is_en_passant()));
+
+        // Capturing en passant would expose the king along the rank, as both
+        // pawns leave it, which the pins of single pieces don't show
+        for fen in [
+            "8/8/8/K2pP2r/8/8/8/7k w - d6 0 1",
+            "8/8/8/K2pP2q/8/8/8/7k w - d6 0 1",
+            "7K/8/8/8/R2Pp2k/8/8/8 b - d3 0 1",
+        ] {
+            let p = Position::from_fen_string(fen.to_string()).unwrap();
+            assert!(p.pinned().is_empty(), "{}", fen);
+            assert!(!p.generate().iter().any(|m| m.is_en_passant()), "{}", fen);
+            let ep = p.en_passant.unwrap();
+            let from = pawn_attacks(!p.turn, ep) & p.board.piece(Piece(p.turn, Role::Pawn));
+            let m = Move::en_passant(from.smallest_square().unwrap(), ep);
+            assert!(!p.is_legal(m), "{}", fen);
+        }
+
+        // Capturing the checking pawn en passant evades the check
+        let p = Position::from_fen_string("8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1".to_string()).unwrap();
+        assert!(p.is_in_check());
+        let exd3 = Move::en_passant(Square::new(28), Square::new(19));
+        assert!(p.generate().contains(&exd3));
+        assert!(p.is_legal(exd3));
+    }
+
+    #[test]
+    fn castling() {
+        let p = Position::from_fen_string("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1".to_string()).unwrap();
+        let moves = p.generate();
+        assert!(moves.contains(&Move::Castle { castling_side: CastlingSide::Kingside }));
+        assert!(moves.contains(&Move::Castle { castling_side: CastlingSide::Queenside }));
+
+        // f1 is attacked, and b1 is blocked
+        let p = Position::from_fen_string("r3k2r/8/8/8/8/8/5r2/RN2K2R w KQkq - 0 1".to_string()).unwrap();
+        assert!(!p.generate().iter().any(|m| m.is_castle()));
+    }
+
+    #[test]
+    fn chess960_castling() {
+        let p = Position::from_fen_string("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9".to_string()).unwrap();
+        assert_eq!(p.generate().len(), 21);
+    }
+
+    #[test]
+    fn promotions() {
+        let p = Position::from_fen_string("1n2k3/P7/8/8/8/8/8/4K3 w - - 0 1".to_string()).unwrap();
+        let promotions = p.generate().into_iter().filter(|m| m.is_promotion()).count();
+        assert_eq!(promotions, 8);
+    }
+
+    #[test]
+    fn lazy_moves() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            // In check, and in double check
+            "4k3/8/8/8/8/8/3q4/R3K2R w KQ - 0 1",
+            "4k3/8/8/8/1b6/8/4r3/4K3 w - - 0 1",
+        ] {
+            let p = Position::from_fen_string(fen.to_string()).unwrap();
+            assert_eq!(p.moves().collect::<Vec<_>>(), p.generate(), "{}", fen);
+        }
+
+        // Taking one move only generates the pawn moves
+        let p = Position::from_fen_string("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1".to_string()).unwrap();
+        let mut moves = p.moves();
+        assert!(moves.next().is_some());
+        assert_eq!(moves.generated(), 8);
+        assert_eq!(moves.count(), 47);
+    }
+
+    #[test]
+    fn sorted() {
+        let p = Position::from_fen_string("4k3/1P6/8/8/8/8/8/4K2R w K - 0 1".to_string()).unwrap();
+        let sorted = p.legal_moves_sorted();
+        assert_eq!(sorted, p.legal_moves_sorted());
+        assert_eq!(sorted.len(), p.generate().len());
+
+        let king = |to: u32| Move::Normal { role: Role::King, from: Square::new(4), to: Square::new(to), capture: None };
+        let rook = |to: u32| Move::Normal { role: Role::Rook, from: Square::new(7), to: Square::new(to), capture: None };
+        let pawn = |promotion: Role| Move::PawnMove { from: Square::new(49), to: Square::new(57), promotion: Some(promotion), en_passant: false, capture: None };
+        let mut expected = vec![
+            king(3),
+            king(5),
+            Move::Castle { castling_side: CastlingSide::Kingside },
+            king(11),
+            king(12),
+            king(13),
+        ];
+        expected.extend([5, 6, 15, 23, 31, 39, 47, 55, 63].map(rook));
+        expected.extend([Role::Knight, Role::Bishop, Role::Rook, Role::Queen].map(pawn));
+        assert_eq!(sorted, expected);
+    }
+}
+
+#[cfg(test)]
+mod castle_tests {
+    use crate::movegen::{Move, MoveList};
+    use crate::position::Position;
+    use crate::position::castling::CastlingSide;
+
+    const KINGSIDE: Move = Move::Castle { castling_side: CastlingSide::Kingside };
+    const QUEENSIDE: Move = Move::Castle { castling_side: CastlingSide::Queenside };
+
+    fn castles(fen: &str) -> MoveList {
+        let p = Position::from_fen_string(fen.to_string()).unwrap();
+        let mut movelist = MoveList::new();
+        p.gen_castles(&mut movelist);
+        for &m in &movelist {
+            assert!(p.is_legal(m));
+        }
+        movelist
+    }
+
+    #[test]
+    fn both_sides() {
+        assert_eq!(castles("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1"), vec![KINGSIDE, QUEENSIDE]);
+        assert_eq!(castles("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1"), vec![KINGSIDE, QUEENSIDE]);
+        assert_eq!(castles("r3k2r/8/8/8/8/8/8/R3K2R w Kkq - 0 1"), vec![KINGSIDE]);
+        assert_eq!(castles("r3k2r/8/8/8/8/8/8/R3K2R w - - 0 1"), vec![]);
+    }
+
+    #[test]
+    fn blocked_path() {
+        // The bishop on f1 blocks the king, and the knight on b1 the rook
+        assert_eq!(castles("4k3/8/8/8/8/8/8/RN2KB1R w KQ - 0 1"), vec![]);
+        // Enemy pieces block too
+        assert_eq!(castles("4k3/8/8/8/8/8/8/R2nK1nR w KQ - 0 1"), vec![]);
+    }
+
+    #[test]
+    fn attacked_squares() {
+        // The king is in check
+        assert_eq!(castles("4k3/8/8/8/8/8/4r3/R3K2R w KQ - 0 1"), vec![]);
+        // The king would pass through f1, or land on c1
+        assert_eq!(castles("4k3/8/8/8/8/8/2r2r2/R3K2R w KQ - 0 1"), vec![]);
+        // Only the rook passes through b1, which may be attacked
+        assert_eq!(castles("4k3/8/8/8/8/8/1r6/R3K2R w KQ - 0 1"), vec![KINGSIDE, QUEENSIDE]);
+    }
+
+    #[test]
+    fn missing_rook() {
+        // The h1 rook was captured without the rights being updated
+        let p = Position::from_fen_string("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1".to_string()).unwrap();
+        let mut castling = p.castling;
+        castling.set(crate::position::Color::White, CastlingSide::Kingside, true);
+        let p = Position::from_parts(p.board, p.turn, castling, None, 0, 1);
+        let mut movelist = MoveList::new();
+        p.gen_castles(&mut movelist);
+        assert_eq!(movelist, vec![QUEENSIDE]);
+        // A knight stands where the rook should be
+        assert_eq!(castles("4k3/8/8/8/8/8/8/n3K2R w KQ - 0 1"), vec![KINGSIDE]);
+    }
+}
+
+#[cfg(test)]
+mod escape_tests {
+    use crate::bits::Square;
+    use crate::movegen::Move;
+    use crate::position::{Position, Role};
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    #[test]
+    fn hanging_queen() {
+        // The d5 queen is attacked by the d2 rook, which the king defends
+        let p = parse("4k3/8/8/3q4/8/1n6/3R4/4K3 b - - 0 1");
+        let queen = Square::new(35);
+        let escapes = p.moves_escaping(queen);
+        let legal = p.generate();
+
+        // Blocking or capturing the rook with the knight
+        let nd4 = Move::Normal { role: Role::Knight, from: Square::new(17), to: Square::new(27), capture: None };
+        let nxd2 = Move::Normal { role: Role::Knight, from: Square::new(17), to: Square::new(11), capture: Some(Role::Rook) };
+        assert!(escapes.contains(&nd4));
+        assert!(escapes.contains(&nxd2));
+
+        // Moving the queen off the d-file
+        let qa5 = Move::Normal { role: Role::Queen, from: queen, to: Square::new(32), capture: None };
+        assert!(escapes.contains(&qa5));
+
+        // Capturing the defended rook or staying on the file doesn't help
+        let qxd2 = Move::Normal { role: Role::Queen, from: queen, to: Square::new(11), capture: Some(Role::Rook) };
+        let qd4 = Move::Normal { role: Role::Queen, from: queen, to: Square::new(27), capture: None };
+        assert!(legal.contains(&qxd2) && !escapes.contains(&qxd2));
+        assert!(legal.contains(&qd4) && !escapes.contains(&qd4));
+
+        // King moves leave the queen hanging
+        assert!(escapes.iter().all(|m| m.role() != Role::King));
+        assert!(escapes.iter().all(|m| legal.contains(m)));
+    }
+
+    #[test]
+    fn not_threatened() {
+        let p = parse("4k3/8/8/3q4/8/8/8/4K3 b - - 0 1");
+        let escapes = p.moves_escaping(Square::new(35));
+        // Every move but Qd2 and Qd1, which walk into the king
+        assert_eq!(escapes.len(), p.generate().len() - 2);
+        let (d1, d2) = (Square::new(3), Square::new(11));
+        assert!(escapes.iter().all(|m| !matches!(m, Move::Normal { to, .. } if *to == d1 || *to == d2)));
+    }
+
+    #[test]
+    fn en_passant_opens_line() {
+        // Taking on d6 en passant clears the fifth rank for the h5 rook
+        let p = parse("4k3/4b3/8/2NpP2r/8/8/8/4K3 w - d6 0 1");
+        let escapes = p.moves_escaping(Square::new(34));
+        let exd6 = Move::PawnMove { from: Square::new(36), to: Square::new(43), promotion: None, en_passant: true, capture: Some(Role::Pawn) };
+        assert!(p.generate().contains(&exd6));
+        assert!(!escapes.contains(&exd6));
+    }
+
+    #[test]
+    fn not_own_piece() {
+        let p = parse("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1");
+        assert!(p.moves_escaping(Square::new(35)).is_empty());
+        assert!(p.moves_escaping(Square::new(20)).is_empty());
+    }
+}
+
+#[cfg(test)]
+mod perft_tests {
+    use crate::movegen::perft::PerftStats;
+    use crate::position::Position;
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    #[test]
+    fn start_position() {
+        let mut p = Position::default();
+        assert_eq!(p.perft(1), 20);
+        assert_eq!(p.perft(2), 400);
+        assert_eq!(p.perft(3), 8902);
+    }
+
+    #[test]
+    fn standard_positions() {
+        // https://www.chessprogramming.org/Perft_Results
+        assert_eq!(parse(KIWIPETE).perft(3), 97862);
+        assert_eq!(parse("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").perft(4), 43238);
+        assert_eq!(parse("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1").perft(3), 9467);
+        assert_eq!(parse("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8").perft(3), 62379);
+        assert_eq!(parse("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10").perft(3), 89890);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_perft() {
+        let mut p = Position::default();
+        assert_eq!(p.par_perft(5), p.perft(5));
+        assert_eq!(parse(KIWIPETE).par_perft(1), 48);
+    }
+
+    #[test]
+    fn unchecked() {
+        let mut p = parse(KIWIPETE);
+        assert_eq!(p.perft_unchecked(3), p.perft(3));
+        assert_eq!(Position::default().perft_unchecked(3), 8902);
+
+        // Making and unmaking moves gives the same positions either way
+        let mut checked = parse(KIWIPETE);
+        for m in p.generate() {
+            let undo = p.make_move_unchecked(m);
+            let checked_undo = checked.make_move(m);
+            assert_eq!(p.fen(), checked.fen());
+            assert_eq!(p.zobrist_key(), checked.zobrist_key());
+            p.unmake_move_unchecked(undo);
+            checked.unmake_move(checked_undo);
+            assert_eq!(p, checked);
+        }
+    }
+
+    #[test]
+    fn naive() {
+        use crate::position::board::without_verification;
+        let mut p = Position::default();
+        // Verifying the board at every leaf is too slow in debug builds
+        let naive = without_verification(|| p.perft_naive(4));
+        assert_eq!(naive, p.perft_unchecked(4));
+        assert_eq!(naive, 197281);
+        assert_eq!(parse(KIWIPETE).perft_naive(2), 2039);
+        assert_eq!(p.perft_naive(0), 1);
+    }
+
+    #[test]
+    fn chess960() {
+        let mut p = parse("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9");
+        assert_eq!(p.perft(3), 12189);
+    }
+
+    #[test]
+    fn kiwipete_stats() {
+        let stats = parse(KIWIPETE).perft_stats(2);
+        assert_eq!(stats, PerftStats {
+            nodes: 2039,
+            captures: 351,
+            en_passants: 1,
+            castles: 91,
+            promotions: 0,
+            checks: 3,
+            checkmates: 0,
+        });
+    }
+
+    #[test]
+    fn start_position_stats() {
+        let stats = Position::default().perft_stats(4);
+        assert_eq!(stats.nodes, 197281);
+        assert_eq!(stats.captures, 1576);
+        assert_eq!(stats.checks, 469);
+        assert_eq!(stats.checkmates, 8);
+    }
+}
+
+#[cfg(test)]
+mod pawn_tests {
+    use crate::bits::Square;
+    use crate::movegen::{Move, MoveList, PROMOTIONS};
+    use crate::position::{Position, Role};
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    fn pawn_moves(fen: &str) -> MoveList {
+        let mut movelist = MoveList::new();
+        parse(fen).gen_pawn_moves(&mut movelist);
+        movelist
+    }
+
+    fn pawn_move(from: u32, to: u32, promotion: Option<Role>, capture: Option<Role>) -> Move {
+        Move::PawnMove { from: Square::new(from), to: Square::new(to), promotion, en_passant: false, capture }
+    }
+
+    fn en_passant(from: u32, to: u32) -> Move {
+        Move::PawnMove { from: Square::new(from), to: Square::new(to), promotion: None, en_passant: true, capture: Some(Role::Pawn) }
+    }
+
+    fn assert_same(mut actual: MoveList, mut expected: MoveList) {
+        let key = |m: &Move| format!("{:?}", m);
+        actual.sort_by_key(key);
+        expected.sort_by_key(key);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn pushes() {
+        // e2 may push one or two squares, d3 only one, and g2 is blocked
+        let moves = pawn_moves("4k3/8/8/8/8/3P2n1/4P1P1/4K3 w - - 0 1");
+        assert_same(moves, vec![
+            pawn_move(12, 20, None, None),
+            Move::DoublePawnPush { from: Square::new(12), to: Square::new(28) },
+            pawn_move(19, 27, None, None),
+        ]);
+        // A double push needs both squares empty
+        let moves = pawn_moves("4k3/8/8/8/4n3/8/4P3/4K3 w - - 0 1");
+        assert_same(moves, vec![pawn_move(12, 20, None, None)]);
+        // Black pushes down the board
+        let moves = pawn_moves("4k3/4p3/8/8/8/8/8/4K3 b - - 0 1");
+        assert_same(moves, vec![
+            pawn_move(52, 44, None, None),
+            Move::DoublePawnPush { from: Square::new(52), to: Square::new(36) },
+        ]);
+    }
+
+    #[test]
+    fn promotions() {
+        // a7 is blocked on a8, so it can only capture on b8, without wrapping
+        // around to h-file squares
+        let moves = pawn_moves("rn2k2r/P7/8/8/8/8/8/4K3 w - - 0 1");
+        let expected = [Role::Queen, Role::Rook, Role::Bishop, Role::Knight]
+            .into_iter()
+            .map(|r| pawn_move(48, 57, Some(r), Some(Role::Knight)))
+            .collect();
+        assert_same(moves, expected);
+        // h2 promotes by pushing to h1 and by capturing on g1
+        let moves = pawn_moves("4k3/8/8/8/8/8/7p/4K1N1 b - - 0 1");
+        let expected = [Role::Queen, Role::Rook, Role::Bishop, Role::Knight]
+            .into_iter()
+            .flat_map(|r| [pawn_move(15, 7, Some(r), None), pawn_move(15, 6, Some(r), Some(Role::Knight))])
+            .collect();
+        assert_same(moves, expected);
+    }
+
+    #[test]
+    fn en_passants() {
+        // White takes on f6 from e5, but the c5 pawn isn't next to f5
+        let moves = pawn_moves("4k3/8/8/2P1Pp2/8/8/8/4K3 w - f6 0 1");
+        assert!(moves.contains(&en_passant(36, 45)));
+        assert_eq!(moves.iter().filter(|m| matches!(m, Move::PawnMove { en_passant: true, .. })).count(), 1);
+        // Black takes on e3 from both sides
+        let moves = pawn_moves("4k3/8/8/8/3pPp2/8/8/4K3 b - e3 0 1");
+        assert!(moves.contains(&en_passant(27, 20)));
+        assert!(moves.contains(&en_passant(29, 20)));
+        // Without an en passant square, no en passant is generated
+        let moves = pawn_moves("4k3/8/8/8/3pPp2/8/8/4K3 b - - 0 1");
+        assert!(!moves.iter().any(|m| matches!(m, Move::PawnMove { en_passant: true, .. })));
+    }
+
+    #[test]
+    fn in_check() {
+        // No pawn can block the rook or take it, nor may one take the knight
+        let moves = pawn_moves("4k3/8/8/8/8/2n5/1P1P4/4K2r w - - 0 1");
+        assert!(moves.is_empty());
+        // Only the captures of the checking knight are legal
+        let moves = pawn_moves("4k3/8/8/8/8/3n4/2P1P3/4K3 w - - 0 1");
+        assert_same(moves, vec![
+            pawn_move(10, 19, None, Some(Role::Knight)),
+            pawn_move(12, 19, None, Some(Role::Knight)),
+        ]);
+    }
+
+    #[test]
+    fn queen_first() {
+        // The b7 pawn can push to b8 or take the rook on a8
+        let p = parse("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1");
+        let mut movelist = MoveList::new();
+        p.gen_pawn_moves(&mut movelist);
+        let pushes: Vec<Role> = movelist.iter()
+            .filter(|m| !m.is_capture())
+            .filter_map(|m| m.promotion())
+            .collect();
+        assert_eq!(pushes, PROMOTIONS);
+        let captures: Vec<Role> = movelist.iter()
+            .filter(|m| m.is_capture())
+            .filter_map(|m| m.promotion())
+            .collect();
+        assert_eq!(captures, PROMOTIONS);
+        assert_eq!(PROMOTIONS[0], Role::Queen);
+    }
+}
+
+#[cfg(test)]
+mod legal_tests {
+    use crate::bits::Square;
+    use crate::movegen::Move;
+    use crate::position::{Position, Role};
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    #[test]
+    fn nonexistent_piece() {
+        let p = Position::default();
+        let nd4 = Move::Normal { role: Role::Knight, from: Square::new(19), to: Square::new(29), capture: None };
+        assert!(!p.is_legal(nd4));
+        // A knight, but claiming to be a bishop
+        let bc3 = Move::Normal { role: Role::Bishop, from: Square::new(1), to: Square::new(18), capture: None };
+        assert!(!p.is_legal(bc3));
+        // A black pawn, with white to move
+        let e5 = Move::DoublePawnPush { from: Square::new(52), to: Square::new(36) };
+        assert!(!p.is_legal(e5));
+    }
+
+    #[test]
+    fn leaves_king_in_check() {
+        // The e2 knight is pinned by the e7 rook
+        let p = parse("4k3/4r3/8/8/8/8/4N3/4K3 w - - 0 1");
+        let nc3 = Move::Normal { role: Role::Knight, from: Square::new(12), to: Square::new(18), capture: None };
+        assert!(!p.is_legal(nc3));
+        // The king can't step along the rook's file
+        let ke2 = Move::Normal { role: Role::King, from: Square::new(4), to: Square::new(12), capture: Some(Role::Knight) };
+        assert!(!p.is_legal(ke2));
+        let kd2 = Move::Normal { role: Role::King, from: Square::new(4), to: Square::new(11), capture: None };
+        assert!(p.is_legal(kd2));
+    }
+
+    #[test]
+    fn legal_moves() {
+        let p = Position::default();
+        let nf3 = Move::Normal { role: Role::Knight, from: Square::new(6), to: Square::new(21), capture: None };
+        let e4 = Move::DoublePawnPush { from: Square::new(12), to: Square::new(28) };
+        let e3 = Move::PawnMove { from: Square::new(12), to: Square::new(20), promotion: None, en_passant: false, capture: None };
+        assert!(p.is_legal(nf3) && p.is_legal(e4) && p.is_legal(e3));
+        // Pushing as a single move, or pushing too far
+        assert!(!p.is_legal(Move::PawnMove { from: Square::new(12), to: Square::new(28), promotion: None, en_passant: false, capture: None }));
+        assert!(!p.is_legal(Move::DoublePawnPush { from: Square::new(12), to: Square::new(20) }));
+    }
+
+    #[test]
+    fn agrees_with_generate() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        ] {
+            let mut p = parse(fen);
+            let legal = p.generate();
+            assert!(legal.iter().all(|&m| p.is_legal(m)), "{}", fen);
+
+            // Our moves from two plies later make for plausible candidates,
+            // many of which are illegal here
+            let mut candidates = Vec::new();
+            for m in legal.clone() {
+                let undo = p.make_move(m);
+                for reply in p.generate() {
+                    let undo = p.make_move(reply);
+                    candidates.extend(p.generate());
+                    p.unmake_move(undo);
+                }
+                p.unmake_move(undo);
+            }
+            for m in candidates {
+                assert_eq!(p.is_legal(m), legal.contains(&m), "{} {:?}", fen, m);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod move_tests {
+    use crate::bits::Square;
+    use crate::movegen::Move;
+    use crate::position::{Position, Role, castling::CastlingSide};
+
+    fn sq(s: u32) -> Square {
+        Square::new(s)
+    }
+
+    #[test]
+    fn constructors() {
+        assert_eq!(
+            Move::quiet(Role::Knight, sq(6), sq(21)),
+            Move::Normal { role: Role::Knight, from: sq(6), to: sq(21), capture: None },
+        );
+        assert_eq!(
+            Move::quiet(Role::Pawn, sq(12), sq(20)),
+            Move::PawnMove { from: sq(12), to: sq(20), promotion: None, en_passant: false, capture: None },
+        );
+        assert_eq!(
+            Move::capturing(Role::Queen, sq(3), sq(59), Role::Queen),
+            Move::Normal { role: Role::Queen, from: sq(3), to: sq(59), capture: Some(Role::Queen) },
+        );
+        assert_eq!(
+            Move::capturing(Role::Pawn, sq(28), sq(35), Role::Knight),
+            Move::PawnMove { from: sq(28), to: sq(35), promotion: None, en_passant: false, capture: Some(Role::Knight) },
+        );
+        assert_eq!(
+            Move::promoting(sq(49), sq(56), Role::Queen, Some(Role::Rook)),
+            Move::PawnMove { from: sq(49), to: sq(56), promotion: Some(Role::Queen), en_passant: false, capture: Some(Role::Rook) },
+        );
+        assert_eq!(Move::double_push(sq(12), sq(28)), Move::DoublePawnPush { from: sq(12), to: sq(28) });
+        assert_eq!(
+            Move::en_passant(sq(36), sq(43)),
+            Move::PawnMove { from: sq(36), to: sq(43), promotion: None, en_passant: true, capture: Some(Role::Pawn) },
+        );
+        assert_eq!(Move::castle(CastlingSide::Queenside), Move::Castle { castling_side: CastlingSide::Queenside });
+    }
+
+    #[test]
+    fn match_generated() {
+        let p: Position = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1".parse().unwrap();
+        for m in [
+            Move::quiet(Role::Pawn, sq(35), sq(43)),
+            Move::capturing(Role::Pawn, sq(35), sq(44), Role::Pawn),
+            Move::capturing(Role::Knight, sq(36), sq(53), Role::Pawn),
+            Move::double_push(sq(8), sq(24)),
+            Move::castle(CastlingSide::Kingside),
+            Move::castle(CastlingSide::Queenside),
+        ] {
+            assert!(p.is_legal(m), "{:?}", m);
+        }
+        let p: Position = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1".parse().unwrap();
+        assert!(p.is_legal(Move::en_passant(sq(36), sq(43))));
+    }
+}
+
+#[cfg(test)]
+mod targets_tests {
+    use crate::bits::{Bitboard, Square};
+    use crate::position::Position;
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    #[test]
+    fn all_match_each() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/Pp2P3/2N2Q1p/1PPBBPPP/R3K2R b KQkq a3 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        ] {
+            let p = parse(fen);
+            let all = p.legal_targets_all();
+            for from in Square::iter() {
+                assert_eq!(all[from.index()], p.legal_targets(from), "{} in {}", from, fen);
+            }
+        }
+    }
+
+    #[test]
+    fn castling_and_en_passant() {
+        let sq = |s: &str| Square::iter().find(|x| x.to_string() == s).unwrap();
+        let squares = |ss: &[&str]| ss.iter().fold(Bitboard::EMPTY, |b, s| b.with_square(sq(s)));
+
+        // The king's targets include castling on both sides
+        let p = parse("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        assert_eq!(p.legal_targets(sq("e1")), squares(&["d1", "f1", "d2", "e2", "f2", "c1", "g1"]));
+        // The a3 en passant capture is among the b4 pawn's targets
+        let p = parse("r3k2r/p1ppqpb1/bn2pnp1/3PN3/Pp2P3/2N2Q1p/1PPBBPPP/R3K2R b KQkq a3 0 1");
+        assert_eq!(p.legal_targets_all()[sq("b4").index()], squares(&["a3", "b3", "c3"]));
+        // Nothing moves from an empty square
+        assert_eq!(p.legal_targets(sq("d4")), Bitboard::EMPTY);
+    }
+}
+
+#[cfg(test)]
+mod only_move_tests {
+    use crate::position::Position;
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    #[test]
+    fn one_move() {
+        // The king must take the checking queen
+        let p = parse("7k/8/8/8/8/8/6q1/7K w - - 0 1");
+        let only = p.only_move().unwrap();
+        assert_eq!(p.uci(only), "h1g2");
+        // The rook must block the check
+        let p = parse("7k/8/8/8/8/2R5/6r1/K6r w - - 0 1");
+        assert_eq!(p.only_move().map(|m| p.uci(m)).as_deref(), Some("c3c1"));
+        // Only the pawn can move, one square
+        let p = parse("k7/8/8/8/p7/8/P1q5/K7 w - - 0 1");
+        assert_eq!(p.only_move().map(|m| p.uci(m)).as_deref(), Some("a2a3"));
+    }
+
+    #[test]
+    fn agrees_with_generate() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "7k/8/8/8/8/8/6q1/7K w - - 0 1",
+            "7k/8/8/8/8/2R5/6r1/K6r w - - 0 1",
+            "k7/8/8/8/p7/8/P1q5/K7 w - - 0 1",
+            // Checkmate and stalemate
+            "6k1/5ppp/8/8/8/8/8/R5K1 b - - 0 1",
+            "7k/5Q2/6K1/8/8/8/8/8 b - - 0 1",
+            // Only the pawn can move, one or two squares
+            "k7/8/8/8/8/8/P1q5/K7 w - - 0 1",
+        ] {
+            let p = parse(fen);
+            let moves = p.generate();
+            let expected = if moves.len() == 1 { Some(moves[0]) } else { None };
+            assert_eq!(p.only_move(), expected, "{}", fen);
+        }
+    }
+}
+
+#[cfg(test)]
+mod canonical_tests {
+    use crate::bits::Square;
+    use crate::movegen::{CanonicalMove, Move};
+    use crate::position::{Position, Role, castling::CastlingSide};
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    fn cm(from: u32, to: u32, promotion: Option<Role>) -> CanonicalMove {
+        CanonicalMove { from: Square::new(from), to: Square::new(to), promotion }
+    }
+
+    #[test]
+    fn equivalent_moves() {
+        let p = parse("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        // Nxf7, with and without its capture filled in
+        let generated = Move::Normal { role: Role::Knight, from: Square::new(36), to: Square::new(53), capture: Some(Role::Pawn) };
+        let bare = Move::Normal { role: Role::Knight, from: Square::new(36), to: Square::new(53), capture: None };
+        assert_eq!(generated.canonical(&p), bare.canonical(&p));
+        assert_eq!(p.find_move(bare.canonical(&p)), Some(generated));
+        assert!(!p.is_legal(bare));
+
+        // A double push as a single pawn move
+        let double = Move::DoublePawnPush { from: Square::new(8), to: Square::new(24) };
+        let single = Move::PawnMove { from: Square::new(8), to: Square::new(24), promotion: None, en_passant: false, capture: None };
+        assert_eq!(double.canonical(&p), single.canonical(&p));
+        assert_eq!(p.find_move(cm(8, 24, None)), Some(double));
+
+        // Castling, as the king's step and as the king's move in UCI
+        let castle = Move::Castle { castling_side: CastlingSide::Kingside };
+        let king = Move::Normal { role: Role::King, from: Square::new(4), to: Square::new(6), capture: None };
+        assert_eq!(castle.canonical(&p), king.canonical(&p));
+        assert_eq!(p.parse_uci("e1g1").unwrap().canonical(&p), cm(4, 6, None));
+        assert_eq!(p.parse_san("O-O").unwrap().canonical(&p), castle.canonical(&p));
+        assert_eq!(p.find_move(cm(4, 6, None)), Some(castle));
+
+        assert_eq!(p.find_move(cm(4, 20, None)), None);
+    }
+
+    #[test]
+    fn promotions() {
+        let p = parse("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1");
+        let queen = p.parse_uci("b7b8q").unwrap();
+        assert_eq!(queen.canonical(&p), cm(49, 57, Some(Role::Queen)));
+        assert_ne!(queen.canonical(&p), p.parse_uci("b7b8n").unwrap().canonical(&p));
+        assert_eq!(p.find_move(cm(49, 57, None)), None);
+    }
+}
+
+#[cfg(test)]
+mod magic_tests {
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    use crate::bits::{Bitboard, Square};
+    use crate::movegen::SlidingBackend;
+    use crate::movegen::magic::*;
+    use crate::position::Role;
+
+    #[test]
+    fn same_seed_same_magics() {
+        for (r, s, index_bits) in [(Role::Rook, Square::new(0), 12), (Role::Bishop, Square::new(27), 9)] {
+            let (a, a_table) = find_magic(r, s, index_bits, &mut StdRng::seed_from_u64(DEFAULT_SEED));
+            let (b, b_table) = find_magic(r, s, index_bits, &mut StdRng::seed_from_u64(DEFAULT_SEED));
+            assert_eq!(a, b);
+            assert_eq!(a_table, b_table);
+        }
+        let (c, _) = find_magic(Role::Bishop, Square::new(27), 9, &mut StdRng::seed_from_u64(DEFAULT_SEED + 1));
+        let (d, _) = find_magic(Role::Bishop, Square::new(27), 9, &mut StdRng::seed_from_u64(DEFAULT_SEED));
+        assert_ne!(c.magic, d.magic);
+    }
+
+    #[test]
+    fn shared_table() {
+        let tables = shared_tables();
+
+        // a1 sees b2 to g7, and d4 sees 9 squares off the edges
+        assert_eq!(tables.bishop[0].mask.count(), 6);
+        assert_eq!(tables.bishop[27].index_bits, 9);
+
+        // The per-square tables are packed back to back
+        let entries = tables.rook.iter().chain(&tables.bishop);
+        let total: usize = entries.clone().map(|e| e.table_len()).sum();
+        assert_eq!(tables.table.len(), total);
+        assert_eq!(total, 102400 + 5248);
+        let mut offset = 0;
+        for entry in entries {
+            assert_eq!(entry.offset, offset);
+            offset += entry.table_len();
+        }
+
+        // Every blocker configuration of a square finds its moves in the
+        // square's part of the shared table
+        for s in [Square::new(0), Square::new(27), Square::new(63)] {
+            let rook = tables.rook[usize::from(s)];
+            for blockers in rook.mask.subsets() {
+                assert_eq!(tables.rook_attacks(s, blockers), SlidingBackend::Classical.rook_attacks(s, blockers));
+            }
+            let bishop = tables.bishop[usize::from(s)];
+            for blockers in bishop.mask.subsets() {
+                assert_eq!(tables.bishop_attacks(s, blockers), SlidingBackend::Classical.bishop_attacks(s, blockers));
+            }
+        }
+
+        // And the attacks match the classical generators
+        let mut rng = StdRng::seed_from_u64(DEFAULT_SEED);
+        for _ in 0..1000 {
+            let s = Square::new(rng.gen_range(0..64));
+            let occ = Bitboard::new(rng.gen::<u64>() & rng.gen::<u64>());
+            assert_eq!(rook_attacks(s, occ), SlidingBackend::Classical.rook_attacks(s, occ));
+            assert_eq!(bishop_attacks(s, occ), SlidingBackend::Classical.bishop_attacks(s, occ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod san_tests {
+    use crate::bits::Square;
+    use crate::movegen::Move;
+    use crate::movegen::san::SanError;
+    use crate::position::{Position, Role};
+    use crate::position::castling::CastlingSide;
+
+    const START: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    fn double(from: u32, to: u32) -> Move {
+        Move::DoublePawnPush { from: Square::new(from), to: Square::new(to) }
+    }
+
+    #[test]
+    fn san() {
+        // Knights on b1 and f1 can both reach d2
+        let p = parse("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1");
+        assert_eq!(p.san(Move::normal(Role::Knight, 1, 11, None)), "Nbd2");
+        // Rooks on a1 and a5 can both reach a3
+        let p = parse("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1");
+        assert_eq!(p.san(Move::normal(Role::Rook, 0, 16, None)), "R1a3");
+
+        let p = parse("r3k3/1P6/8/8/8/8/8/4K2R w K - 0 1");
+        let bxa8 = Move::PawnMove { from: Square::new(49), to: Square::new(56), promotion: Some(Role::Queen), en_passant: false, capture: Some(Role::Rook) };
+        assert_eq!(p.san(bxa8), "bxa8=Q+");
+        assert_eq!(p.san(Move::Castle { castling_side: CastlingSide::Kingside }), "O-O");
+
+        // Fool's mate
+        let p = parse("rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2");
+        assert_eq!(p.san(Move::normal(Role::Queen, 59, 31, None)), "Qh4#");
+    }
+
+    #[test]
+    fn pinned_not_ambiguous() {
+        // Rooks on d1 and d7 could both reach d4, but the one on d7 is pinned
+        // to the king by the rook on h7
+        let p = parse("8/K2R3r/8/7k/8/8/8/3R4 w - - 0 1");
+        let rd4 = Move::normal(Role::Rook, 3, 27, None);
+        assert_eq!(p.san(rd4), "Rd4");
+        assert_eq!(p.parse_san("Rd4"), Ok(rd4));
+        // Without the pin, it needs the rank
+        let p = parse("8/K2R4/8/7k/8/8/8/3R4 w - - 0 1");
+        assert_eq!(p.san(rd4), "R1d4");
+    }
+
+    #[test]
+    fn san_line() {
+        let p = parse(START);
+        let line = [
+            double(12, 28),
+            double(52, 36),
+            Move::normal(Role::Knight, 6, 21, None),
+            Move::normal(Role::Knight, 57, 42, None),
+            Move::normal(Role::Bishop, 5, 33, None),
+        ];
+        assert_eq!(p.san_line(&line), "1. e4 e5 2. Nf3 Nc6 3. Bb5");
+
+        // Starting on black's move
+        let mut p = parse(START);
+        p.make_move(line[0]);
+        assert_eq!(p.san_line(&line[1..4]), "1... e5 2. Nf3 Nc6");
+        assert_eq!(p.san_line(&[]), "");
+    }
+
+    #[test]
+    fn push_san() {
+        let mut p = parse(START);
+        for san in ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Bxc6", "dxc6", "0-0", "Bg4"] {
+            p.push_san(san).unwrap();
+        }
+        assert_eq!(p.fen(), "r2qkbnr/1pp2ppp/p1p5/4p3/4P1b1/5N2/PPPP1PPP/RNBQ1RK1 w kq - 2 6");
+        let h3 = Move::PawnMove { from: Square::new(15), to: Square::new(23), promotion: None, en_passant: false, capture: None };
+        assert_eq!(p.push_san("h3!?"), Ok(h3));
+        assert_eq!(p.parse_san("Bxf7"), Err(SanError::Illegal));
+        assert_eq!(p.parse_san("Nbd7"), Err(SanError::Illegal));
+        assert_eq!(p.parse_san("hello"), Err(SanError::Syntax));
+        assert_eq!(p.parse_san(""), Err(SanError::Syntax));
+    }
+
+    #[test]
+    fn figurines() {
+        let p = parse(START);
+        assert_eq!(p.parse_san("♘f3"), p.parse_san("Nf3"));
+        assert_eq!(p.parse_san("♞f3"), p.parse_san("Nf3"));
+        assert_eq!(p.parse_san("♙e4"), p.parse_san("e4"));
+        assert!(p.parse_san("♘f3").is_ok());
+
+        let p = parse("3k4/4p3/8/8/8/8/8/4QK2 w - - 0 1");
+        assert_eq!(p.parse_san("♕xe7+"), p.parse_san("Qxe7+"));
+        assert_eq!(p.parse_san("♕xe7+"), Ok(Move::normal(Role::Queen, 4, 52, Some(Role::Pawn))));
+        assert_eq!(p.parse_san("♔xe7"), Err(SanError::Illegal));
+
+        assert_eq!(Role::from_figurine('♗'), Some(Role::Bishop));
+        assert_eq!(Role::from_figurine('♜'), Some(Role::Rook));
+        assert_eq!(Role::from_figurine('R'), None);
+    }
+
+    #[test]
+    fn parse_parts() {
+        // Knights on b1 and f1 can both reach d2
+        let p = parse("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1");
+        assert_eq!(p.parse_san("Nd2"), Err(SanError::Illegal));
+        assert_eq!(p.parse_san("Nbd2"), Ok(Move::normal(Role::Knight, 1, 11, None)));
+        assert_eq!(p.parse_san("N1d2"), Err(SanError::Illegal));
+        // A needless origin still fits one move
+        assert_eq!(p.parse_san("Nfg3"), Ok(Move::normal(Role::Knight, 5, 22, None)));
+        assert_eq!(p.parse_san("Nf1g3"), Ok(Move::normal(Role::Knight, 5, 22, None)));
+        // The capture marker must match
+        assert_eq!(p.parse_san("Nxg3"), Err(SanError::Illegal));
+
+        let p = parse(START);
+        for san in ["e2e4", "xe4", "e8Q", "e8=K", "Nf", "Ng1f3x"] {
+            assert_eq!(p.parse_san(san), Err(SanError::Syntax), "{}", san);
+        }
+        for san in ["O-O", "e4=Q", "exd3"] {
+            assert_eq!(p.parse_san(san), Err(SanError::Illegal), "{}", san);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        for fen in [
+            START,
+            // Kiwipete, with castling both ways and en passant
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/Pp2P3/2N2Q1p/1PPBBPPP/R3K2R b KQkq a3 0 1",
+            // Promotions, with and without capturing
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+            "1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1",
+            // Rooks and queens needing disambiguation by file, rank or both
+            "4k3/8/8/R7/8/8/8/R3K2R w K - 0 1",
+            "7k/8/8/8/Q1Q5/8/Q7/4K3 w - - 0 1",
+        ] {
+            let p = parse(fen);
+            for m in p.generate() {
+                let san = p.san(m);
+                assert_eq!(p.parse_san(&san), Ok(m), "{} in {}", san, fen);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod uci_tests {
+    use crate::movegen::uci::UciError;
+    use crate::position::Position;
+    use crate::position::castling::CastlingSide;
+    use crate::movegen::Move;
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    #[test]
+    fn push_uci() {
+        let mut p = parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        for uci in ["e2e4", "e7e5", "g1f3", "b8c6", "f1b5", "a7a6", "b5c6", "d7c6", "e1g1"] {
+            let m = p.parse_uci(uci).unwrap();
+            assert_eq!(p.uci(m), uci);
+            p.push_uci(uci).unwrap();
+        }
+        assert_eq!(p.fen(), "r1bqkbnr/1pp2ppp/p1p5/4p3/4P3/5N2/PPPP1PPP/RNBQ1RK1 b kq - 1 5");
+        assert_eq!(p.parse_uci("e8c8"), Err(UciError::Illegal));
+        assert_eq!(p.parse_uci("e8e9"), Err(UciError::Syntax));
+        assert_eq!(p.parse_uci("a6a5k"), Err(UciError::Syntax));
+    }
+
+    #[test]
+    fn castling() {
+        // The king may take its own rook in standard chess too
+        let p = parse("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        let o_o_o = Move::Castle { castling_side: CastlingSide::Queenside };
+        assert_eq!(p.parse_uci("e1a1"), Ok(o_o_o));
+        assert_eq!(p.parse_uci("e1c1"), Ok(o_o_o));
+        assert_eq!(p.uci(o_o_o), "e1c1");
+
+        // In Chess960, castling is always written as taking the rook
+        let p = parse("4k3/8/8/8/8/8/8/1R2K1R1 w GB - 0 1");
+        let o_o = Move::Castle { castling_side: CastlingSide::Kingside };
+        assert_eq!(p.uci(o_o), "e1g1");
+        assert_eq!(p.parse_uci("e1g1"), Ok(o_o));
+        let p = parse("rk5r/8/8/8/8/8/8/RK5R w HAha - 0 1");
+        assert_eq!(p.uci(o_o), "b1h1");
+        assert_eq!(p.parse_uci("b1h1"), Ok(o_o));
+    }
+
+    #[test]
+    fn promotion() {
+        let p = parse("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1");
+        let m = p.parse_uci("a7b8n").unwrap();
+        assert_eq!(p.uci(m), "a7b8n");
+        assert!(matches!(m, Move::PawnMove { promotion: Some(_), capture: Some(_), .. }));
+        assert_eq!(p.parse_uci("a7a8"), Err(UciError::Illegal));
+    }
+}
+
+#[cfg(test)]
+mod backend_tests {
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    use crate::bits::{Bitboard, Square};
+    use crate::movegen::SlidingBackend;
+
+    #[test]
+    fn backends_agree() {
+        let backends: Vec<SlidingBackend> = SlidingBackend::iter()
+            .filter(|b| b.is_available())
+            .collect();
+        assert!(backends.contains(&SlidingBackend::Classical));
+        assert!(backends.contains(&SlidingBackend::Magic));
+
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..2000 {
+            let s = Square::new(rng.gen_range(0..64));
+            // Sparse and dense occupancies
+            let occ = match rng.gen_range(0..3) {
+                0 => Bitboard::new(rng.gen::<u64>() & rng.gen::<u64>() & rng.gen::<u64>()),
+                1 => Bitboard::new(rng.gen::<u64>()),
+                _ => Bitboard::new(rng.gen::<u64>() | rng.gen::<u64>()),
+            };
+            let rook = SlidingBackend::Classical.rook_attacks(s, occ);
+            let bishop = SlidingBackend::Classical.bishop_attacks(s, occ);
+            for &b in &backends {
+                assert_eq!(b.rook_attacks(s, occ), rook, "{:?} {} {}", b, s, occ);
+                assert_eq!(b.bishop_attacks(s, occ), bishop, "{:?} {} {}", b, s, occ);
+            }
+        }
+    }
+
+    #[test]
+    fn default_backend() {
+        let backend = SlidingBackend::default_for_build();
+        assert!(backend.is_available());
+        if cfg!(feature = "no-magics") || !cfg!(feature = "magics") {
+            assert_eq!(backend, SlidingBackend::Classical);
+        }
+    }
+}
+
+#[cfg(test)]
+mod const_tests {
+    use crate::bits::Square;
+    use crate::movegen::util::*;
+
+    const fn tables_equal(a: &[u64; 64], b: &[u64; 64]) -> bool {
+        let mut i = 0;
+        while i < 64 {
+            if a[i] != b[i] {
+                return false
+            }
+            i += 1;
+        }
+        true
+    }
+
+    // Checked at compile time
+    const _: () = assert!(tables_equal(&leaper_table(&KNIGHT_OFFSETS), &KNIGHT_MOVE));
+    const _: () = assert!(tables_equal(&leaper_table(&KING_OFFSETS), &KING_MOVE));
+    const _: () = assert!(KNIGHT_MOVE[Square::new(0).index()].count_ones() == 2);
+    const _: () = assert!(KNIGHT_MOVE[Square::new(27).index()].count_ones() == 8);
+}
+
+#[cfg(test)]
+mod smoke_tests {
+    use crate::position::Position;
+
+    /// Asserts that the legal moves of `fen`, in UCI notation, are exactly
+    /// `expected` (in any order), listing the missing and unexpected moves if
+    /// not
+    fn assert_moves_eq(fen: &str, expected: &[&str]) {
+        let p: Position = fen.parse().unwrap();
+        let mut actual: Vec<String> = p.generate().into_iter().map(|m| p.uci(m)).collect();
+        actual.sort();
+        let mut expected: Vec<String> = expected.iter().map(|s| s.to_string()).collect();
+        expected.sort();
+        if actual != expected {
+            let missing: Vec<&String> = expected.iter().filter(|m| !actual.contains(m)).collect();
+            let unexpected: Vec<&String> = actual.iter().filter(|m| !expected.contains(m)).collect();
+            panic!(
+                "Moves differ in {}\n  missing:    {:?}\n  unexpected: {:?}\n  ({} generated, {} expected)",
+                fen, missing, unexpected, actual.len(), expected.len(),
+            );
+        }
+    }
+
+    #[test]
+    fn start_position() {
+        assert_moves_eq("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", &[
+            "a2a3", "a2a4", "b1a3", "b1c3", "b2b3", "b2b4", "c2c3", "c2c4", "d2d3", "d2d4",
+            "e2e3", "e2e4", "f2f3", "f2f4", "g1f3", "g1h3", "g2g3", "g2g4", "h2h3", "h2h4",
+        ]);
+    }
+
+    #[test]
+    fn kiwipete() {
+        assert_moves_eq("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", &[
+            "a1b1", "a1c1", "a1d1", "a2a3", "a2a4", "b2b3", "c3a4", "c3b1", "c3b5", "c3d1",
+            "d2c1", "d2e3", "d2f4", "d2g5", "d2h6", "d5d6", "d5e6", "e1c1", "e1d1", "e1f1",
+            "e1g1", "e2a6", "e2b5", "e2c4", "e2d1", "e2d3", "e2f1", "e5c4", "e5c6", "e5d3",
+            "e5d7", "e5f7", "e5g4", "e5g6", "f3d3", "f3e3", "f3f4", "f3f5", "f3f6", "f3g3",
+            "f3g4", "f3h3", "f3h5", "g2g3", "g2g4", "g2h3", "h1f1", "h1g1",
+        ]);
+    }
+
+    #[test]
+    fn en_passant() {
+        // Only the e-pawn just pushed, so it can be taken en passant but the
+        // c-pawn can't
+        assert_moves_eq("4k3/8/8/2pPp3/8/8/8/4K3 w - e6 0 1", &[
+            "d5d6", "d5e6", "e1d1", "e1d2", "e1e2", "e1f1", "e1f2",
+        ]);
+    }
+
+    #[test]
+    fn promotions() {
+        assert_moves_eq("1n2k3/P1P5/8/8/8/8/8/4K3 w - - 0 1", &[
+            "a7a8b", "a7a8n", "a7a8q", "a7a8r", "a7b8b", "a7b8n", "a7b8q", "a7b8r",
+            "c7b8b", "c7b8n", "c7b8q", "c7b8r", "c7c8b", "c7c8n", "c7c8q", "c7c8r",
+            "e1d1", "e1d2", "e1e2", "e1f1", "e1f2",
+        ]);
+    }
+
+    #[test]
+    fn pins() {
+        // The pawn on d2 is pinned by the bishop, and the knight on e2 by the
+        // rook, so only the king can move
+        assert_moves_eq("4k3/4r3/8/8/1b6/8/3PN3/4K3 w - - 0 1", &["e1d1", "e1f1", "e1f2"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "missing:    [\"e1e2\"]")]
+    fn reports_differences() {
+        assert_moves_eq("4k3/4r3/8/8/1b6/8/3PN3/4K3 w - - 0 1", &["e1d1", "e1e2", "e1f1", "e1f2"]);
+    }
+}
@@ -0,0 +1,132 @@
+//! Formatting and parsing moves in the long algebraic notation of the
+//! [UCI protocol](https://www.chessprogramming.org/UCI), e.g. `e2e4` or `e7e8q`
+
+use std::fmt::Display;
+
+use crate::{
+    bits::{
+        Coords,
+        File,
+        Rank,
+        Square,
+    },
+    movegen::{
+        CanonicalMove,
+        Move,
+    },
+    position::{
+        Color,
+        Piece,
+        Position,
+        Role,
+    },
+};
+
+/// An error from parsing a move in UCI notation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UciError {
+    /// The string isn't formatted as a UCI move
+    Syntax,
+    /// The string is a UCI move, but not a legal move in the position
+    Illegal,
+}
+
+impl Display for UciError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UciError::Syntax => write!(f, "Invalid UCI move"),
+            UciError::Illegal => write!(f, "Illegal move"),
+        }
+    }
+}
+
+/// Parses a square such as `e4`
+pub(super) fn parse_square(s: &str) -> Option<Square> {
+    let mut chs = s.chars();
+    let f = File::try_from(chs.next()?).ok()?;
+    let r = Rank::try_from(chs.next()?).ok()?;
+    Some(Square::from(Coords(f, r)))
+}
+
+/// Parses the lowercase letter of a promotion role
+fn parse_promotion(ch: char) -> Option<Role> {
+    match ch {
+        'n' => Some(Role::Knight),
+        'b' => Some(Role::Bishop),
+        'r' => Some(Role::Rook),
+        'q' => Some(Role::Queen),
+        _ => None,
+    }
+}
+
+/// # Notation methods
+impl Position {
+    /// Gets the origin, destination and promotion of the (legal) move `m`,
+    /// where castling is written as the king taking its own rook in Chess960
+    /// (e.g. `e1h1`) and as the king's two-square step otherwise (e.g. `e1g1`)
+    fn uci_parts(&self, m: Move, chess960: bool) -> CanonicalMove {
+        match m {
+            Move::Castle { castling_side } if chess960 => {
+                let from = self.board.king_square(self.turn);
+                let to_file = self.castling.rook_file(self.turn, castling_side)
+                    .unwrap_or(castling_side.standard_rook_file());
+                let to = Square::from(Coords(to_file, from.rank()));
+                CanonicalMove { from, to, promotion: None }
+            }
+            _ => m.canonical(self),
+        }
+    }
+
+    /// Formats the (legal) move `m` in UCI notation
+    ///
+    /// Castling is written as the king taking its own rook when the position
+    /// isn't a standard chess position, as in Chess960
+    pub fn uci(&self, m: Move) -> String {
+        let king = self.board.king_square(self.turn);
+        let chess960 = !self.castling.is_standard() || king.file() != File::E;
+        let CanonicalMove { from, to, promotion } = self.uci_parts(m, chess960);
+        let mut uci = format!("{}{}", from, to);
+        if let Some(r) = promotion {
+            uci.push(char::from(Piece(Color::Black, r)));
+        }
+        uci
+    }
+
+    /// Parses `uci` as a legal move in the position
+    ///
+    /// Castling may be written either as the king taking its own rook or, 
+    /// from the e-file, as the king's two-square step
+    pub fn parse_uci(&self, uci: &str) -> Result<Move, UciError> {
+        if !uci.is_ascii() || !(4..=5).contains(&uci.len()) {
+            return Err(UciError::Syntax)
+        }
+        let from = parse_square(&uci[0..2]).ok_or(UciError::Syntax)?;
+        let to = parse_square(&uci[2..4]).ok_or(UciError::Syntax)?;
+        let promotion = match uci[4..].chars().next() {
+            Some(ch) => Some(parse_promotion(ch).ok_or(UciError::Syntax)?),
+            None => None,
+        };
+        let cm = CanonicalMove { from, to, promotion };
+        // The two-square step is only read as castling from the e-file, where
+        // it can't be mistaken for a normal king move
+        let king_on_e = self.board.king_square(self.turn).file() == File::E;
+        self.generate()
+            .into_iter()
+            .find(|&m| match m {
+                Move::Castle { .. } => {
+                    self.uci_parts(m, true) == cm ||
+                    (king_on_e && m.canonical(self) == cm)
+                }
+                _ => m.canonical(self) == cm,
+            })
+            .ok_or(UciError::Illegal)
+    }
+
+    /// Parses `uci` as a legal move in the position (see
+    /// [`parse_uci`](Self::parse_uci())) and makes it, returning the move
+    pub fn push_uci(&mut self, uci: &str) -> Result<Move, UciError> {
+        let m = self.parse_uci(uci)?;
+        self.make_move(m);
+        Ok(m)
+    }
+}
@@ -1,4 +1,39 @@
-const KING_MOVE: [u64; 64] = [
+//! Precomputed tables of piece moves and rays
+
+use crate::bits::Square;
+
+/// The file and rank steps of a king
+pub const KING_OFFSETS: [(i32, i32); 8] = [
+    (-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1),
+];
+
+/// The file and rank jumps of a knight
+pub const KNIGHT_OFFSETS: [(i32, i32); 8] = [
+    (-1, -2), (1, -2), (-2, -1), (2, -1), (-2, 1), (2, 1), (-1, 2), (1, 2),
+];
+
+/// Builds the table of the squares a leaper (a king or knight) attacks from
+/// each square, given its file and rank `offsets`
+pub const fn leaper_table(offsets: &[(i32, i32)]) -> [u64; 64] {
+    let mut table = [0; 64];
+    let mut i = 0;
+    while i < Square::COUNT {
+        let from = Square::ALL[i];
+        let mut j = 0;
+        while j < offsets.len() {
+            let (files, ranks) = offsets[j];
+            if let Some(to) = from.offset(files, ranks) {
+                table[i] |= 1 << to.index();
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    table
+}
+
+/// The squares a king attacks from each square
+pub const KING_MOVE: [u64; 64] = [
     0x0000000000000302, 0x0000000000000705, 0x0000000000000E0A, 0x0000000000001C14, 0x0000000000003828, 0x0000000000007050, 0x000000000000E0A0, 0x000000000000C040,
     0x0000000000030203, 0x0000000000070507, 0x00000000000E0A0E, 0x00000000001C141C, 0x0000000000382838, 0x0000000000705070, 0x0000000000E0A0E0, 0x0000000000C040C0,
     0x0000000003020300, 0x0000000007050700, 0x000000000E0A0E00, 0x000000001C141C00, 0x0000000038283800, 0x0000000070507000, 0x00000000E0A0E000, 0x00000000C040C000,
@@ -9,7 +44,8 @@ const KING_MOVE: [u64; 64] = [
     0x0203000000000000, 0x0507000000000000, 0x0A0E000000000000, 0x141C000000000000, 0x2838000000000000, 0x5070000000000000, 0xA0E0000000000000, 0x40C0000000000000,
 ];
 
-const KNIGHT_MOVE: [u64; 64] = [
+/// The squares a knight attacks from each square
+pub const KNIGHT_MOVE: [u64; 64] = [
     0x0000000000020400, 0x0000000000050800, 0x00000000000A1100, 0x0000000000142200, 0x0000000000284400, 0x0000000000508800, 0x0000000000A01000, 0x0000000000402000,
     0x0000000002040004, 0x0000000005080008, 0x000000000A110011, 0x0000000014220022, 0x0000000028440044, 0x0000000050880088, 0x00000000A0100010, 0x0000000040200020,
     0x0000000204000402, 0x0000000508000805, 0x0000000A1100110A, 0x0000001422002214, 0x0000002844004428, 0x0000005088008850, 0x000000A0100010A0, 0x0000004020002040,
@@ -20,19 +56,24 @@ const KNIGHT_MOVE: [u64; 64] = [
     0x0004020000000000, 0x0008050000000000, 0x00110A0000000000, 0x0022140000000000, 0x0044280000000000, 0x0088500000000000, 0x0010A00000000000, 0x0020400000000000,
 ];
 
-const PAWN_PUSH_UP: [u64; 64] = [
+/// The squares a white pawn can push to from each square, including double pushes
+pub const PAWN_PUSH_UP: [u64; 64] = [
     0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000,
     0x0000000001010000, 0x0000000002020000, 0x0000000004040000, 0x0000000008080000, 0x0000000010100000, 0x0000000020200000, 0x0000000040400000, 0x0000000080800000,
     0x0000000001000000, 0x0000000002000000, 0x0000000004000000, 0x0000000008000000, 0x0000000010000000, 0x0000000020000000, 0x0000000040000000, 0x0000000080000000,
     0x0000000100000000, 0x0000000200000000, 0x0000000400000000, 0x0000000800000000, 0x0000001000000000, 0x0000002000000000, 0x0000004000000000, 0x0000008000000000,
     0x0000010000000000, 0x0000020000000000, 0x0000040000000000, 0x0000080000000000, 0x0000100000000000, 0x0000200000000000, 0x0000400000000000, 0x0000800000000000,
-    0x0001000000000000, 0x0002000000000000, 0x0004000000000000, 0x0008000000000000, 00010000000000000, 0x0020000000000000, 0x0040000000000000, 0x0080000000000000,
+    0x0001000000000000, 0x0002000000000000, 0x0004000000000000, 0x0008000000000000, 0x0010000000000000, 0x0020000000000000, 0x0040000000000000, 0x0080000000000000,
     0x0100000000000000, 0x0200000000000000, 0x0400000000000000, 0x0800000000000000, 0x1000000000000000, 0x2000000000000000, 0x4000000000000000, 0x8000000000000000,
     0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000,
 ];
 
-const PAWN_ATTACK_UP: [u64; 64] = [
-    0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000,
+/// The squares a white pawn attacks from each square
+///
+/// The first rank is filled in too, since the table is also used to find the
+/// black pawns attacking a square
+pub const PAWN_ATTACK_UP: [u64; 64] = [
+    0x0000000000000200, 0x0000000000000500, 0x0000000000000A00, 0x0000000000001400, 0x0000000000002800, 0x0000000000005000, 0x000000000000A000, 0x0000000000004000,
     0x0000000000020000, 0x0000000000050000, 0x00000000000A0000, 0x0000000000140000, 0x0000000000280000, 0x0000000000500000, 0x0000000000A00000, 0x0000000000400000,
     0x0000000002000000, 0x0000000005000000, 0x000000000A000000, 0x0000000014000000, 0x0000000028000000, 0x0000000050000000, 0x00000000A0000000, 0x0000000040000000,
     0x0000000200000000, 0x0000000500000000, 0x0000000A00000000, 0x0000001400000000, 0x0000002800000000, 0x0000005000000000, 0x000000A000000000, 0x0000004000000000,
@@ -42,7 +83,8 @@ const PAWN_ATTACK_UP: [u64; 64] = [
     0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000,
 ];
 
-const PAWN_PUSH_DOWN: [u64; 64] = [
+/// The squares a black pawn can push to from each square, including double pushes
+pub const PAWN_PUSH_DOWN: [u64; 64] = [
     0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000,
     0x0000000000000001, 0x0000000000000002, 0x0000000000000004, 0x0000000000000008, 0x0000000000000010, 0x0000000000000020, 0x0000000000000040, 0x0000000000000080,
     0x0000000000000100, 0x0000000000000200, 0x0000000000000400, 0x0000000000000800, 0x0000000000001000, 0x0000000000002000, 0x0000000000004000, 0x0000000000008000,
@@ -53,7 +95,11 @@ const PAWN_PUSH_DOWN: [u64; 64] = [
     0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000,
 ];
 
-const PAWN_ATTACK_DOWN: [u64; 64] = [
+/// The squares a black pawn attacks from each square
+///
+/// The eighth rank is filled in too, since the table is also used to find the
+/// white pawns attacking a square
+pub const PAWN_ATTACK_DOWN: [u64; 64] = [
     0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000,
     0x0000000000000002, 0x0000000000000005, 0x000000000000000A, 0x0000000000000014, 0x0000000000000028, 0x0000000000000050, 0x00000000000000A0, 0x0000000000000040,
     0x0000000000000200, 0x0000000000000500, 0x0000000000000A00, 0x0000000000001400, 0x0000000000002800, 0x0000000000005000, 0x000000000000A000, 0x0000000000004000,
@@ -61,9 +107,12 @@ const PAWN_ATTACK_DOWN: [u64; 64] = [
     0x0000000002000000, 0x0000000005000000, 0x000000000A000000, 0x0000000014000000, 0x0000000028000000, 0x0000000050000000, 0x00000000A0000000, 0x0000000040000000,
     0x0000000200000000, 0x0000000500000000, 0x0000000A00000000, 0x0000001400000000, 0x0000002800000000, 0x0000005000000000, 0x000000A000000000, 0x0000004000000000,
     0x0000020000000000, 0x0000050000000000, 0x00000A0000000000, 0x0000140000000000, 0x0000280000000000, 0x0000500000000000, 0x0000A00000000000, 0x0000400000000000,
-    0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000, 0x0000000000000000,
+    0x0002000000000000, 0x0005000000000000, 0x000A000000000000, 0x0014000000000000, 0x0028000000000000, 0x0050000000000000, 0x00A0000000000000, 0x0040000000000000,
 ];
 
+/// The squares on the ray from each square in each [`Direction`](crate::bits::Direction)
+/// 
+/// Each ray includes its starting square
 pub const RAYS: [[u64; 64]; 8] = [
     [
         0x0101010101010101, 0x0202020202020202, 0x0404040404040404, 0x0808080808080808, 0x1010101010101010, 0x2020202020202020, 0x4040404040404040, 0x8080808080808080,
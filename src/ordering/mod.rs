@@ -0,0 +1,184 @@
+//! Ordering moves so that a search tries the most promising first
+//!
+//! Moves are scored, from best to worst, as the transposition table move,
+//! captures and promotions that don't lose material (by
+//! [MVV-LVA](https://www.chessprogramming.org/MVV-LVA)), quiet moves (by the
+//! history heuristic), and then captures that lose material (by
+//! [SEE](Position::see())).
+
+mod tests;
+
+use crate::{
+    bits::Square,
+    eval::PieceValues,
+    movegen::Move,
+    position::{
+        Color,
+        Position,
+        Role,
+    },
+};
+
+/// The score of the transposition table move, above every other score
+pub const TT_MOVE_SCORE: i32 = 1_000_000;
+
+/// The score added to captures and promotions that don't lose material,
+/// putting them above quiet moves
+pub const GOOD_CAPTURE_SCORE: i32 = 100_000;
+
+/// The score added to captures that lose material, putting them below quiet
+/// moves
+pub const BAD_CAPTURE_SCORE: i32 = -100_000;
+
+/// The most a quiet move's history score can reach, which keeps it below the
+/// [`GOOD_CAPTURE_SCORE`]
+pub const MAX_HISTORY: i32 = 50_000;
+
+/// Gets the origin and destination of a quiet move, or [`None`] for captures,
+/// promotions and castling, which aren't ordered by history
+fn quiet_squares(m: Move) -> Option<(Square, Square)> {
+    match m {
+        Move::Normal { from, to, capture: None, .. } |
+        Move::PawnMove { from, to, promotion: None, capture: None, .. } |
+        Move::DoublePawnPush { from, to } => Some((from, to)),
+        _ => None,
+    }
+}
+
+/// The [history heuristic](https://www.chessprogramming.org/History_Heuristic)
+/// scores of quiet moves, indexed by color, origin and destination, with the
+/// [butterfly](https://www.chessprogramming.org/Butterfly_Boards) counts of
+/// how often each was tried
+/// 
+/// A search should [`update`](Self::update()) the table when a quiet move 
+/// causes a beta cutoff, and [`record_try`](Self::record_try()) every quiet
+/// move it searches.
+#[derive(Debug, Clone)]
+pub struct HistoryTable {
+    scores: Box<[[[i32; 64]; 64]; 2]>,
+    tries: Box<[[[u32; 64]; 64]; 2]>,
+}
+
+impl Default for HistoryTable {
+    fn default() -> Self {
+        HistoryTable::new()
+    }
+}
+
+impl HistoryTable {
+    /// Creates a new table with every score and count at 0
+    pub fn new() -> Self {
+        HistoryTable {
+            scores: Box::new([[[0; 64]; 64]; 2]),
+            tries: Box::new([[[0; 64]; 64]; 2]),
+        }
+    }
+
+    /// Rewards the color `c` quiet move `m` for causing a cutoff at `depth`,
+    /// by the square of the depth (up to [`MAX_HISTORY`])
+    /// 
+    /// Other moves are ignored
+    pub fn update(&mut self, c: Color, m: Move, depth: u32) {
+        if let Some((from, to)) = quiet_squares(m) {
+            let bonus = depth.saturating_mul(depth).min(MAX_HISTORY as u32) as i32;
+            let score = &mut self.scores[c as usize][usize::from(from)][usize::from(to)];
+            *score = (*score + bonus).min(MAX_HISTORY);
+        }
+    }
+
+    /// Counts a try of the color `c` quiet move `m`
+    /// 
+    /// Other moves are ignored
+    pub fn record_try(&mut self, c: Color, m: Move) {
+        if let Some((from, to)) = quiet_squares(m) {
+            let tries = &mut self.tries[c as usize][usize::from(from)][usize::from(to)];
+            *tries = tries.saturating_add(1);
+        }
+    }
+
+    /// Gets the history score of the color `c` move `m`, which is 0 for
+    /// moves that aren't quiet
+    pub fn score(&self, c: Color, m: Move) -> i32 {
+        quiet_squares(m).map_or(0, |(from, to)| {
+            self.scores[c as usize][usize::from(from)][usize::from(to)]
+        })
+    }
+
+    /// Gets the number of tries of the color `c` move `m`, which is 0 for 
+    /// moves that aren't quiet
+    pub fn tries(&self, c: Color, m: Move) -> u32 {
+        quiet_squares(m).map_or(0, |(from, to)| {
+            self.tries[c as usize][usize::from(from)][usize::from(to)]
+        })
+    }
+
+    /// Halves every score and count, so that older cutoffs count for less
+    pub fn age(&mut self) {
+        self.scores.iter_mut().flatten().flatten().for_each(|score| *score /= 2);
+        self.tries.iter_mut().flatten().flatten().for_each(|tries| *tries /= 2);
+    }
+}
+
+/// A move with the score it was ordered by
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScoredMove {
+    /// The move
+    pub mv: Move,
+    /// The move's ordering score, where higher is tried first
+    pub score: i32,
+}
+
+/// What is known about a position besides its moves, for ordering them
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OrderContext<'a> {
+    /// The best move stored in the transposition table, if any
+    pub tt_move: Option<Move>,
+    /// The history of quiet moves, if any
+    pub history: Option<&'a HistoryTable>,
+    /// The piece values for SEE
+    pub values: PieceValues,
+}
+
+/// # Ordering methods
+impl Position {
+    /// Gets the ordering score of the (legal) move `m`
+    pub fn score_move(&self, m: Move, ctx: &OrderContext) -> i32 {
+        if ctx.tt_move == Some(m) {
+            return TT_MOVE_SCORE
+        }
+        let promotion = match m {
+            Move::PawnMove { promotion, .. } => promotion,
+            _ => None,
+        };
+        if m.capture().is_none() && promotion.is_none() {
+            return ctx.history.map_or(0, |history| history.score(self.turn, m))
+        }
+
+        let see = self.see(m, &ctx.values);
+        if see < 0 {
+            return BAD_CAPTURE_SCORE + see
+        }
+        // Most valuable victim, then least valuable attacker
+        let victim = m.capture().map_or(0, |r| ctx.values.mg(r));
+        let promoted = promotion.map_or(0, |r| ctx.values.mg(r) - ctx.values.mg(Role::Pawn));
+        let attacker = ctx.values.mg(m.role()) / 100;
+        GOOD_CAPTURE_SCORE + 10 * (victim + promoted) - attacker
+    }
+
+    /// Gets the (legal) `moves` with their ordering scores, in the same order
+    pub fn score_moves(&self, moves: &[Move], ctx: &OrderContext) -> Vec<ScoredMove> {
+        moves.iter()
+            .map(|&mv| ScoredMove { mv, score: self.score_move(mv, ctx) })
+            .collect()
+    }
+
+    /// Sorts the (legal) `moves` from the highest ordering score to the
+    /// lowest, keeping moves with equal scores in their original order
+    pub fn order_moves(&self, moves: &mut [Move], ctx: &OrderContext) {
+        let mut scored = self.score_moves(moves, ctx);
+        scored.sort_by_key(|sm| std::cmp::Reverse(sm.score));
+        for (m, sm) in moves.iter_mut().zip(scored) {
+            *m = sm.mv;
+        }
+    }
+}
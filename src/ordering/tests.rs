@@ -0,0 +1,93 @@
+#[cfg(test)]
+mod ordering_tests {
+    use crate::movegen::Move;
+    use crate::ordering::*;
+    use crate::position::{Color, Position, Role};
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    // White can take the undefended rook on d5 with the knight or the bishop,
+    // or the pawn on b7, which the other rook defends, with the queen
+    const FEN: &str = "1r2k3/1p6/8/3r4/8/2N5/B7/1Q2K3 w - - 0 1";
+
+    #[test]
+    fn captures_first() {
+        let p = parse(FEN);
+        let mut moves = p.generate();
+        let ctx = OrderContext::default();
+        p.order_moves(&mut moves, &ctx);
+        // The knight takes the rook before the bishop does
+        assert_eq!(moves[0], Move::normal(Role::Knight, 18, 35, Some(Role::Rook)));
+        assert_eq!(moves[1], Move::normal(Role::Bishop, 8, 35, Some(Role::Rook)));
+        // Losing the queen for a pawn is tried last
+        assert_eq!(moves[moves.len() - 1], Move::normal(Role::Queen, 1, 49, Some(Role::Pawn)));
+
+        let scored = p.score_moves(&moves, &ctx);
+        assert!(scored.windows(2).all(|w| w[0].score >= w[1].score));
+        let quiet = scored.iter().find(|sm| sm.mv.capture().is_none()).unwrap();
+        assert!(scored[1].score > quiet.score);
+        assert!(scored[scored.len() - 1].score < quiet.score);
+    }
+
+    #[test]
+    fn tt_move_first() {
+        let p = parse(FEN);
+        let mut moves = p.generate();
+        let tt_move = Move::normal(Role::King, 4, 5, None);
+        let ctx = OrderContext { tt_move: Some(tt_move), ..Default::default() };
+        p.order_moves(&mut moves, &ctx);
+        assert_eq!(moves[0], tt_move);
+        assert_eq!(p.score_move(tt_move, &ctx), TT_MOVE_SCORE);
+    }
+
+    #[test]
+    fn history() {
+        let p = parse(FEN);
+        let mut history = HistoryTable::new();
+        let ke2 = Move::normal(Role::King, 4, 12, None);
+        history.update(Color::White, ke2, 20);
+        let ctx = OrderContext { history: Some(&history), ..Default::default() };
+        let mut moves = p.generate();
+        p.order_moves(&mut moves, &ctx);
+        // After the two good captures, the quiet move with history is first
+        assert_eq!(moves[2], ke2);
+        assert_eq!(p.score_move(ke2, &ctx), 400);
+    }
+}
+
+#[cfg(test)]
+mod history_tests {
+    use crate::movegen::Move;
+    use crate::ordering::*;
+    use crate::position::{Color, Role};
+
+    #[test]
+    fn cutoffs_boost() {
+        let mut history = HistoryTable::new();
+        let nf3 = Move::normal(Role::Knight, 6, 21, None);
+        let nc3 = Move::normal(Role::Knight, 1, 18, None);
+        for depth in [3, 4, 5] {
+            history.update(Color::White, nf3, depth);
+            history.record_try(Color::White, nf3);
+            history.record_try(Color::White, nc3);
+        }
+        history.update(Color::White, nc3, 2);
+        assert!(history.score(Color::White, nf3) > history.score(Color::White, nc3));
+        assert_eq!(history.score(Color::White, nf3), 9 + 16 + 25);
+        assert_eq!(history.tries(Color::White, nc3), 3);
+        // The colors are kept apart
+        assert_eq!(history.score(Color::Black, nf3), 0);
+
+        // Captures aren't quiet moves
+        let nxe5 = Move::normal(Role::Knight, 21, 36, Some(Role::Pawn));
+        history.update(Color::White, nxe5, 10);
+        assert_eq!(history.score(Color::White, nxe5), 0);
+
+        history.age();
+        assert_eq!(history.score(Color::White, nf3), 25);
+        history.update(Color::White, nc3, 1000);
+        assert_eq!(history.score(Color::White, nc3), MAX_HISTORY);
+    }
+}
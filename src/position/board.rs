@@ -3,11 +3,13 @@
 //! A [`Board`] is only concerned with keeping track of the placement of pieces
 //! on a chess board.
 
+use std::cell::Cell;
 use std::fmt::Display;
 
-use crate::bits::{Bitboard, Square, Flippable};
+use crate::bits::{Bitboard, Square, Flippable, squares_display_order};
 use crate::position::util::{WHITE_KING, BLACK_KING};
 use super::mailbox::Mailbox;
+use super::map::ColorMap;
 use super::{Color, Role, Piece};
 
 
@@ -16,14 +18,28 @@ use super::{Color, Role, Piece};
 //=======//
 
 /// The physical, time-independent state of a chess board
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(Eq, Clone, Copy, Debug)]
 pub struct Board {
-    colors: [Bitboard; 2],
+    colors: ColorMap<Bitboard>,
     roles: [Bitboard; 5],
-    kings: [Square; 2],
+    kings: ColorMap<Square>,
     pieces: Mailbox,
 }
 
+impl PartialEq for Board {
+    /// Compares the bitboards and king squares of two boards
+    /// 
+    /// The mailbox is only a cache of the bitboards, so it is not compared 
+    /// (except in debug builds, where it must agree)
+    fn eq(&self, other: &Self) -> bool {
+        let eq = self.colors == other.colors && 
+                 self.roles == other.roles && 
+                 self.kings == other.kings;
+        debug_assert!(!eq || self.pieces == other.pieces);
+        eq
+    }
+}
+
 /// # Create methods
 impl Board {
     /// Creates a new chessboard
@@ -34,23 +50,75 @@ impl Board {
         let black_king = Square::new(60);
 
         Board {
-            colors: [
+            colors: ColorMap::new([
                 Bitboard::square(white_king), 
                 Bitboard::square(black_king)
-            ],
+            ]),
             roles: [Bitboard::EMPTY; 5],
-            kings: [white_king, black_king],
+            kings: ColorMap::new([white_king, black_king]),
             pieces: Mailbox::new(),
         }
     }
 
     /// Creates a board from the placement of pieces
+    /// 
+    /// If a color has no king in the placement, its king is left on its
+    /// default square
     pub fn from_placement(pm: [Option<Piece>; 64]) -> Self {
+        let mut kings = ColorMap::new([0; 2]);
+        for Piece(c, _) in pm.into_iter().flatten().filter(|p| p.1 == Role::King) {
+            kings[c] += 1;
+        }
+        if kings == ColorMap::new([1, 1]) {
+            Board::from_placement_fast(pm)
+        } else {
+            Board::from_placement_by_square(pm)
+        }
+    }
+
+    /// Creates a board from the placement of pieces, which must have exactly
+    /// one king of each color (this is only checked in debug builds), in one
+    /// pass over the placement
+    /// 
+    /// The board is only [verified](Self::debug_verify()) once, at the end.
+    pub fn from_placement_fast(pm: [Option<Piece>; 64]) -> Self {
+        let mut board = Board::new();
+        board.set_placement(pm);
+        board
+    }
+
+    /// Creates a board from the placement of pieces by placing them one at a
+    /// time
+    fn from_placement_by_square(pm: [Option<Piece>; 64]) -> Self {
         let mut board = Board::new();
         let mailbox = Mailbox::from_placement(pm);
+        // The kings are moved first, since the default king squares could be 
+        // occupied by other pieces in the placement. Both are lifted before
+        // either is put down, as each may be headed for the other's square
+        let kings: Vec<(Square, Color)> = mailbox.into_iter()
+            .filter_map(|(s, o_p)| match o_p {
+                Some(Piece(c, Role::King)) => Some((s, c)),
+                _ => None,
+            })
+            .collect();
+        for &(_, c) in &kings {
+            let old_s = board.kings[c];
+            board.colors[c].remove(old_s);
+            board.pieces[old_s] = None;
+        }
+        for &(s, c) in &kings {
+            board.kings[c] = s;
+        }
+        for c in [Color::White, Color::Black] {
+            let s = board.kings[c];
+            board.colors[c].insert(s);
+            board.pieces[s] = Some(Piece(c, Role::King));
+        }
         for (s, o_p) in mailbox {
             if let Some(p) = o_p {
-                board.place(s, p);
+                if p.1 != Role::King {
+                    board.place(s, p);
+                }
             }
         }
         board.debug_verify();
@@ -70,7 +138,7 @@ impl Default for Board {
         const QUEENS_DEFAULT: Bitboard = Bitboard::new(0x0800_0000_0000_0008);
 
         Self { 
-            colors: [WHITE_DEFAULT, BLACK_DEFAULT], 
+            colors: ColorMap::new([WHITE_DEFAULT, BLACK_DEFAULT]), 
             roles: [
                 PAWNS_DEFAULT,
                 KNIGHTS_DEFAULT,
@@ -78,7 +146,7 @@ impl Default for Board {
                 ROOKS_DEFAULT,
                 QUEENS_DEFAULT
             ], 
-            kings: [Square::new(4), Square::new(52)], 
+            kings: ColorMap::new([Square::new(4), Square::new(60)]), 
             pieces: Default::default() 
         }
     }
@@ -98,6 +166,24 @@ impl Board {
         o_p
     }
 
+    /// Gets the role of the piece at a square `s`, if any
+    #[inline]
+    pub fn role_on(&self, s: Square) -> Option<Role> {
+        self.get(s).map(|Piece(_, r)| r)
+    }
+
+    /// Gets the color of the piece at a square `s`, if any
+    #[inline]
+    pub fn color_on(&self, s: Square) -> Option<Color> {
+        self.get(s).map(|Piece(c, _)| c)
+    }
+
+    /// Returns `true` if there is a piece at a square `s`
+    #[inline]
+    pub fn is_occupied(&self, s: Square) -> bool {
+        self.all().contains(s)
+    }
+
     /// Gets the piece at a square `s`, if any, via bitboards instead of the 
     /// mailbox
     /// 
@@ -107,11 +193,11 @@ impl Board {
     fn get_bitboard(&self, s: Square) -> Option<Piece> {
         self.debug_verify();
 
-        if self.kings[0] == s { return Some(WHITE_KING) }
-        else if self.kings[1] == s { return Some(BLACK_KING) }
+        if self.kings[Color::White] == s { return Some(WHITE_KING) }
+        else if self.kings[Color::Black] == s { return Some(BLACK_KING) }
 
-        let white_b = self.colors[Color::White as usize];
-        let black_b = self.colors[Color::Black as usize];
+        let white_b = self.colors[Color::White];
+        let black_b = self.colors[Color::Black];
 
         let white = white_b.contains(s);
         let black = black_b.contains(s);
@@ -136,7 +222,7 @@ impl Board {
     /// Gets the bitboard containing all squares of color `c`
     #[inline]
     pub fn color(&self, c: Color) -> Bitboard {
-        self.colors[c as usize]
+        self.colors[c]
     }
 
     /// Gets the bitboard containing all squares of role `r`
@@ -159,10 +245,38 @@ impl Board {
         self.color(c) & self.role(r)
     }
 
+    /// Gets the bitboard containing the rooks and queens of color `c`
+    #[inline]
+    pub fn majors(&self, c: Color) -> Bitboard {
+        self.color(c) & (self.role(Role::Rook) | self.role(Role::Queen))
+    }
+
+    /// Gets the bitboard containing the knights and bishops of color `c`
+    #[inline]
+    pub fn minors(&self, c: Color) -> Bitboard {
+        self.color(c) & (self.role(Role::Knight) | self.role(Role::Bishop))
+    }
+
+    /// Gets the bitboard containing the bishops, rooks and queens of color `c`
+    #[inline]
+    pub fn sliders(&self, c: Color) -> Bitboard {
+        self.color(c) & (
+            self.role(Role::Bishop) | 
+            self.role(Role::Rook) | 
+            self.role(Role::Queen)
+        )
+    }
+
+    /// Gets the bitboard containing the files with no pawns of either color
+    #[inline]
+    pub fn open_files(&self) -> Bitboard {
+        Bitboard::files(!self.role(Role::Pawn).occupied_files())
+    }
+
     /// Gets the square of the color `c` king
     #[inline]
     pub fn king_square(&self, c: Color) -> Square {
-        self.kings[c as usize]
+        self.kings[c]
     }
 
     /// Gets the bitboard containing the square of the color `c` king
@@ -174,7 +288,7 @@ impl Board {
     /// Gets the bitboard containing all the occupied squares
     #[inline]
     pub fn all(&self) -> Bitboard {
-        self.colors[Color::White as usize] | self.colors[Color::Black as usize]
+        self.colors[Color::White] | self.colors[Color::Black]
     }
 
     /// Gets the bitboard containing all the empty squares
@@ -187,6 +301,12 @@ impl Board {
     pub fn iter_pieces(&self) -> std::vec::IntoIter<(Square, Option<Piece>)> {
         self.pieces.into_iter()
     }
+
+    /// Returns an iterator over the squares and the pieces on them, in the
+    /// order they are displayed (see [`squares_display_order`])
+    pub fn iter_display(&self) -> impl Iterator<Item = (Square, Option<Piece>)> + '_ {
+        squares_display_order().map(|s| (s, self.get(s)))
+    }
 }
 
 /// # Update methods
@@ -212,18 +332,18 @@ impl Board {
             // The king cannot be "placed", only moved
             (None, Piece(c, Role::King)) => {
                 // Remove from board
-                let old_s = self.kings[c as usize];
-                self.colors[c as usize].remove(old_s);
+                let old_s = self.kings[c];
+                self.colors[c].remove(old_s);
                 self.pieces[old_s] = None;
                 
                 // Add to board
-                self.kings[c as usize] = s;
-                self.colors[c as usize].insert(s);
+                self.kings[c] = s;
+                self.colors[c].insert(s);
                 self.pieces[s] = Some(p);
                 true
             }
             (None, Piece(c, r)) => {
-                self.colors[c as usize].insert(s);
+                self.colors[c].insert(s);
                 self.roles[r as usize].insert(s);
                 self.pieces[s] = Some(p);
                 true
@@ -251,8 +371,13 @@ impl Board {
 
         // Require that the replaced piece is not the king
         debug_assert!(captured.map_or(true, |p| p.1 != Role::King));
+
+        if let Some(Piece(c, r)) = captured {
+            self.colors[c].remove(s);
+            self.roles[r as usize].remove(s);
+        }
         
-        self.colors[p.0 as usize].insert(s);
+        self.colors[p.0].insert(s);
         self.roles[p.1 as usize].insert(s);
         self.pieces[s] = Some(p);
 
@@ -261,6 +386,29 @@ impl Board {
         captured
     }
 
+    /// Removes the (non-king) piece on square `s`, returning it if there was 
+    /// one
+    /// 
+    /// # Precondition
+    /// 
+    /// Cannot remove a king, as there must always be one king for each color
+    pub fn remove(&mut self, s: Square) -> Option<Piece> {
+        self.debug_verify();
+
+        let removed = self.pieces[s];
+
+        if let Some(Piece(c, r)) = removed {
+            debug_assert_ne!(r, Role::King);
+            self.colors[c].remove(s);
+            self.roles[r as usize].remove(s);
+            self.pieces[s] = None;
+        }
+
+        self.debug_verify();
+
+        removed
+    }
+
     /// Moves a (non-king) piece from `s_from` to `s_to`
     /// 
     /// Returns the captured piece that was on `s_to`, if there was one
@@ -279,21 +427,23 @@ impl Board {
         debug_assert!(capturer.1 != Role::King);
 
         let captured = self.get(s_to);
-        
-        self.colors[capturer.0 as usize].remove(s_from);
-        self.roles[capturer.1 as usize].remove(s_from);
-        self.pieces[s_from] = None;
-
-        self.colors[capturer.0 as usize].insert(s_to);
-        self.roles[capturer.1 as usize].insert(s_to);
-        self.pieces[s_to] = Some(capturer);
 
+        // The captured piece is removed first, as it may share a role with
+        // the capturer
         if let Some(captured) = captured {
             debug_assert_ne!(captured.1, Role::King);
             debug_assert_ne!(capturer.0, captured.0);
-            self.colors[captured.0 as usize].remove(s_to);
+            self.colors[captured.0].remove(s_to);
             self.roles[captured.1 as usize].remove(s_to);
         }
+        
+        self.colors[capturer.0].remove(s_from);
+        self.roles[capturer.1 as usize].remove(s_from);
+        self.pieces[s_from] = None;
+
+        self.colors[capturer.0].insert(s_to);
+        self.roles[capturer.1 as usize].insert(s_to);
+        self.pieces[s_to] = Some(capturer);
 
         self.debug_verify();
 
@@ -310,21 +460,21 @@ impl Board {
     pub fn king_move(&mut self, c: Color, s_to: Square) -> Option<Piece> {
         self.debug_verify();
 
-        let s_from = self.kings[c as usize];
+        let s_from = self.kings[c];
 
         let captured = self.get(s_to);
 
-        self.colors[c as usize].remove(s_from);
+        self.colors[c].remove(s_from);
         self.pieces[s_from] = None;
 
-        self.colors[c as usize].insert(s_to);
+        self.colors[c].insert(s_to);
         self.pieces[s_to] = Some(Piece(c, Role::King));
-        self.kings[c as usize] = s_to;
+        self.kings[c] = s_to;
 
         if let Some(captured) = captured {
             debug_assert_ne!(captured.1, Role::King);
             debug_assert_ne!(c, captured.0);
-            self.colors[captured.0 as usize].remove(s_to);
+            self.colors[captured.0].remove(s_to);
             self.roles[captured.1 as usize].remove(s_to);
         }
 
@@ -332,32 +482,83 @@ impl Board {
 
         captured
     }
+
+    /// Replaces every piece on the board with the placement `pm`, in place,
+    /// giving the same board as [`from_placement`](Self::from_placement())
+    /// 
+    /// The king squares are taken from the placement, which must have exactly
+    /// one king of each color (this is only checked in debug builds)
+    pub fn set_placement(&mut self, pm: [Option<Piece>; 64]) {
+        self.colors = ColorMap::default();
+        self.roles = [Bitboard::EMPTY; 5];
+        self.pieces = Mailbox::from_placement(pm);
+
+        let mut kings = ColorMap::new([0; 2]);
+        for (s, o_p) in self.pieces {
+            let Some(Piece(c, r)) = o_p else { continue };
+            self.colors[c].insert(s);
+            if r == Role::King {
+                self.kings[c] = s;
+                kings[c] += 1;
+            } else {
+                self.roles[r as usize].insert(s);
+            }
+        }
+        debug_assert_eq!(kings, ColorMap::new([1, 1]), "The placement needs exactly one king per side");
+
+        self.debug_verify();
+    }
 }
 
 impl Flippable for Board {
+    /// Mirrors the ranks and swaps the colors of the pieces, giving the same
+    /// board from the other side's point of view
     fn flipped(&self) -> Self {
         Board {
-            colors: [self.colors[0].flipped(), self.colors[1].flipped()],
-            roles: [
-                self.roles[0].flipped(),
-                self.roles[1].flipped(),
-                self.roles[2].flipped(),
-                self.roles[3].flipped(),
-                self.roles[4].flipped(),
-            ],
-            kings: [self.kings[0].flipped(), self.kings[1].flipped()],
+            colors: ColorMap::new([self.colors[Color::Black].flipped(), self.colors[Color::White].flipped()]),
+            roles: self.roles.map(|b| b.flipped()),
+            kings: ColorMap::new([
+                self.kings[Color::Black].flipped(), 
+                self.kings[Color::White].flipped(),
+            ]),
             pieces: self.pieces.flipped(),
         }
     }
 }
 
+thread_local! {
+    /// Whether [`Board::debug_verify`] checks boards on this thread
+    static VERIFY: Cell<bool> = const { Cell::new(true) };
+}
+
+/// Restores whether boards are verified when dropped, including while
+/// unwinding from a panic
+struct VerifyGuard(bool);
+
+impl Drop for VerifyGuard {
+    fn drop(&mut self) {
+        VERIFY.with(|verify| verify.set(self.0));
+    }
+}
+
+/// Runs `f` with [`Board::debug_verify`] skipped on the current thread, 
+/// returning the result of `f`
+/// 
+/// Verifying every update makes tight loops such as perft very slow in debug
+/// builds. This has no effect in release builds, where boards are never 
+/// verified.
+pub fn without_verification<R>(f: impl FnOnce() -> R) -> R {
+    let _guard = VerifyGuard(VERIFY.with(|verify| verify.replace(false)));
+    f()
+}
+
 /// # Debug methods
 impl Board {
     /// Verifies that the board state is legal
     /// 
     /// Only does checks in dev/debug builds, and disappears in release builds
     pub fn debug_verify(&self) {
-        if !cfg!(debug_assertions) { 
+        if !cfg!(debug_assertions) || !VERIFY.with(Cell::get) { 
             return
         }
 
@@ -365,8 +566,8 @@ impl Board {
 
         log::trace!("debug_verify: Starting...");
 
-        let white = self.colors[Color::White as usize];
-        let black = self.colors[Color::Black as usize];
+        let white = self.colors[Color::White];
+        let black = self.colors[Color::Black];
 
         let pawns = self.roles[Role::Pawn as usize];
         let knights = self.roles[Role::Knight as usize];
@@ -386,13 +587,13 @@ impl Board {
         }
 
         log::trace!("Verifying the king squares");
-        assert_ne!(self.kings[Color::White as usize], 
-                   self.kings[Color::Black as usize]);
+        assert_ne!(self.kings[Color::White], 
+                   self.kings[Color::Black]);
         // TODO Check that the kings are not adjacent
 
         log::trace!("Checking colors and roles overlap once and only once");
         for c in [Color::White, Color::Black] {
-            let c_b = self.colors[c as usize];
+            let c_b = self.colors[c];
             for s in c_b {
                 let mut overlapped = false;
                 for r_b in self.roles {
@@ -401,7 +602,7 @@ impl Board {
                         overlapped = true;
                     }
                 }
-                if self.kings[c as usize] == s {
+                if self.kings[c] == s {
                     assert!(!overlapped);
                     overlapped = true;
                 }
@@ -416,9 +617,9 @@ impl Board {
                     !white.contains(s) && !black.contains(s)
                 ),
                 Some(Piece(c, r)) => {
-                    assert!(self.colors[c as usize].contains(s));
+                    assert!(self.colors[c].contains(s));
                     if r == Role::King {
-                        assert_eq!(self.kings[c as usize], s);
+                        assert_eq!(self.kings[c], s);
                     } else {
                         assert!(self.roles[r as usize].contains(s));
                     }
@@ -428,6 +629,19 @@ impl Board {
 
         log::trace!("debug_verify: Passed!");
     }
+
+    /// Lists the squares whose pieces differ between the board and `other`,
+    /// from the lowest square to the highest, each with its piece on the
+    /// board and then on `other`
+    /// 
+    /// This shows what a move changed, or where two boards that should be 
+    /// equal diverged.
+    pub fn diff(&self, other: &Board) -> Vec<(Square, Option<Piece>, Option<Piece>)> {
+        Square::iter()
+            .map(|s| (s, self.get(s), other.get(s)))
+            .filter(|(_, before, after)| before != after)
+            .collect()
+    }
 }
 
 impl Display for Board {
@@ -435,3 +649,211 @@ impl Display for Board {
         write!(f, "{}", self.pieces)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::bits::{Bitboard, File, Square};
+    use crate::position::{Position, Color, Piece, Role};
+    use crate::position::util::*;
+    use super::Board;
+
+    #[test]
+    fn eq() {
+        let mut b1 = Board::new();
+        let mut b2 = Board::new();
+        assert_eq!(b1, b2);
+        b1.place(Square::new(20), WHITE_KNIGHT);
+        assert_ne!(b1, b2);
+        b2.place(Square::new(20), WHITE_KNIGHT);
+        assert_eq!(b1, b2);
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(debug_assertions)]
+    fn mailbox_mismatch_is_caught() {
+        let b1 = Board::new();
+        let mut b2 = Board::new();
+        b2.pieces[Square::new(20)] = Some(WHITE_KNIGHT);
+        b2.debug_verify();
+        let _ = b1 == b2;
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn without_verification() {
+        let mut b = Board::new();
+        b.pieces[Square::new(20)] = Some(WHITE_KNIGHT);
+        // The mismatch goes unnoticed, and is caught again afterwards
+        super::without_verification(|| b.debug_verify());
+        let caught = std::panic::catch_unwind(|| b.debug_verify());
+        assert!(caught.is_err());
+    }
+
+    #[test]
+    fn square_queries() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let b = Position::from_fen_string(fen.to_string()).unwrap().board;
+        // a1, e1, e5, e7 and a6
+        assert_eq!(b.role_on(Square::new(0)), Some(Role::Rook));
+        assert_eq!(b.color_on(Square::new(0)), Some(Color::White));
+        assert_eq!(b.role_on(Square::new(4)), Some(Role::King));
+        assert_eq!(b.role_on(Square::new(36)), Some(Role::Knight));
+        assert_eq!(b.color_on(Square::new(52)), Some(Color::Black));
+        assert_eq!(b.role_on(Square::new(52)), Some(Role::Queen));
+        assert_eq!(b.color_on(Square::new(40)), Some(Color::Black));
+        assert!(b.is_occupied(Square::new(4)));
+        assert!(b.is_occupied(Square::new(60)));
+        // b1 and d4 are empty
+        assert!(!b.is_occupied(Square::new(1)));
+        assert_eq!(b.role_on(Square::new(1)), None);
+        assert_eq!(b.color_on(Square::new(27)), None);
+    }
+
+    #[test]
+    fn piece_groups() {
+        let b = Board::default();
+        for c in [Color::White, Color::Black] {
+            assert_eq!(b.minors(c).count(), 4);
+            assert_eq!(b.majors(c).count(), 3);
+            assert_eq!(b.sliders(c).count(), 5);
+        }
+        // b1, c1, f1 and g1
+        assert_eq!(u64::from(b.minors(Color::White)), 0x66);
+        // a8, d8 and h8
+        assert_eq!(u64::from(b.majors(Color::Black)), 0x8900_0000_0000_0000);
+    }
+
+    #[test]
+    fn diff() {
+        let mut p = Position::default();
+        let before = p.board;
+        assert!(before.diff(&p.board).is_empty());
+        p.push_uci("e2e4").unwrap();
+        assert_eq!(
+            before.diff(&p.board),
+            [(Square::new(12), Some(WHITE_PAWN), None), (Square::new(28), None, Some(WHITE_PAWN))]
+        );
+        // A capture changes only the squares moved from and to
+        p.push_uci("d7d5").unwrap();
+        let before = p.board;
+        p.push_uci("e4d5").unwrap();
+        assert_eq!(
+            before.diff(&p.board),
+            [(Square::new(28), Some(WHITE_PAWN), None), (Square::new(35), Some(BLACK_PAWN), Some(WHITE_PAWN))]
+        );
+    }
+
+    #[test]
+    fn iter_display() {
+        let p = Position::default();
+        let squares: Vec<_> = p.board.iter_display().collect();
+        assert_eq!(squares.len(), 64);
+        assert_eq!(squares[0], (Square::new(56), Some(BLACK_ROOK)));
+        assert_eq!(squares[4], (Square::new(60), Some(BLACK_KING)));
+        assert_eq!(squares[20], (Square::new(44), None));
+        assert_eq!(squares[63], (Square::new(7), Some(WHITE_ROOK)));
+    }
+
+    #[test]
+    fn set_placement() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            // The kings on each other's default squares
+            "4K3/8/8/8/8/8/8/4k3 w - - 0 1",
+        ] {
+            let expected = Position::from_fen_string(fen.to_string()).unwrap().board;
+            let mut pm = [None; 64];
+            for (s, o_p) in expected.pieces {
+                pm[usize::from(s)] = o_p;
+            }
+            let mut b = Board::default();
+            b.set_placement(pm);
+            assert_eq!(b, Board::from_placement(pm));
+            assert_eq!(b.pieces, expected.pieces);
+            assert_eq!(b.king_square(Color::White), expected.king_square(Color::White));
+        }
+    }
+
+    #[test]
+    fn from_placement_fast() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+            "4K3/8/8/8/8/8/8/4k3 w - - 0 1",
+            "8/8/8/8/8/8/8/kK6 w - - 0 1",
+        ] {
+            let b = Position::from_fen_string(fen.to_string()).unwrap().board;
+            let mut pm = [None; 64];
+            for (s, o_p) in b.pieces {
+                pm[usize::from(s)] = o_p;
+            }
+            let fast = Board::from_placement_fast(pm);
+            let by_square = Board::from_placement_by_square(pm);
+            assert_eq!(fast, by_square, "{}", fen);
+            assert_eq!(fast.pieces, by_square.pieces, "{}", fen);
+            assert_eq!(Board::from_placement(pm), by_square, "{}", fen);
+        }
+        // Without a white king, it stays on its default square
+        let mut pm = [None; 64];
+        pm[0] = Some(BLACK_KING);
+        assert_eq!(Board::from_placement(pm).king_square(Color::White), Square::new(4));
+    }
+
+    /// Times building boards from 100k placements both ways
+    ///
+    /// Run with `cargo test --release from_placement_speed -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn from_placement_speed() {
+        use std::hint::black_box;
+        use std::time::Instant;
+
+        let placements: Vec<[Option<Piece>; 64]> = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        ]
+            .iter()
+            .map(|fen| {
+                let b = fen.parse::<Position>().unwrap().board;
+                let mut pm = [None; 64];
+                for (s, o_p) in b.pieces {
+                    pm[usize::from(s)] = o_p;
+                }
+                pm
+            })
+            .cycle()
+            .take(100_000)
+            .collect();
+
+        let start = Instant::now();
+        for &pm in &placements {
+            black_box(Board::from_placement_fast(black_box(pm)));
+        }
+        let fast = start.elapsed();
+        let start = Instant::now();
+        for &pm in &placements {
+            black_box(Board::from_placement_by_square(black_box(pm)));
+        }
+        let by_square = start.elapsed();
+        println!("from_placement_fast: {:?}, from_placement_by_square: {:?}", fast, by_square);
+    }
+
+    #[test]
+    #[should_panic]
+    #[cfg(debug_assertions)]
+    fn set_placement_without_king() {
+        let mut pm = [None; 64];
+        pm[4] = Some(WHITE_KING);
+        Board::default().set_placement(pm);
+    }
+
+    #[test]
+    fn open_files() {
+        let fen = "4k3/8/8/8/3P4/8/8/4K3 w - - 0 1";
+        let b = Position::from_fen_string(fen.to_string()).unwrap().board;
+        assert_eq!(b.open_files(), !Bitboard::file(File::D));
+        assert_eq!(Board::default().open_files(), Bitboard::EMPTY);
+    }
+}
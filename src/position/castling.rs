@@ -6,6 +6,7 @@
 use std::fmt::Display;
 
 use super::{Color};
+use crate::bits::{File, Flippable};
 
 /// The direction of castling
 #[allow(missing_docs)]
@@ -15,53 +16,171 @@ pub enum CastlingSide {
     Queenside,
 }
 
+/// The notation used when writing castling rights in a FEN string
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum CastlingNotation {
+    /// `KQkq`, which can only describe rooks on the outermost files
+    Standard,
+    /// `HAha`, naming the file of each castling rook (used for Chess960)
+    Shredder,
+}
+
+/// The castling rights of one color, on each side
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+pub struct CastlingRights {
+    /// Whether the color may still castle kingside
+    pub kingside: bool,
+    /// Whether the color may still castle queenside
+    pub queenside: bool,
+}
+
+impl CastlingRights {
+    /// Returns `true` if the color may castle on either side
+    #[inline]
+    pub fn any(self) -> bool {
+        self.kingside || self.queenside
+    }
+}
+
 /// The castling state of a chessboard
+/// 
+/// Each castling right is stored as the file of the rook that the king castles
+/// with, so that Chess960 positions (where the rooks need not start on the `a`
+/// and `h` files) can be represented.
 #[derive(PartialEq, Eq, Debug, Copy, Clone)]
 pub struct Castling {
-    rights: [[bool; 2]; 2]
+    rooks: [[Option<File>; 2]; 2]
 }
 
 impl Default for Castling {
     fn default() -> Self {
-        Castling { rights: [[true, true], [true, true]] }
+        Castling { 
+            rooks: [
+                [Some(File::H), Some(File::A)], 
+                [Some(File::H), Some(File::A)],
+            ]
+        }
+    }
+}
+
+impl Flippable for Castling {
+    /// Swaps the castling rights of the two colors
+    fn flipped(&self) -> Self {
+        Castling { rooks: [self.rooks[1], self.rooks[0]] }
+    }
+}
+
+impl CastlingSide {
+    /// Gets the file of the castling rook in standard chess
+    #[inline]
+    pub fn standard_rook_file(self) -> File {
+        match self {
+            CastlingSide::Kingside => File::H,
+            CastlingSide::Queenside => File::A,
+        }
     }
 }
 
 impl Castling {
     /// Creates a new `Castling` object
     pub fn new() -> Self {
-        Castling { rights: [[false, false], [false, false]] }
+        Castling { rooks: [[None, None], [None, None]] }
     }
 
     /// Gets the castling rights for color `c`
     pub fn get(self, c: Color, cs: CastlingSide) -> bool {
-        self.rights[c as usize][cs as usize]
+        self.rooks[c as usize][cs as usize].is_some()
     }
 
     /// Sets the castling rights for color `c` to `cr`
+    /// 
+    /// The castling rook is assumed to be on its standard file (see 
+    /// [`set_rook_file`](Self::set_rook_file()) for Chess960)
     pub fn set(&mut self, c: Color, cs: CastlingSide, cr: bool) {
-        self.rights[c as usize][cs as usize] = cr;
+        self.rooks[c as usize][cs as usize] = cr.then(|| cs.standard_rook_file());
+    }
+
+    /// Gets the file of the rook that color `c` may castle with on side `cs`,
+    /// or [`None`] if `c` cannot castle on that side
+    pub fn rook_file(self, c: Color, cs: CastlingSide) -> Option<File> {
+        self.rooks[c as usize][cs as usize]
+    }
+
+    /// Sets the file of the rook that color `c` may castle with on side `cs`
+    /// 
+    /// Setting it to [`None`] removes the castling right
+    pub fn set_rook_file(&mut self, c: Color, cs: CastlingSide, f: Option<File>) {
+        self.rooks[c as usize][cs as usize] = f;
+    }
+
+    /// Returns `true` if every castling rook is on its standard file, i.e. the
+    /// rights can be written in [`Standard`](CastlingNotation::Standard) 
+    /// notation without losing information
+    pub fn is_standard(self) -> bool {
+        self.iter_rook_files().all(|(_, cs, o_f)| {
+            o_f.is_none_or(|f| f == cs.standard_rook_file())
+        })
+    }
+
+    /// Returns the notation that describes these castling rights exactly
+    pub fn notation(self) -> CastlingNotation {
+        if self.is_standard() {
+            CastlingNotation::Standard
+        } else {
+            CastlingNotation::Shredder
+        }
     }
 
     /// Returns an iterator over the castling rights
     pub fn iter_rights(&self) -> std::array::IntoIter<(Color, CastlingSide, bool), 4> {
         [
-            (Color::White, CastlingSide::Kingside, self.rights[0][0]),
-            (Color::White, CastlingSide::Queenside, self.rights[0][1]),
-            (Color::Black, CastlingSide::Kingside, self.rights[1][0]),
-            (Color::Black, CastlingSide::Queenside, self.rights[1][1]),
+            (Color::White, CastlingSide::Kingside, self.rooks[0][0].is_some()),
+            (Color::White, CastlingSide::Queenside, self.rooks[0][1].is_some()),
+            (Color::Black, CastlingSide::Kingside, self.rooks[1][0].is_some()),
+            (Color::Black, CastlingSide::Queenside, self.rooks[1][1].is_some()),
+        ].into_iter()
+    }
+
+    /// Returns an iterator over the castling rook files
+    pub fn iter_rook_files(&self) -> std::array::IntoIter<(Color, CastlingSide, Option<File>), 4> {
+        [
+            (Color::White, CastlingSide::Kingside, self.rooks[0][0]),
+            (Color::White, CastlingSide::Queenside, self.rooks[0][1]),
+            (Color::Black, CastlingSide::Kingside, self.rooks[1][0]),
+            (Color::Black, CastlingSide::Queenside, self.rooks[1][1]),
         ].into_iter()
     }
+
+    /// Writes the castling rights as they appear in a FEN string, using the 
+    /// notation `n`
+    /// 
+    /// In [`Standard`](CastlingNotation::Standard) notation the rook files are
+    /// dropped, so non-standard rooks are written as `K`/`Q` (as in X-FEN).
+    pub fn to_string_with(&self, n: CastlingNotation) -> String {
+        let mut cr_str = String::new();
+        for (c, cs, o_f) in self.iter_rook_files() {
+            if let Some(f) = o_f {
+                let ch = match (n, cs) {
+                    (CastlingNotation::Standard, CastlingSide::Kingside) => 'k',
+                    (CastlingNotation::Standard, CastlingSide::Queenside) => 'q',
+                    (CastlingNotation::Shredder, _) => char::from(f),
+                };
+                match c {
+                    Color::White => cr_str.push(ch.to_ascii_uppercase()),
+                    Color::Black => cr_str.push(ch),
+                }
+            }
+        }
+        if cr_str.is_empty() { cr_str.push('-') }
+        cr_str
+    }
 }
 
 impl Display for Castling {
+    /// Writes the castling rights in [`Standard`](CastlingNotation::Standard)
+    /// notation, unless a rook is on a non-standard file, in which case 
+    /// [`Shredder`](CastlingNotation::Shredder) notation is used
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut cr_str = String::new();
-        if self.rights[0][0] { cr_str.push('K') }
-        if self.rights[0][1] { cr_str.push('Q') }
-        if self.rights[1][0] { cr_str.push('k') }
-        if self.rights[1][1] { cr_str.push('q') }
-        if cr_str.is_empty() { cr_str.push('-') }
-        write!(f, "{}", cr_str)
+        write!(f, "{}", self.to_string_with(self.notation()))
     }
-}
\ No newline at end of file
+}
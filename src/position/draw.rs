@@ -0,0 +1,106 @@
+//! Draws under the FIDE Laws of Chess, telling the draws a player may claim
+//! apart from those that end the game on their own
+
+use std::fmt::Display;
+
+use crate::bits::Bitboard;
+use super::{Position, Role};
+
+/// Why a game is (or may be claimed) drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawReason {
+    /// The position has occurred at least 3 times (claimable)
+    ThreefoldRepetition,
+    /// There have been at least 50 moves by each side without a capture or
+    /// pawn move (claimable)
+    FiftyMoves,
+    /// The position has occurred at least 5 times (automatic)
+    FivefoldRepetition,
+    /// There have been at least 75 moves by each side without a capture or
+    /// pawn move (automatic)
+    SeventyFiveMoves,
+    /// The side to move has no legal moves but isn't in check (automatic)
+    Stalemate,
+    /// Neither side has the material to checkmate (automatic)
+    InsufficientMaterial,
+}
+
+impl Display for DrawReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DrawReason::ThreefoldRepetition => write!(f, "Threefold repetition"),
+            DrawReason::FiftyMoves => write!(f, "Fifty-move rule"),
+            DrawReason::FivefoldRepetition => write!(f, "Fivefold repetition"),
+            DrawReason::SeventyFiveMoves => write!(f, "Seventy-five-move rule"),
+            DrawReason::Stalemate => write!(f, "Stalemate"),
+            DrawReason::InsufficientMaterial => write!(f, "Insufficient material"),
+        }
+    }
+}
+
+/// # Rules methods
+impl Position {
+    /// Returns `true` if neither side can possibly checkmate, which is when
+    /// the only pieces besides the kings are a single knight or bishop, or
+    /// bishops all on the same color of squares
+    pub fn is_insufficient_material(&self) -> bool {
+        let heavy = self.board.role(Role::Pawn) |
+                    self.board.role(Role::Rook) |
+                    self.board.role(Role::Queen);
+        if heavy.is_any() {
+            return false
+        }
+        let knights = self.board.role(Role::Knight);
+        let bishops = self.board.role(Role::Bishop);
+        match (knights.count(), bishops.count()) {
+            (0, 0) | (1, 0) | (0, 1) => true,
+            (0, _) => {
+                (bishops & Bitboard::LIGHT_SQUARES).is_empty() ||
+                (bishops & Bitboard::DARK_SQUARES).is_empty()
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the side to move is checkmated, which takes
+    /// precedence over any draw
+    fn is_checkmated(&self) -> bool {
+        self.is_in_check() && self.generate().is_empty()
+    }
+
+    /// Gets the reason the side to move may claim a draw, if it may
+    ///
+    /// This is [threefold repetition](Self::repetition_count()) or the
+    /// fifty-move rule, which only end the game once claimed. See
+    /// [`automatic_draw`](Self::automatic_draw()) for the draws that end it
+    /// on their own.
+    pub fn claimable_draw(&self) -> Option<DrawReason> {
+        if self.repetition_count() >= 3 {
+            Some(DrawReason::ThreefoldRepetition)
+        } else if self.halfmove >= 100 && !self.is_checkmated() {
+            Some(DrawReason::FiftyMoves)
+        } else {
+            None
+        }
+    }
+
+    /// Gets the reason the game is drawn without either side claiming it, if
+    /// it is
+    ///
+    /// This is stalemate, insufficient material, fivefold repetition or the
+    /// seventy-five-move rule (unless the last move checkmated).
+    pub fn automatic_draw(&self) -> Option<DrawReason> {
+        let no_moves = self.generate().is_empty();
+        if no_moves && !self.is_in_check() {
+            Some(DrawReason::Stalemate)
+        } else if self.is_insufficient_material() {
+            Some(DrawReason::InsufficientMaterial)
+        } else if self.repetition_count() >= 5 {
+            Some(DrawReason::FivefoldRepetition)
+        } else if self.halfmove >= 150 && !no_moves {
+            Some(DrawReason::SeventyFiveMoves)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,195 @@
+//! A chess board that may be missing kings
+//! 
+//! A [`BoardFragment`] holds a placement of pieces with at most one king per
+//! side, such as a puzzle diagram or a study of part of a board. Unlike a 
+//! [`Board`], it makes no assumption that both kings are present.
+
+use std::fmt::Display;
+
+use crate::bits::{Bitboard, Square};
+use super::board::Board;
+use super::mailbox::Mailbox;
+use super::map::{ColorMap, RoleMap};
+use super::{Color, Role, Piece};
+
+
+//===============//
+// BoardFragment //
+//===============//
+
+/// A placement of pieces with at most one king per side
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct BoardFragment {
+    colors: ColorMap<Bitboard>,
+    roles: RoleMap<Bitboard>,
+    pieces: Mailbox,
+}
+
+/// # Create methods
+impl BoardFragment {
+    /// Creates an empty board fragment, without any kings
+    pub fn new() -> Self {
+        BoardFragment {
+            colors: ColorMap::default(),
+            roles: RoleMap::default(),
+            pieces: Mailbox::from_placement([None; 64]),
+        }
+    }
+
+    /// Creates a board fragment from the placement of pieces, or returns an 
+    /// error if a side has more than one king
+    pub fn from_placement(pm: [Option<Piece>; 64]) -> Result<Self, &'static str> {
+        let mut fragment = BoardFragment::new();
+        for (i, o_p) in pm.into_iter().enumerate() {
+            let Some(p) = o_p else { continue };
+            if !fragment.place(Square::new(i as u32), p) {
+                return Err("A side has more than one king")
+            }
+        }
+        Ok(fragment)
+    }
+}
+
+impl Default for BoardFragment {
+    fn default() -> Self {
+        BoardFragment::new()
+    }
+}
+
+impl From<Board> for BoardFragment {
+    fn from(board: Board) -> Self {
+        let mut fragment = BoardFragment::new();
+        for (s, o_p) in board.iter_pieces() {
+            if let Some(p) = o_p {
+                fragment.place(s, p);
+            }
+        }
+        fragment
+    }
+}
+
+/// # Read methods
+impl BoardFragment {
+    /// Gets the piece on square `s`, if any
+    #[inline]
+    pub fn get(&self, s: Square) -> Option<Piece> {
+        self.pieces[s]
+    }
+
+    /// Gets the bitboard containing all squares of color `c`
+    #[inline]
+    pub fn color(&self, c: Color) -> Bitboard {
+        self.colors[c]
+    }
+
+    /// Gets the bitboard containing all squares of role `r`, including the 
+    /// kings
+    #[inline]
+    pub fn role(&self, r: Role) -> Bitboard {
+        self.roles[r]
+    }
+
+    /// Gets the bitboard containing all squares of piece `p`
+    #[inline]
+    pub fn piece(&self, p: Piece) -> Bitboard {
+        let Piece(c, r) = p;
+        self.color(c) & self.role(r)
+    }
+
+    /// Gets the bitboard containing all the occupied squares
+    #[inline]
+    pub fn all(&self) -> Bitboard {
+        self.colors[Color::White] | self.colors[Color::Black]
+    }
+
+    /// Gets the square of the color `c` king, or [`None`] if it has no king
+    #[inline]
+    pub fn king_square(&self, c: Color) -> Option<Square> {
+        self.king_bitboard(c).smallest_square()
+    }
+
+    /// Gets the bitboard containing the square of the color `c` king, which is
+    /// empty if it has no king
+    #[inline]
+    pub fn king_bitboard(&self, c: Color) -> Bitboard {
+        self.piece(Piece(c, Role::King))
+    }
+
+    /// Gets the fragment as a [`Board`], if both sides have a king
+    pub fn to_board(&self) -> Option<Board> {
+        self.king_square(Color::White)?;
+        self.king_square(Color::Black)?;
+        let mut pm = [None; 64];
+        for (s, o_p) in self.pieces {
+            pm[usize::from(s)] = o_p;
+        }
+        Some(Board::from_placement(pm))
+    }
+}
+
+/// # Update methods
+impl BoardFragment {
+    /// If `s` is empty, and `p` isn't a second king of its color, it places
+    /// `p` on `s` and returns `true`, otherwise does nothing and returns 
+    /// `false`
+    pub fn place(&mut self, s: Square, p: Piece) -> bool {
+        let Piece(c, r) = p;
+        if self.pieces[s].is_some() || (r == Role::King && self.king_square(c).is_some()) {
+            return false
+        }
+        self.colors[c].insert(s);
+        self.roles[r].insert(s);
+        self.pieces[s] = Some(p);
+        self.debug_verify();
+        true
+    }
+
+    /// Removes and returns the piece on `s`, if any, which may be a king
+    pub fn remove(&mut self, s: Square) -> Option<Piece> {
+        let removed = self.pieces[s];
+        if let Some(Piece(c, r)) = removed {
+            self.colors[c].remove(s);
+            self.roles[r].remove(s);
+            self.pieces[s] = None;
+        }
+        self.debug_verify();
+        removed
+    }
+}
+
+/// # Debug methods
+impl BoardFragment {
+    /// Verifies the integrity of the fragment's data structures, as 
+    /// [`Board::debug_verify`] does, except that kings may be missing
+    pub fn debug_verify(&self) {
+        if !cfg!(debug_assertions) {
+            return
+        }
+
+        let white = self.colors[Color::White];
+        let black = self.colors[Color::Black];
+        assert_eq!(white & black, Bitboard::EMPTY);
+
+        for c in [Color::White, Color::Black] {
+            assert!(self.king_bitboard(c).count() <= 1);
+        }
+
+        for (s, o_p) in self.pieces {
+            let roles = self.roles.iter().filter(|r_b| r_b.contains(s)).count();
+            match o_p {
+                None => assert!(!white.contains(s) && !black.contains(s) && roles == 0),
+                Some(Piece(c, r)) => {
+                    assert!(self.colors[c].contains(s));
+                    assert!(self.roles[r].contains(s));
+                    assert_eq!(roles, 1);
+                }
+            }
+        }
+    }
+}
+
+impl Display for BoardFragment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.pieces)
+    }
+}
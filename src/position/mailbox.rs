@@ -70,10 +70,11 @@ impl Default for Mailbox {
 }
 
 impl Flippable for Mailbox {
+    /// Mirrors the ranks and swaps the colors of the pieces
     fn flipped(&self) -> Self {
-        let mut flipped = Mailbox::new();
-        for i in 0..64usize {
-            flipped.0[i] = self.0[63 - i];
+        let mut flipped = Mailbox([None; 64]);
+        for s in Square::iter() {
+            flipped[s.flipped()] = self[s].map(|Piece(c, r)| Piece(!c, r));
         }
         flipped
     }
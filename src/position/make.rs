@@ -0,0 +1,316 @@
+//! Making and unmaking moves on a [`Position`]
+
+use crate::bits::{Square, Coords, File};
+use crate::movegen::Move;
+use super::castling::{Castling, CastlingSide};
+use super::zobrist::{piece_key, turn_key, castling_key, en_passant_key};
+use super::{Position, Color, Role, Piece};
+use super::board;
+
+/// The state of a position that can't be recovered from a move, saved by
+/// [`make_move`](Position::make_move()) so that the move can be unmade
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Undo {
+    /// The move that was made
+    pub m: Move,
+    king: Square,
+    castling: Castling,
+    en_passant: Option<Square>,
+    halfmove: u32,
+    fullmove: u32,
+    zobrist: u64,
+}
+
+/// Unmakes the moves that were made on a position when dropped, including 
+/// while unwinding from a panic
+struct Rollback<'a> {
+    position: &'a mut Position,
+    undos: Vec<Undo>,
+}
+
+impl Drop for Rollback<'_> {
+    fn drop(&mut self) {
+        while let Some(undo) = self.undos.pop() {
+            self.position.unmake_move(undo);
+        }
+    }
+}
+
+impl Position {
+    /// Makes the (legal) `moves` in order, runs `f` on the resulting 
+    /// position, then unmakes them in reverse, returning the result of `f`
+    /// 
+    /// The moves are unmade even if `f` panics
+    pub fn with_moves<R>(&mut self, moves: &[Move], f: impl FnOnce(&Position) -> R) -> R {
+        let mut rollback = Rollback {
+            position: self,
+            undos: Vec::with_capacity(moves.len()),
+        };
+        for &m in moves {
+            let undo = rollback.position.make_move(m);
+            rollback.undos.push(undo);
+        }
+        f(rollback.position)
+    }
+
+    /// Makes each of `moves` in order, as long as it is
+    /// [legal](Self::is_legal())
+    /// 
+    /// On the first illegal move, stops and returns its index along with a
+    /// copy of the position reached so far, which the position is also left
+    /// in.
+    #[allow(clippy::result_large_err)]
+    pub fn apply_moves_checked(&mut self, moves: &[Move]) -> Result<(), (usize, Position)> {
+        for (i, &m) in moves.iter().enumerate() {
+            if !self.is_legal(m) {
+                return Err((i, self.clone()))
+            }
+            self.make_move(m);
+        }
+        Ok(())
+    }
+
+    /// Makes the (legal) move `m`, returning the [`Undo`] needed to unmake it
+    pub fn make_move(&mut self, m: Move) -> Undo {
+        let undo = Undo {
+            m,
+            king: self.board.king_square(self.turn),
+            castling: self.castling,
+            en_passant: self.en_passant,
+            halfmove: self.halfmove,
+            fullmove: self.fullmove,
+            zobrist: self.zobrist,
+        };
+
+        let us = self.turn;
+        let them = !us;
+        let mut key = self.zobrist ^ castling_key(self.castling) ^ en_passant_key(self.en_passant);
+
+        self.en_passant = None;
+        self.halfmove += 1;
+
+        match m {
+            Move::PawnMove { from, to, promotion, en_passant, capture } => {
+                if en_passant {
+                    let captured = Square::from(Coords(to.file(), from.rank()));
+                    self.board.remove(captured);
+                    key ^= piece_key(Piece(them, Role::Pawn), captured);
+                } else if let Some(r) = capture {
+                    key ^= piece_key(Piece(them, r), to);
+                }
+                self.board.r#move(from, to);
+                key ^= piece_key(Piece(us, Role::Pawn), from);
+                match promotion {
+                    Some(r) => {
+                        self.board.replace(to, Piece(us, r));
+                        key ^= piece_key(Piece(us, r), to);
+                    }
+                    None => key ^= piece_key(Piece(us, Role::Pawn), to),
+                }
+                self.halfmove = 0;
+                self.update_castling(from, to, false);
+            }
+            Move::DoublePawnPush { from, to } => {
+                self.board.r#move(from, to);
+                key ^= piece_key(Piece(us, Role::Pawn), from) ^ piece_key(Piece(us, Role::Pawn), to);
+                self.halfmove = 0;
+
+                self.en_passant = self.en_passant_after(from);
+            }
+            Move::Normal { role, from, to, capture } => {
+                if let Some(r) = capture {
+                    key ^= piece_key(Piece(them, r), to);
+                    self.halfmove = 0;
+                }
+                if role == Role::King {
+                    self.board.king_move(us, to);
+                } else {
+                    self.board.r#move(from, to);
+                }
+                key ^= piece_key(Piece(us, role), from) ^ piece_key(Piece(us, role), to);
+                self.update_castling(from, to, role == Role::King);
+            }
+            Move::Castle { castling_side } => {
+                let (k_from, k_to, r_from, r_to) = self.castling_squares(us, castling_side);
+                // The rook is lifted first, since in Chess960 the king may
+                // land on the rook's square (or vice versa)
+                self.board.remove(r_from);
+                self.board.king_move(us, k_to);
+                self.board.place(r_to, Piece(us, Role::Rook));
+                key ^= piece_key(Piece(us, Role::King), k_from) ^ piece_key(Piece(us, Role::King), k_to);
+                key ^= piece_key(Piece(us, Role::Rook), r_from) ^ piece_key(Piece(us, Role::Rook), r_to);
+                self.update_castling(r_from, r_to, true);
+            }
+        }
+
+        if us == Color::Black {
+            self.fullmove += 1;
+        }
+        self.turn = them;
+
+        key ^= turn_key() ^ castling_key(self.castling) ^ en_passant_key(self.en_passant);
+        self.zobrist = key;
+        self.history.push(undo.zobrist);
+        self.clear_attacks();
+
+        undo
+    }
+
+    /// Makes the (legal) move `m` like [`make_move`](Self::make_move()), 
+    /// but without [verifying](board::Board::debug_verify()) the board in debug
+    /// builds
+    pub fn make_move_unchecked(&mut self, m: Move) -> Undo {
+        board::without_verification(|| self.make_move(m))
+    }
+
+    /// Unmakes the move that `undo` was returned for like
+    /// [`unmake_move`](Self::unmake_move()), but without
+    /// [verifying](board::Board::debug_verify()) the board in debug builds
+    pub fn unmake_move_unchecked(&mut self, undo: Undo) {
+        board::without_verification(|| self.unmake_move(undo))
+    }
+
+    /// Unmakes the move that `undo` was returned for, which must be the last
+    /// move made
+    ///
+    /// The halfmove clock and fullmove number are restored from `undo` as they
+    /// were, rather than worked back from the move and whose turn it was.
+    pub fn unmake_move(&mut self, undo: Undo) {
+        let them = self.turn;
+        let us = !them;
+
+        match undo.m {
+            Move::PawnMove { from, to, promotion, en_passant, capture } => {
+                if promotion.is_some() {
+                    self.board.replace(to, Piece(us, Role::Pawn));
+                }
+                self.board.r#move(to, from);
+                if en_passant {
+                    let captured = Square::from(Coords(to.file(), from.rank()));
+                    self.board.place(captured, Piece(them, Role::Pawn));
+                } else if let Some(r) = capture {
+                    self.board.place(to, Piece(them, r));
+                }
+            }
+            Move::DoublePawnPush { from, to } => {
+                self.board.r#move(to, from);
+            }
+            Move::Normal { role, from, to, capture } => {
+                if role == Role::King {
+                    self.board.king_move(us, from);
+                } else {
+                    self.board.r#move(to, from);
+                }
+                if let Some(r) = capture {
+                    self.board.place(to, Piece(them, r));
+                }
+            }
+            Move::Castle { castling_side } => {
+                // The squares are found from the saved king square and rights,
+                // since the move has moved the one and cleared the other
+                let back_rank = undo.king.rank();
+                let r_from = undo.castling.rook_file(us, castling_side)
+                    .unwrap_or(castling_side.standard_rook_file());
+                let r_to = match castling_side {
+                    CastlingSide::Kingside => File::F,
+                    CastlingSide::Queenside => File::D,
+                };
+                self.board.remove(Square::from(Coords(r_to, back_rank)));
+                self.board.king_move(us, undo.king);
+                self.board.place(Square::from(Coords(r_from, back_rank)), Piece(us, Role::Rook));
+            }
+        }
+
+        self.turn = us;
+        self.castling = undo.castling;
+        self.en_passant = undo.en_passant;
+        self.halfmove = undo.halfmove;
+        self.fullmove = undo.fullmove;
+        self.zobrist = undo.zobrist;
+        self.history.pop();
+        self.clear_attacks();
+    }
+
+    /// Gets the Zobrist key of the position after the (legal) move `m`,
+    /// without making it
+    /// 
+    /// This is the key [`make_move`](Self::make_move()) would leave, 
+    /// including the castling rights lost and the en passant square set or
+    /// cleared by the move, e.g. for prefetching a transposition table entry.
+    pub fn key_after(&self, m: Move) -> u64 {
+        let us = self.turn;
+        let them = !us;
+        let mut key = self.zobrist ^ turn_key() ^ 
+                      castling_key(self.castling) ^ en_passant_key(self.en_passant);
+        let mut en_passant = None;
+
+        let castling = match m {
+            Move::PawnMove { from, to, promotion, en_passant, capture } => {
+                if en_passant {
+                    let captured = Square::from(Coords(to.file(), from.rank()));
+                    key ^= piece_key(Piece(them, Role::Pawn), captured);
+                } else if let Some(r) = capture {
+                    key ^= piece_key(Piece(them, r), to);
+                }
+                let promoted = promotion.unwrap_or(Role::Pawn);
+                key ^= piece_key(Piece(us, Role::Pawn), from) ^ piece_key(Piece(us, promoted), to);
+                self.castling_after(from, to, false)
+            }
+            Move::DoublePawnPush { from, to } => {
+                key ^= piece_key(Piece(us, Role::Pawn), from) ^ piece_key(Piece(us, Role::Pawn), to);
+                en_passant = self.en_passant_after(from);
+                self.castling
+            }
+            Move::Normal { role, from, to, capture } => {
+                if let Some(r) = capture {
+                    key ^= piece_key(Piece(them, r), to);
+                }
+                key ^= piece_key(Piece(us, role), from) ^ piece_key(Piece(us, role), to);
+                self.castling_after(from, to, role == Role::King)
+            }
+            Move::Castle { castling_side } => {
+                let (k_from, k_to, r_from, r_to) = self.castling_squares(us, castling_side);
+                key ^= piece_key(Piece(us, Role::King), k_from) ^ piece_key(Piece(us, Role::King), k_to);
+                key ^= piece_key(Piece(us, Role::Rook), r_from) ^ piece_key(Piece(us, Role::Rook), r_to);
+                self.castling_after(r_from, r_to, true)
+            }
+        };
+
+        key ^ castling_key(castling) ^ en_passant_key(en_passant)
+    }
+
+    /// Gets the en passant square left by the side to move's double pawn push
+    /// from `from`, which is only recorded if an enemy pawn can capture on it
+    fn en_passant_after(&self, from: Square) -> Option<Square> {
+        let them = !self.turn;
+        let ep = Square::from(Coords(from.file(), them.en_passant_rank()));
+        let capturers = crate::movegen::pawn_attacks(self.turn, ep) &
+                        self.board.piece(Piece(them, Role::Pawn));
+        capturers.is_any().then_some(ep)
+    }
+
+    /// Gets the castling rights left after moving a piece from `from` to 
+    /// `to`, where `king_moved` is whether the side to move moved its king
+    fn castling_after(&self, from: Square, to: Square, king_moved: bool) -> Castling {
+        let mut castling = self.castling;
+        if king_moved {
+            castling.set(self.turn, CastlingSide::Kingside, false);
+            castling.set(self.turn, CastlingSide::Queenside, false);
+        }
+        for (c, cs, o_f) in self.castling.iter_rook_files() {
+            let Some(f) = o_f else { continue };
+            let rook = Square::from(Coords(f, c.back_rank()));
+            if from == rook || to == rook {
+                castling.set_rook_file(c, cs, None);
+            }
+        }
+        castling
+    }
+
+    /// Removes the castling rights that are lost by moving a piece from
+    /// `from` to `to`, where `king_moved` is whether the side to move moved
+    /// its king
+    fn update_castling(&mut self, from: Square, to: Square, king_moved: bool) {
+        self.castling = self.castling_after(from, to, king_moved);
+    }
+}
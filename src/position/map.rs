@@ -0,0 +1,102 @@
+//! Fixed-size arrays indexed by [`Color`], [`Role`] or [`Piece`], rather than
+//! by casting them to `usize`
+
+use std::ops::{Index, IndexMut};
+
+use super::{Color, Piece, Role};
+
+/// A value for each color, indexed by [`Color`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Hash)]
+pub struct ColorMap<T>([T; 2]);
+
+impl<T> ColorMap<T> {
+    /// Creates a map from the values for white and black, in that order
+    #[inline]
+    pub const fn new(values: [T; 2]) -> Self {
+        ColorMap(values)
+    }
+
+    /// Returns an iterator over the values, white's first
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.0.iter()
+    }
+}
+
+impl<T> Index<Color> for ColorMap<T> {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, c: Color) -> &T {
+        &self.0[c as usize]
+    }
+}
+
+impl<T> IndexMut<Color> for ColorMap<T> {
+    #[inline]
+    fn index_mut(&mut self, c: Color) -> &mut T {
+        &mut self.0[c as usize]
+    }
+}
+
+/// A value for each role, indexed by [`Role`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Hash)]
+pub struct RoleMap<T>([T; 6]);
+
+impl<T> RoleMap<T> {
+    /// Creates a map from the values for each role, in
+    /// [index](Role::index()) order
+    #[inline]
+    pub const fn new(values: [T; 6]) -> Self {
+        RoleMap(values)
+    }
+
+    /// Returns an iterator over the values, in [index](Role::index()) order
+    pub fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.0.iter()
+    }
+}
+
+impl<T> Index<Role> for RoleMap<T> {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, r: Role) -> &T {
+        &self.0[r as usize]
+    }
+}
+
+impl<T> IndexMut<Role> for RoleMap<T> {
+    #[inline]
+    fn index_mut(&mut self, r: Role) -> &mut T {
+        &mut self.0[r as usize]
+    }
+}
+
+/// A value for each piece, indexed by [`Piece`]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Hash)]
+pub struct PieceMap<T>([[T; 6]; 2]);
+
+impl<T> PieceMap<T> {
+    /// Creates a map from the values for each of white's roles and then each
+    /// of black's
+    #[inline]
+    pub const fn new(values: [[T; 6]; 2]) -> Self {
+        PieceMap(values)
+    }
+}
+
+impl<T> Index<Piece> for PieceMap<T> {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, Piece(c, r): Piece) -> &T {
+        &self.0[c as usize][r as usize]
+    }
+}
+
+impl<T> IndexMut<Piece> for PieceMap<T> {
+    #[inline]
+    fn index_mut(&mut self, Piece(c, r): Piece) -> &mut T {
+        &mut self.0[c as usize][r as usize]
+    }
+}
@@ -3,16 +3,28 @@
 pub mod mailbox;
 pub mod castling;
 pub mod board;
+pub mod fragment;
+pub mod map;
+pub mod draw;
+pub mod symmetry;
 pub mod zobrist;
+pub mod make;
 pub mod util;
 mod tests;
 
 use crate::bits::*;
+use crate::movegen::pawn_attacks;
 use self::castling::*;
 use self::board::Board;
 use self::util::*;
+use self::map::ColorMap;
 
 use std::fmt::Display;
+use std::hash::{Hash, Hasher};
+use std::io::BufRead;
+use std::ops::Not;
+use std::str::FromStr;
+use std::sync::OnceLock;
 
 
 /// The color of a piece, turn, etc.
@@ -32,6 +44,19 @@ impl From<Color> for char {
     }
 }
 
+impl Not for Color {
+    type Output = Color;
+
+    /// The opposite color
+    #[inline]
+    fn not(self) -> Self::Output {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+}
+
 impl TryFrom<char> for Color {
     type Error = char;
 
@@ -44,9 +69,81 @@ impl TryFrom<char> for Color {
     }
 }
 
+impl From<Color> for u8 {
+    fn from(c: Color) -> Self {
+        c as u8
+    }
+}
+
+impl TryFrom<u8> for Color {
+    type Error = u8;
+
+    fn try_from(i: u8) -> Result<Self, Self::Error> {
+        match i {
+            0 => Ok(Color::White),
+            1 => Ok(Color::Black),
+            _ => Err(i),
+        }
+    }
+}
+
+impl Color {
+    /// Gets the color's index (0 for white, 1 for black), for indexing tables
+    #[inline]
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Gets the direction the color's pawns move in
+    #[inline]
+    pub const fn forward(self) -> Direction {
+        match self {
+            Color::White => Direction::North,
+            Color::Black => Direction::South,
+        }
+    }
+
+    /// Gets the rank the color's pawns start on
+    #[inline]
+    pub const fn pawn_start_rank(self) -> Rank {
+        match self {
+            Color::White => Rank::Second,
+            Color::Black => Rank::Seventh,
+        }
+    }
+
+    /// Gets the rank the color's pieces start on
+    #[inline]
+    pub const fn back_rank(self) -> Rank {
+        match self {
+            Color::White => Rank::First,
+            Color::Black => Rank::Eighth,
+        }
+    }
+
+    /// Gets the rank the color's pawns promote on
+    #[inline]
+    pub const fn promotion_rank(self) -> Rank {
+        match self {
+            Color::White => Rank::Eighth,
+            Color::Black => Rank::First,
+        }
+    }
+
+    /// Gets the rank of the en passant squares the color's pawns can capture
+    /// on, which the opponent's pawns skip with a double push
+    #[inline]
+    pub const fn en_passant_rank(self) -> Rank {
+        match self {
+            Color::White => Rank::Sixth,
+            Color::Black => Rank::Third,
+        }
+    }
+}
+
 /// The type of chess piece
 #[allow(missing_docs)]
-#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash, PartialOrd, Ord)]
 pub enum Role {
     Pawn = 0,
     Knight = 1,
@@ -59,7 +156,8 @@ pub enum Role {
 impl Role {
     const NUM_ROLES: usize = 6;
 
-    const ITER: [Role; Self::NUM_ROLES] = [
+    /// Every role, in [index](Self::index()) order
+    pub const ITER: [Role; Self::NUM_ROLES] = [
         Role::Pawn, 
         Role::Knight, 
         Role::Bishop, 
@@ -68,17 +166,97 @@ impl Role {
         Role::King
     ];
 
-    const ITER_PIECE: [Role; Self::NUM_ROLES - 1] = [
+    /// Every role but the king, which has no bitboard of its own, in
+    /// [index](Self::index()) order
+    pub const ITER_PIECE: [Role; Self::NUM_ROLES - 1] = [
         Role::Pawn, 
         Role::Knight, 
         Role::Bishop, 
         Role::Rook, 
         Role::Queen, 
     ]; 
+
+    /// Gets the role's index (0 for a pawn up to 5 for a king), for indexing
+    /// tables
+    #[inline]
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Gets the role's material value in centipawns in the middlegame
+    /// 
+    /// The king is worth nothing, as it is never traded
+    pub const fn value_mg(self) -> i32 {
+        match self {
+            Role::Pawn => 100,
+            Role::Knight => 320,
+            Role::Bishop => 330,
+            Role::Rook => 500,
+            Role::Queen => 900,
+            Role::King => 0,
+        }
+    }
+
+    /// Gets the role's material value in centipawns in the endgame
+    /// 
+    /// The king is worth nothing, as in the
+    /// [middlegame](Self::value_mg())
+    pub const fn value_eg(self) -> i32 {
+        match self {
+            Role::Pawn => 120,
+            Role::Knight => 290,
+            Role::Bishop => 310,
+            Role::Rook => 540,
+            Role::Queen => 950,
+            Role::King => 0,
+        }
+    }
+
+    /// Gets the role's material value in centipawns, which is its
+    /// [middlegame value](Self::value_mg())
+    pub const fn value(self) -> i32 {
+        self.value_mg()
+    }
+
+    /// Gets the role of a Unicode chess symbol (figurine) of either color,
+    /// e.g. `♘` or `♞` for a knight
+    pub const fn from_figurine(c: char) -> Option<Role> {
+        match c {
+            '♙' | '♟' => Some(Role::Pawn),
+            '♘' | '♞' => Some(Role::Knight),
+            '♗' | '♝' => Some(Role::Bishop),
+            '♖' | '♜' => Some(Role::Rook),
+            '♕' | '♛' => Some(Role::Queen),
+            '♔' | '♚' => Some(Role::King),
+            _ => None,
+        }
+    }
+}
+
+impl From<Role> for u8 {
+    fn from(r: Role) -> Self {
+        r as u8
+    }
+}
+
+impl TryFrom<u8> for Role {
+    type Error = u8;
+
+    fn try_from(i: u8) -> Result<Self, Self::Error> {
+        match i {
+            0 => Ok(Role::Pawn),
+            1 => Ok(Role::Knight),
+            2 => Ok(Role::Bishop),
+            3 => Ok(Role::Rook),
+            4 => Ok(Role::Queen),
+            5 => Ok(Role::King),
+            _ => Err(i),
+        }
+    }
 }
 
 /// A tuple of a [`Color`] and [`Role`] representing a piece on a chessboard
-#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash)]
 pub struct Piece(pub Color, pub Role);
 
 impl From<Piece> for char {
@@ -122,6 +300,31 @@ impl TryFrom<char> for Piece {
     }
 }
 
+impl Piece {
+    /// Gets the piece's index (0 to 5 for the white pieces and 6 to 11 for
+    /// the black pieces, in [`Role`] order), for indexing tables
+    #[inline]
+    pub const fn index(self) -> usize {
+        self.0.index() * Role::NUM_ROLES + self.1.index()
+    }
+}
+
+impl From<Piece> for u8 {
+    fn from(p: Piece) -> Self {
+        p.index() as u8
+    }
+}
+
+impl TryFrom<u8> for Piece {
+    type Error = u8;
+
+    fn try_from(i: u8) -> Result<Self, Self::Error> {
+        let c = Color::try_from(i / Role::NUM_ROLES as u8).map_err(|_| i)?;
+        let r = Role::try_from(i % Role::NUM_ROLES as u8).map_err(|_| i)?;
+        Ok(Piece(c, r))
+    }
+}
+
 impl Display for Piece {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", char::from(*self))
@@ -129,6 +332,11 @@ impl Display for Piece {
 }
 
 /// A time-dependent representation of the state of a chess game
+/// 
+/// Positions are equal, and [hash](Hash) the same, when their boards, turns,
+/// castling rights and en passant squares are, as for repetitions. The move
+/// clocks and the moves made are ignored by both (see
+/// [`full_key`](Self::full_key()) to compare the clocks too).
 #[derive(Eq, Debug)]
 pub struct Position {
     /// The placement of pieces
@@ -143,74 +351,426 @@ pub struct Position {
     pub halfmove: u32,
     /// Number of fullmoves since start of game
     pub fullmove: u32,
+    /// The Zobrist key of the position (see [`zobrist`])
+    zobrist: u64,
+    /// The Zobrist keys of the positions before each move made, oldest first
+    history: Vec<u64>,
+    /// The squares attacked by each color, computed when first needed (see
+    /// [`attacks_by`](Self::attacks_by())) and cleared by each move
+    attacks: ColorMap<OnceLock<Bitboard>>,
 }
 
 impl Position {
+    /// Gets the position as a key that is only equal to (and hashes the same
+    /// as) another if their move clocks are equal as well
+    #[inline]
+    pub fn full_key(&self) -> PositionKey<'_> {
+        PositionKey(self)
+    }
+
     /// Creates a new, empty chess position
     pub fn new() -> Self {
-        Position {
-            board: Board::new(),
-            turn: Color::White,
-            castling: Castling::new(),
-            en_passant: None,
-            halfmove: 1,
-            fullmove: 1,
+        Position::from_parts(
+            Board::new(),
+            Color::White,
+            Castling::new(),
+            None,
+            1,
+            1,
+        )
+    }
+
+    /// Creates a chess position from its parts
+    pub fn from_parts(
+        board: Board,
+        turn: Color,
+        castling: Castling,
+        en_passant: Option<Square>,
+        halfmove: u32,
+        fullmove: u32,
+    ) -> Self {
+        let mut p = Position {
+            board,
+            turn,
+            castling,
+            en_passant,
+            halfmove,
+            fullmove,
+            zobrist: 0,
+            history: Vec::new(),
+            attacks: ColorMap::default(),
+        };
+        p.rebuild_zobrist();
+        p
+    }
+
+    /// Copies the position without its game history, so that the copy's
+    /// history starts at the current position
+    /// 
+    /// Useful for searches, which needn't know how the game got here
+    pub fn clone_fresh(&self) -> Self {
+        Self { 
+            board: self.board, 
+            turn: self.turn, 
+            castling: self.castling, 
+            en_passant: self.en_passant, 
+            halfmove: self.halfmove, 
+            fullmove: self.fullmove,
+            zobrist: self.zobrist,
+            history: Vec::new(),
+            attacks: self.attacks.clone(),
         }
     }
 
     /// Attempts to create a chess position from a FEN string
-    pub fn from_fen_string(fen: String) -> Result<Position, &'static str> {
-        let tokens: Vec<&str> = fen.split(' ').collect();
-        
-        if tokens.len() != 6 {
-            return Err("Invalid number of fields in FEN string")
+    /// 
+    /// The halfmove clock and fullmove number fields may be omitted, in which 
+    /// case they default to 0 and 1, respectively. On failure, the error 
+    /// holds the byte offset in `fen` of the problem
+    /// 
+    /// The en passant square is only kept if a pawn of the side to move could
+    /// capture on it, as when [making](Self::make_move()) the double push, so
+    /// that the same position has the same Zobrist key however it was reached.
+    /// Otherwise it is dropped, and the FEN of the position has `-` instead.
+    pub fn from_fen_string(fen: String) -> Result<Position, FenError> {
+        // Each field with the byte offset it starts at
+        let mut fields = Vec::new();
+        let mut start = 0;
+        for token in fen.split(' ') {
+            fields.push((start, token));
+            start += token.len() + 1;
         }
         
-        let placement_str = tokens[0];
-        let turn_str = tokens[1];
-        let castling_str = tokens[2];
-        let en_passant_str = tokens[3];
-        let halfmove_str = tokens[4];
-        let fullmove_str = tokens[5];
+        if !(4..=6).contains(&fields.len()) {
+            return Err(FenError::FieldCount(fields.len()))
+        }
         
-        let p = Position {
-            board: Board::from_placement(get_placement(placement_str)),
-            turn: get_turn(turn_str),
-            castling: get_castling(castling_str),
-            en_passant: get_en_passant(en_passant_str),
-            halfmove: get_number(halfmove_str),
-            fullmove: get_number(fullmove_str),
+        let placement = get_placement(fields[0])?;
+        let board = Board::from_placement(placement);
+        let turn = get_turn(fields[1])?;
+        let castling = get_castling(fields[2], &board)?;
+        let en_passant = get_en_passant(fields[3])?;
+        let halfmove = match fields.get(4) {
+            Some(&field) => get_number(field).map_err(|byte| FenError::BadHalfmove { byte })?,
+            None => 0,
+        };
+        let fullmove = match fields.get(5) {
+            Some(&field) => get_number(field).map_err(|byte| FenError::BadFullmove { byte })?,
+            None => 1,
+        };
 
+        let mut p = Position {
+            board,
+            turn,
+            castling,
+            en_passant: None,
+            halfmove,
+            fullmove,
+            zobrist: 0,
+            history: Vec::new(),
+            attacks: ColorMap::default(),
         };
+
+        // An impossible en passant square is dropped rather than rejected
+        if en_passant.is_some_and(|s| p.en_passant_is_valid(s)) {
+            p.en_passant = en_passant;
+        }
+        p.rebuild_zobrist();
         
         Ok(p)
     }
 }
 
+/// # Update methods
+impl Position {
+    /// Sets the en passant square, keeping the Zobrist key in sync
+    /// 
+    /// Fails if `s` couldn't have been skipped by a double pawn push on the 
+    /// last move, i.e. if it isn't an empty square on the 6th (3rd) rank, with
+    /// an empty square behind it and an enemy pawn in front of it, or if no
+    /// pawn of the side to move could capture on it (as
+    /// [`make_move`](Self::make_move()) only records it then)
+    pub fn set_en_passant(&mut self, s: Option<Square>) -> Result<(), &'static str> {
+        if let Some(s) = s {
+            if !self.en_passant_is_valid(s) {
+                return Err("Invalid en passant square")
+            }
+        }
+        self.zobrist ^= zobrist::en_passant_key(self.en_passant) ^ 
+                        zobrist::en_passant_key(s);
+        self.en_passant = s;
+        Ok(())
+    }
+
+    /// Sets the side to move, keeping the Zobrist key in sync
+    /// 
+    /// If the turn changes, the en passant square is cleared, since it was 
+    /// for the other side's pawns.
+    pub fn set_turn(&mut self, c: Color) {
+        if c == self.turn {
+            return
+        }
+        self.zobrist ^= zobrist::turn_key() ^ 
+                        zobrist::en_passant_key(self.en_passant) ^ 
+                        zobrist::en_passant_key(None);
+        self.en_passant = None;
+        self.turn = c;
+    }
+
+    /// Clears the cached [attacks](Self::attacks_by()) of both colors
+    /// 
+    /// Moves clear them already, and so does [`board_mut`](Self::board_mut()),
+    /// but they must be cleared after mutating the `board` field directly.
+    #[inline]
+    pub fn clear_attacks(&mut self) {
+        self.attacks = ColorMap::default();
+    }
+
+    /// Gets the board for editing, clearing the cached
+    /// [attacks](Self::attacks_by()) that the edits may change
+    /// 
+    /// The Zobrist key must still be [rebuilt](Self::rebuild_zobrist())
+    /// after the edits.
+    #[inline]
+    pub fn board_mut(&mut self) -> &mut Board {
+        self.clear_attacks();
+        &mut self.board
+    }
+
+    /// Sets the number of halfmoves since the last capture or pawn move
+    #[inline]
+    pub fn set_halfmove_clock(&mut self, halfmove: u32) {
+        self.halfmove = halfmove;
+    }
+
+    /// Sets the fullmove number
+    #[inline]
+    pub fn set_fullmove_number(&mut self, fullmove: u32) {
+        self.fullmove = fullmove;
+    }
+
+    /// Returns `true` if `s` could have been skipped by the opponent's double
+    /// pawn push on the last move, and a pawn of the side to move could
+    /// capture on it
+    fn en_passant_is_valid(&self, s: Square) -> bool {
+        let them = !self.turn;
+        let (files, ranks) = them.forward().offsets();
+        let (Some(from), Some(to)) = (s.offset(-files, -ranks), s.offset(files, ranks)) else {
+            return false
+        };
+        s.rank() == self.turn.en_passant_rank() &&
+        self.board.get(s).is_none() &&
+        self.board.get(from).is_none() &&
+        self.board.get(to) == Some(Piece(them, Role::Pawn)) &&
+        (pawn_attacks(them, s) & self.board.piece(Piece(self.turn, Role::Pawn))).is_any()
+    }
+}
+
+/// # Validation methods
+impl Position {
+    /// Checks that the position could arise in a game, returning what's wrong
+    /// with it if not
+    /// 
+    /// The kings must not be adjacent, pawns must not be on the back ranks, 
+    /// each side's pieces must be reachable from the starting set by 
+    /// promotion, the side not to move must not be in check, and the castling
+    /// rights and en passant square must agree with the board
+    pub fn validate(&self) -> Result<(), &'static str> {
+        let white_king = self.board.king_square(Color::White);
+        let black_king = self.board.king_square(Color::Black);
+        if crate::movegen::king_attacks(white_king).contains(black_king) {
+            return Err("The kings are adjacent")
+        }
+
+        let back_ranks = Bitboard::rank(Rank::First) | Bitboard::rank(Rank::Eighth);
+        if (self.board.role(Role::Pawn) & back_ranks).is_any() {
+            return Err("There are pawns on the back ranks")
+        }
+
+        for c in [Color::White, Color::Black] {
+            if self.board.color(c).count() > 16 {
+                return Err("A side has more than 16 pieces")
+            }
+            let pawns = self.board.piece(Piece(c, Role::Pawn)).count();
+            if pawns > 8 {
+                return Err("A side has more than 8 pawns")
+            }
+            // Every piece beyond the starting set must have been a pawn
+            let promoted: u32 = [
+                (Role::Knight, 2),
+                (Role::Bishop, 2),
+                (Role::Rook, 2),
+                (Role::Queen, 1),
+            ].into_iter()
+                .map(|(r, n)| self.board.piece(Piece(c, r)).count().saturating_sub(n))
+                .sum();
+            if pawns + promoted > 8 {
+                return Err("A side has more promoted pieces than missing pawns")
+            }
+        }
+
+        let them = !self.turn;
+        let their_king = self.board.king_square(them);
+        if self.attackers(their_king, self.turn).is_any() {
+            return Err("The side not to move is in check")
+        }
+        if self.checkers().count() > 2 {
+            return Err("The side to move is in more than double check")
+        }
+
+        for (c, cs, o_f) in self.castling.iter_rook_files() {
+            let Some(f) = o_f else { continue };
+            let back_rank = c.back_rank();
+            let king = self.board.king_square(c);
+            if king.rank() != back_rank {
+                return Err("A side has castling rights without its king on the back rank")
+            }
+            let rook = Square::from(Coords(f, back_rank));
+            if self.board.get(rook) != Some(Piece(c, Role::Rook)) {
+                return Err("A side has castling rights without a rook to castle with")
+            }
+            let on_side = match cs {
+                CastlingSide::Kingside => (f as u32) > (king.file() as u32),
+                CastlingSide::Queenside => (f as u32) < (king.file() as u32),
+            };
+            if !on_side {
+                return Err("A castling rook is on the wrong side of its king")
+            }
+        }
+
+        if let Some(s) = self.en_passant {
+            if !self.en_passant_is_valid(s) {
+                return Err("Invalid en passant square")
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// # Read methods
+impl Position {
+    /// Gets the placement of pieces
+    #[inline]
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Gets whose move it is
+    #[inline]
+    pub fn turn(&self) -> Color {
+        self.turn
+    }
+
+    /// Gets the castling rights for either side
+    #[inline]
+    pub fn castling(&self) -> Castling {
+        self.castling
+    }
+
+    /// Returns `true` if color `c` still has the right to castle on side 
+    /// `side`, whether or not castling is legal in the position
+    #[inline]
+    pub fn can_castle(&self, c: Color, side: CastlingSide) -> bool {
+        self.castling.get(c, side)
+    }
+
+    /// Gets color `c`'s castling rights on both sides (see 
+    /// [`can_castle`](Self::can_castle()))
+    #[inline]
+    pub fn castling_rights(&self, c: Color) -> CastlingRights {
+        CastlingRights {
+            kingside: self.can_castle(c, CastlingSide::Kingside),
+            queenside: self.can_castle(c, CastlingSide::Queenside),
+        }
+    }
+
+    /// Gets the en passant square, if any
+    #[inline]
+    pub fn en_passant(&self) -> Option<Square> {
+        self.en_passant
+    }
+
+    /// Gets the number of halfmoves since the last capture or pawn move
+    #[inline]
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove
+    }
+
+    /// Gets the fullmove number
+    #[inline]
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove
+    }
+
+    /// Gets the squares attacked by color `c`'s pieces, as computed by 
+    /// [`attacked_squares`](Self::attacked_squares())
+    /// 
+    /// The squares are computed when first asked for and kept until the next
+    /// move is made or unmade, so that evaluation terms such as king safety
+    /// can share them. Debug builds check that the cache isn't stale, as it
+    /// would be after editing the `board` field without
+    /// [`board_mut`](Self::board_mut()).
+    #[inline]
+    pub fn attacks_by(&self, c: Color) -> Bitboard {
+        let attacks = *self.attacks[c].get_or_init(|| self.attacked_squares(c));
+        debug_assert_eq!(attacks, self.attacked_squares(c), "Stale attacks cache");
+        attacks
+    }
+
+    /// Gets the number of positions in the game history, counting the current
+    /// one
+    #[inline]
+    pub fn history_len(&self) -> usize {
+        self.history.len() + 1
+    }
+
+    /// Gets the number of times the position has occurred, counting itself
+    ///
+    /// Only the positions since the last capture or pawn move are compared, 
+    /// as none before it can repeat. A count of 3 allows a draw to be claimed 
+    /// (threefold repetition), and a count of 5 is a draw (fivefold)
+    pub fn repetition_count(&self) -> u32 {
+        let reversible = (self.halfmove as usize).min(self.history.len());
+        let count = self.history[self.history.len() - reversible..]
+            .iter()
+            .rev()
+            .skip(1)
+            .step_by(2)
+            .filter(|&&key| key == self.zobrist)
+            .count();
+        1 + count as u32
+    }
+}
+
 impl Default for Position {
     /// Creates the starting position of a chess game
     fn default() -> Self {
-        Self { 
-            board: Default::default(), 
-            turn: Color::White, 
-            castling: Default::default(), 
-            en_passant: Default::default(), 
-            halfmove: 0, 
-            fullmove: 0
-        }
+        Position::from_parts(
+            Default::default(), 
+            Color::White, 
+            Default::default(), 
+            Default::default(), 
+            0, 
+            0
+        )
     }
 }
 
 impl Flippable for Position {
+    /// Mirrors the ranks and swaps the colors of everything in the position,
+    /// giving the same position from the other side's point of view
+    /// 
+    /// The history of the position isn't kept.
     fn flipped(&self) -> Self {
-        Position {
-            board: self.board.flipped(),
-            turn: self.turn,
-            castling: self.castling,
-            en_passant: self.en_passant.map(|s| s.flipped()),
-            halfmove: self.halfmove,
-            fullmove: self.fullmove,
-        }
+        Position::from_parts(
+            self.board.flipped(),
+            !self.turn,
+            self.castling.flipped(),
+            self.en_passant.map(|s| s.flipped()),
+            self.halfmove,
+            self.fullmove,
+        )
     }
 }
 
@@ -229,24 +789,68 @@ impl PartialEq for Position {
     }
 }
 
+/// A position compared and hashed with its move clocks, unlike the position
+/// itself, given by [`full_key`](Position::full_key())
+#[derive(Debug, Clone, Copy)]
+pub struct PositionKey<'a>(&'a Position);
+
+impl PartialEq for PositionKey<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0 && 
+        self.0.halfmove == other.0.halfmove && 
+        self.0.fullmove == other.0.fullmove
+    }
+}
+
+impl Eq for PositionKey<'_> {}
+
+impl Hash for PositionKey<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+        state.write_u32(self.0.halfmove);
+        state.write_u32(self.0.fullmove);
+    }
+}
+
 impl Clone for Position {
     fn clone(&self) -> Self {
-        Self { 
-            board: self.board, 
-            turn: self.turn, 
-            castling: self.castling, 
-            en_passant: self.en_passant, 
-            halfmove: self.halfmove, 
-            fullmove: self.fullmove
-        }
+        Self { history: self.history.clone(), ..self.clone_fresh() }
+    }
+}
+
+impl FromStr for Position {
+    type Err = FenError;
+
+    /// Parses a position from a FEN string (see 
+    /// [`from_fen_string`](Position::from_fen_string()))
+    fn from_str(fen: &str) -> Result<Self, Self::Err> {
+        Position::from_fen_string(fen.to_string())
     }
 }
 
 impl Position {
     /// Creates a FEN string from the position
+    #[deprecated(note = "use `Position::fen` instead")]
     pub fn to_fen_string(&self) -> String {
+        self.fen()
+    }
+
+    /// Creates a FEN string from the position, which
+    /// [parses](Position::from_str()) back into the same position
+    pub fn fen(&self) -> String {
         let mut fen = String::new();
-        
+        self.write_fen(&mut fen);
+        fen
+    }
+
+    /// Appends the [FEN string](Self::fen()) of the position to `out`, so 
+    /// that one buffer can be reused for many positions
+    /// 
+    /// Anything already in `out` is kept, so it should be cleared first to 
+    /// hold only this FEN string.
+    pub fn write_fen(&self, out: &mut String) {
+        use std::fmt::Write;
+
         let Position {
             board,
             turn,
@@ -254,33 +858,51 @@ impl Position {
             en_passant,
             halfmove,
             fullmove,
+            ..
         } = self;
         
-        placement_str(board, &mut fen);
-        fen.push(' ');
+        placement_str(board, out);
+        out.push(' ');
 
-        fen.push(char::from(*turn));
-        fen.push(' ');
+        out.push(char::from(*turn));
+        out.push(' ');
 
-        fen.push_str(&castling.to_string());
-        fen.push(' ');
+        // Writing to a `String` never fails
+        let _ = write!(out, "{}", castling);
+        out.push(' ');
 
         match en_passant {
-            Some(s) => fen.push_str(&s.to_string()),
-            None => fen.push('-'),
+            Some(s) => { let _ = write!(out, "{}", s); }
+            None => out.push('-'),
         }
-        fen.push(' ');
 
-        fen.push_str(&halfmove.to_string());
-        fen.push(' ');
-
-        fen.push_str(&fullmove.to_string());
+        let _ = write!(out, " {} {}", halfmove, fullmove);
+    }
 
-        fen
+    /// Creates the six fields of the [FEN string](Self::fen()) separately:
+    /// the piece placement, turn, castling rights, en passant square,
+    /// halfmove clock and fullmove number
+    ///
+    /// Joining them with spaces gives the FEN string, so one field can be
+    /// replaced without rewriting the others.
+    pub fn fen_fields(&self) -> [String; 6] {
+        let mut placement = String::new();
+        placement_str(&self.board, &mut placement);
+        [
+            placement,
+            char::from(self.turn).to_string(),
+            self.castling.to_string(),
+            match self.en_passant {
+                Some(s) => s.to_string(),
+                None => "-".to_string(),
+            },
+            self.halfmove.to_string(),
+            self.fullmove.to_string(),
+        ]
     }
 }
 
-// Position::to_fen_string helper functions
+// Position::fen helper functions
 
 fn placement_str(board: &Board, fen: &mut String) {
     for r in Rank::iter().rev() {
@@ -290,7 +912,7 @@ fn placement_str(board: &Board, fen: &mut String) {
             match board.get(s) {
                 Some(p) => {
                     if space > 0 {
-                        fen.push_str(&space.to_string());
+                        fen.push(char::from(b'0' + space));
                     }
                     fen.push(char::from(p));
                     space = 0;
@@ -300,7 +922,7 @@ fn placement_str(board: &Board, fen: &mut String) {
         }
     
         if space > 0 {
-            fen.push_str(&space.to_string())
+            fen.push(char::from(b'0' + space))
         }
     
         if r != Rank::First {
@@ -310,95 +932,168 @@ fn placement_str(board: &Board, fen: &mut String) {
 }
 
 
-// Position::from_fen_string helper functions
+/// An error from parsing a FEN string, with the byte offset of the problem 
+/// in the string where there is one
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FenError {
+    /// The string doesn't have 4 to 6 space-separated fields (it has this 
+    /// many)
+    FieldCount(usize),
+    /// The piece placement has a bad character, or a rank of the wrong length,
+    /// while reading the given rank
+    BadPlacement { rank: Rank, byte: usize },
+    /// The turn isn't `w` or `b`
+    BadTurn { byte: usize },
+    /// The castling rights have a bad character
+    BadCastling { byte: usize },
+    /// The en passant square isn't `-` or a square
+    BadEnPassant { byte: usize },
+    /// The halfmove clock isn't a number
+    BadHalfmove { byte: usize },
+    /// The fullmove number isn't a number
+    BadFullmove { byte: usize },
+}
+
+impl Display for FenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FenError::FieldCount(n) => write!(f, "Invalid number of fields in FEN string ({})", n),
+            FenError::BadPlacement { rank, byte } => 
+                write!(f, "Invalid placement of rank {} at byte {}", char::from(*rank), byte),
+            FenError::BadTurn { byte } => write!(f, "Invalid turn at byte {}", byte),
+            FenError::BadCastling { byte } => write!(f, "Invalid castling rights at byte {}", byte),
+            FenError::BadEnPassant { byte } => write!(f, "Invalid en passant square at byte {}", byte),
+            FenError::BadHalfmove { byte } => write!(f, "Invalid halfmove clock at byte {}", byte),
+            FenError::BadFullmove { byte } => write!(f, "Invalid fullmove number at byte {}", byte),
+        }
+    }
+}
 
-fn get_placement(s: &str) -> [Option<Piece>; 64] {
+/// Reads positions from `r` lazily, parsing one FEN string per line
+/// 
+/// Blank lines are skipped and surrounding whitespace is ignored, so that a
+/// dump can be streamed without buffering it whole. A malformed line yields
+/// its [`FenError`] and reading carries on with the next line, but reading 
+/// stops at the first I/O error.
+pub fn read_fens<R: BufRead>(r: R) -> impl Iterator<Item = Result<Position, FenError>> {
+    r.lines()
+        .map_while(Result::ok)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.trim().parse())
+}
+
+// Position::from_fen_string helper functions, each given a field with the 
+// byte offset it starts at
+
+fn get_placement((start, s): (usize, &str)) -> Result<[Option<Piece>; 64], FenError> {
     let mut placement = [None; 64];
     let mut f_index = 0;
     let mut r_index = 7;
-    for ch in s.chars() {
-        let s_index = f_index + r_index * 8;
-        if let Some(offset) = ch.to_digit(10) {
-            let offset = offset as usize;
-            assert!(0 < offset && offset < 9);
-            for i in 0..offset {
-                placement[s_index + i] = None;
+    for (i, ch) in s.char_indices() {
+        let error = FenError::BadPlacement {
+            rank: Rank::try_from(r_index as u32).unwrap(),
+            byte: start + i,
+        };
+        if ch == '/' {
+            if f_index != 8 || r_index == 0 {
+                return Err(error)
             }
-            f_index += offset;
-        } else if ch == '/' {
-            assert_eq!(f_index, 8);
             f_index = 0;
             r_index -= 1;
+        } else if let Some(offset) = ch.to_digit(10) {
+            let offset = offset as usize;
+            if !(1..=8).contains(&offset) || f_index + offset > 8 {
+                return Err(error)
+            }
+            f_index += offset;
         } else {
-            match Piece::try_from(ch) {
-                Ok(p) => placement[s_index] = Some(p),
-                Err(ch) => panic!("{ch} is not a valid FEN placement character"),
+            let Ok(p) = Piece::try_from(ch) else { return Err(error) };
+            if f_index == 8 {
+                return Err(error)
             }
+            placement[f_index + r_index * 8] = Some(p);
             f_index += 1;
         }
     }
-    placement
+    if f_index != 8 || r_index != 0 {
+        return Err(FenError::BadPlacement {
+            rank: Rank::try_from(r_index as u32).unwrap(),
+            byte: start + s.len(),
+        })
+    }
+    Ok(placement)
 }
 
-fn get_turn(s: &str) -> Color {
-    assert_eq!(s.len(), 1);
-    let ch = s.chars().next().unwrap();
-    match Color::try_from(ch) {
-        Ok(c) => c,
-        Err(ch) => panic!("{ch} is not a valid FEN turn"),
+fn get_turn((start, s): (usize, &str)) -> Result<Color, FenError> {
+    let mut chs = s.chars();
+    match (chs.next().map(Color::try_from), chs.next()) {
+        (Some(Ok(c)), None) => Ok(c),
+        _ => Err(FenError::BadTurn { byte: start }),
     }
 }
 
-fn get_castling(s: &str) -> Castling {
-    assert!(s.len() < 5);
+fn get_castling((start, s): (usize, &str), board: &Board) -> Result<Castling, FenError> {
     let mut castling = Castling::new();
     if s == "-" {
-        return castling
-    }
-    let (mut w_ks, mut w_qs, mut b_ks, mut b_qs) = (false, false, false, false);
-    for ch in s.chars() {
-        match ch {
-            'K' => w_ks = true,
-            'Q' => w_qs = true,
-            'k' => b_ks = true,
-            'q' => b_qs = true,
-            _ => panic!("{ch} is an invalid FEN castling character")
-        }
-    }
-
-    castling.set(Color::White, CastlingSide::Kingside, w_ks);
-    castling.set(Color::White, CastlingSide::Queenside, w_qs);
-    castling.set(Color::Black, CastlingSide::Kingside, b_ks);
-    castling.set(Color::Black, CastlingSide::Queenside, b_qs);
-
-    castling
-}
-
-fn get_en_passant(s: &str) -> Option<Square> {
-    if s.len() == 1 {
-        assert_eq!(s, "-");
-        None
-    } else if s.len() == 2 {
-        let mut chs = s.chars();
-        let f_ch = chs.next().unwrap();
-        let r_ch = chs.next().unwrap();
-        let f = match File::try_from(f_ch) {
-            Ok(f) => f,
-            Err(ch) => panic!("{ch} is an invalid file character"),
-        };
-        let r = match Rank::try_from(r_ch) {
-            Ok(r) => r,
-            Err(ch) => panic!("{ch} is an invalid rank character"),
+        return Ok(castling)
+    }
+    if s.is_empty() || s.len() > 4 {
+        return Err(FenError::BadCastling { byte: start })
+    }
+    for (i, ch) in s.char_indices() {
+        let c = if ch.is_ascii_uppercase() { Color::White } else { Color::Black };
+        let king_file = board.king_square(c).file();
+        let (cs, f) = match ch.to_ascii_lowercase() {
+            'k' => (
+                CastlingSide::Kingside, 
+                outermost_rook_file(board, c, CastlingSide::Kingside)
+            ),
+            'q' => (
+                CastlingSide::Queenside, 
+                outermost_rook_file(board, c, CastlingSide::Queenside)
+            ),
+            // Shredder notation names the file of the castling rook
+            f_ch => match File::try_from(f_ch) {
+                Ok(f) if (f as u32) > (king_file as u32) => (CastlingSide::Kingside, f),
+                Ok(f) if (f as u32) < (king_file as u32) => (CastlingSide::Queenside, f),
+                _ => return Err(FenError::BadCastling { byte: start + i }),
+            }
         };
-        Some(Square::from(Coords(f, r)))
-    } else {
-        panic!("{s} is invalid en_passant token");
+        castling.set_rook_file(c, cs, Some(f));
     }
+
+    Ok(castling)
 }
 
-fn get_number(s: &str) -> u32 {
-    match s.to_string().parse::<u32>() {
-        Ok(n) => n,
-        Err(_) => panic!("{s} is invalid move number"),
+/// Finds the file of the rook furthest from the color `c` king on side `cs`
+/// of its back rank, falling back to the standard rook file (as in X-FEN)
+fn outermost_rook_file(board: &Board, c: Color, cs: CastlingSide) -> File {
+    let king_s = board.king_square(c);
+    let rooks = board.piece(Piece(c, Role::Rook)) & Bitboard::rank(king_s.rank());
+    let outermost = match cs {
+        CastlingSide::Kingside => rooks.largest_square()
+            .filter(|s| (s.file() as u32) > (king_s.file() as u32)),
+        CastlingSide::Queenside => rooks.smallest_square()
+            .filter(|s| (s.file() as u32) < (king_s.file() as u32)),
+    };
+    outermost.map_or(cs.standard_rook_file(), |s| s.file())
+}
+
+fn get_en_passant((start, s): (usize, &str)) -> Result<Option<Square>, FenError> {
+    if s == "-" {
+        return Ok(None)
+    }
+    let mut chs = s.chars();
+    let f = chs.next().and_then(|ch| File::try_from(ch).ok());
+    let r = chs.next().and_then(|ch| Rank::try_from(ch).ok());
+    match (f, r, chs.next()) {
+        (Some(f), Some(r), None) => Ok(Some(Square::from(Coords(f, r)))),
+        _ => Err(FenError::BadEnPassant { byte: start }),
     }
-}
\ No newline at end of file
+}
+
+/// Parses a number, or gets the byte offset of the field if it isn't one
+fn get_number((start, s): (usize, &str)) -> Result<u32, usize> {
+    s.parse::<u32>().map_err(|_| start)
+}
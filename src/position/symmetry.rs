@@ -0,0 +1,134 @@
+//! The symmetries of the board that map a position to an equivalent one, for
+//! finding a canonical representative among them
+
+use crate::bits::{Flippable, Square};
+use super::{Castling, Piece, Position, Role};
+use super::board::Board;
+
+/// A symmetry of the square board, as a map of squares
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Symmetry {
+    Identity,
+    /// Mirrors the files (a to h)
+    Horizontal,
+    /// Mirrors the ranks (1 to 8)
+    Vertical,
+    /// Turns the board by half a turn
+    Rotate180,
+    /// Mirrors the board in the a1-h8 diagonal
+    Diagonal,
+    /// Mirrors the board in the a8-h1 diagonal
+    AntiDiagonal,
+    /// Turns the board by a quarter turn clockwise
+    Rotate90,
+    /// Turns the board by a quarter turn anticlockwise
+    Rotate270,
+}
+
+impl Symmetry {
+    const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Horizontal,
+        Symmetry::Vertical,
+        Symmetry::Rotate180,
+        Symmetry::Diagonal,
+        Symmetry::AntiDiagonal,
+        Symmetry::Rotate90,
+        Symmetry::Rotate270,
+    ];
+
+    /// Gets the square that `s` maps to
+    fn apply(self, s: Square) -> Square {
+        let (f, r) = (s.index() as u32 % 8, s.index() as u32 / 8);
+        let (f, r) = match self {
+            Symmetry::Identity => (f, r),
+            Symmetry::Horizontal => (7 - f, r),
+            Symmetry::Vertical => (f, 7 - r),
+            Symmetry::Rotate180 => (7 - f, 7 - r),
+            Symmetry::Diagonal => (r, f),
+            Symmetry::AntiDiagonal => (7 - r, 7 - f),
+            Symmetry::Rotate90 => (r, 7 - f),
+            Symmetry::Rotate270 => (7 - r, f),
+        };
+        Square::new(r * 8 + f)
+    }
+
+    /// Returns `true` if the symmetry maps a position to an equivalent one,
+    /// with the colors swapped if `swap`
+    ///
+    /// With pawns, the ranks must be mirrored exactly when the colors are
+    /// swapped, so that pawns still move forwards. With castling rights, only
+    /// the identity and swapping the colors with the ranks mirrored (as
+    /// [`flipped`](Flippable::flipped()) does) keep them.
+    fn applies(self, swap: bool, pawns: bool, castling: bool) -> bool {
+        use Symmetry::*;
+        if castling {
+            matches!((self, swap), (Identity, false) | (Vertical, true))
+        } else if pawns {
+            match swap {
+                false => matches!(self, Identity | Horizontal),
+                true => matches!(self, Vertical | Rotate180),
+            }
+        } else {
+            true
+        }
+    }
+}
+
+/// # Create methods
+impl Position {
+    /// Gets the position's image under `sym`, with the colors swapped (and
+    /// the turn passed to the other side) if `swap`
+    fn transformed(&self, sym: Symmetry, swap: bool) -> Position {
+        let mut placement = [None; Square::COUNT];
+        for s in Square::iter() {
+            placement[sym.apply(s).index()] = self.board.get(s)
+                .map(|Piece(c, r)| Piece(if swap { !c } else { c }, r));
+        }
+        let castling = match (sym, swap) {
+            (Symmetry::Identity, false) => self.castling,
+            (Symmetry::Vertical, true) => self.castling.flipped(),
+            _ => Castling::new(),
+        };
+        Position::from_parts(
+            Board::from_placement(placement),
+            if swap { !self.turn } else { self.turn },
+            castling,
+            self.en_passant.map(|s| sym.apply(s)),
+            self.halfmove,
+            self.fullmove,
+        )
+    }
+
+    /// Gets the images of the position under each symmetry that maps it to
+    /// an equivalent position, starting with the position itself
+    fn symmetries(&self) -> Vec<Position> {
+        let pawns = self.board.role(Role::Pawn).is_any();
+        let castling = self.castling != Castling::new();
+        [false, true]
+            .into_iter()
+            .flat_map(|swap| Symmetry::ALL.map(|sym| (sym, swap)))
+            .filter(|&(sym, swap)| sym.applies(swap, pawns, castling))
+            .map(|(sym, swap)| self.transformed(sym, swap))
+            .collect()
+    }
+
+    /// Gets the canonical form of the position among its images under the
+    /// symmetries of the board: the one whose FEN string is lexicographically
+    /// smallest
+    ///
+    /// Swapping the colors and mirroring the ranks always gives an equivalent
+    /// position. Mirroring the files does too without castling rights, and
+    /// without pawns as well, so does every mirroring or turning of the
+    /// board, with or without the colors swapped.
+    ///
+    /// Positions that are mirror images of each other have the same canonical
+    /// form, for deduplicating opening books and puzzles. The history of the
+    /// position isn't kept.
+    pub fn canonical_symmetry(&self) -> Position {
+        self.symmetries()
+            .into_iter()
+            .min_by_key(|p| p.fen())
+            .unwrap()
+    }
+}
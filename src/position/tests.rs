@@ -0,0 +1,1165 @@
+#[cfg(test)]
+mod castling_tests {
+    use crate::bits::File;
+    use crate::position::{Position, Color};
+    use crate::position::castling::{CastlingSide, CastlingNotation};
+
+    const CHESS960_SHREDDER: &str = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9";
+    const CHESS960_XFEN: &str = "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w KQkq - 2 9";
+
+    #[test]
+    fn standard_round_trip() {
+        let fen = "r3k2r/8/8/8/8/8/8/R3K2R b Kq - 0 1";
+        let p = Position::from_fen_string(fen.to_string()).unwrap();
+        assert_eq!(p.castling.notation(), CastlingNotation::Standard);
+        assert_eq!(p.fen(), fen);
+    }
+
+    #[test]
+    fn shredder_round_trip() {
+        let p = Position::from_fen_string(CHESS960_SHREDDER.to_string()).unwrap();
+        assert_eq!(p.castling.rook_file(Color::White, CastlingSide::Kingside), Some(File::H));
+        assert_eq!(p.castling.rook_file(Color::White, CastlingSide::Queenside), Some(File::F));
+        assert_eq!(p.castling.rook_file(Color::Black, CastlingSide::Kingside), Some(File::H));
+        assert_eq!(p.castling.rook_file(Color::Black, CastlingSide::Queenside), Some(File::F));
+        assert_eq!(p.castling.notation(), CastlingNotation::Shredder);
+        assert_eq!(p.fen(), CHESS960_SHREDDER);
+    }
+
+    #[test]
+    fn xfen_round_trip() {
+        let p1 = Position::from_fen_string(CHESS960_XFEN.to_string()).unwrap();
+        let p2 = Position::from_fen_string(CHESS960_SHREDDER.to_string()).unwrap();
+        assert_eq!(p1.castling, p2.castling);
+        assert_eq!(p1.castling.to_string_with(CastlingNotation::Standard), "KQkq");
+        assert_eq!(p1.castling.to_string_with(CastlingNotation::Shredder), "HFhf");
+    }
+
+    #[test]
+    fn rights() {
+        use crate::position::castling::CastlingRights;
+        let both = CastlingRights { kingside: true, queenside: true };
+        let mut p = Position::default();
+        for c in [Color::White, Color::Black] {
+            assert!(p.can_castle(c, CastlingSide::Kingside));
+            assert!(p.can_castle(c, CastlingSide::Queenside));
+            assert_eq!(p.castling_rights(c), both);
+        }
+
+        // Once the white king has moved, white has no rights left
+        for uci in ["e2e4", "e7e5", "e1e2"] {
+            p.push_uci(uci).unwrap();
+        }
+        assert!(!p.can_castle(Color::White, CastlingSide::Kingside));
+        assert!(!p.can_castle(Color::White, CastlingSide::Queenside));
+        assert!(!p.castling_rights(Color::White).any());
+        assert_eq!(p.castling_rights(Color::Black), both);
+
+        // Each side separately
+        let p = Position::from_fen_string("r3k2r/8/8/8/8/8/8/R3K2R b Kq - 0 1".to_string()).unwrap();
+        assert_eq!(p.castling_rights(Color::White), CastlingRights { kingside: true, queenside: false });
+        assert_eq!(p.castling_rights(Color::Black), CastlingRights { kingside: false, queenside: true });
+    }
+
+    #[test]
+    fn no_rights() {
+        let fen = "4k3/8/8/8/8/8/8/4K3 w - - 0 1";
+        let p = Position::from_fen_string(fen.to_string()).unwrap();
+        assert_eq!(p.castling.to_string_with(CastlingNotation::Shredder), "-");
+        assert_eq!(p.fen(), fen);
+    }
+}
+
+#[cfg(test)]
+mod zobrist_tests {
+    use crate::bits::Square;
+    use crate::position::{Position, Color};
+    use crate::position::castling::CastlingSide;
+    use crate::position::util::*;
+
+    const FEN: &str = "r3k2r/pp3ppp/8/3pP3/8/8/PP3PPP/R3K2R w KQkq d6 0 12";
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    #[test]
+    fn parse_is_deterministic() {
+        assert_eq!(parse(FEN).zobrist_key(), parse(FEN).zobrist_key());
+    }
+
+    #[test]
+    fn fields_change_key() {
+        let p = parse(FEN);
+        assert_ne!(p.zobrist_key(), parse("r3k2r/pp3ppp/8/3pP3/8/8/PP3PPP/R3K2R b KQkq d6 0 12").zobrist_key());
+        assert_ne!(p.zobrist_key(), parse("r3k2r/pp3ppp/8/3pP3/8/8/PP3PPP/R3K2R w Kkq d6 0 12").zobrist_key());
+        assert_ne!(p.zobrist_key(), parse("r3k2r/pp3ppp/8/3pP3/8/8/PP3PPP/R3K2R w KQkq - 0 12").zobrist_key());
+        assert_eq!(p.zobrist_key(), parse("r3k2r/pp3ppp/8/3pP3/8/8/PP3PPP/R3K2R w KQkq d6 7 40").zobrist_key());
+    }
+
+    #[test]
+    fn rebuild_after_edit() {
+        let mut p = parse(FEN);
+        let old_key = p.zobrist_key();
+
+        p.board.place(Square::new(20), WHITE_KNIGHT);
+        p.turn = Color::Black;
+        p.castling.set(Color::White, CastlingSide::Queenside, false);
+        p.en_passant = None;
+        assert_eq!(p.zobrist_key(), old_key);
+
+        p.rebuild_zobrist();
+        assert_ne!(p.zobrist_key(), old_key);
+        assert_eq!(p.zobrist_key(), parse(&p.fen()).zobrist_key());
+    }
+
+    #[test]
+    fn from_parts() {
+        let p1 = parse(FEN);
+        let p2 = Position::from_parts(
+            p1.board, 
+            p1.turn, 
+            p1.castling, 
+            p1.en_passant, 
+            p1.halfmove, 
+            p1.fullmove
+        );
+        assert_eq!(p1.zobrist_key(), p2.zobrist_key());
+    }
+
+    #[test]
+    fn hash_agrees_with_eq() {
+        use std::hash::BuildHasher;
+        use crate::position::zobrist::BuildZobristHasher;
+
+        let fens = [
+            FEN,
+            "r3k2r/pp3ppp/8/3pP3/8/8/PP3PPP/R3K2R w KQkq d6 7 40",
+            "r3k2r/pp3ppp/8/3pP3/8/8/PP3PPP/R3K2R w KQkq - 0 12",
+            "r3k2r/pp3ppp/8/3pP3/8/8/PP3PPP/R3K2R b KQkq - 0 12",
+            "r3k2r/pp3ppp/8/3pP3/8/8/PP3PPP/R3K2R w Kkq - 0 12",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        ];
+        // The start position again, after shuffling the knights
+        let mut shuffled = parse(fens[5]);
+        for uci in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+            shuffled.push_uci(uci).unwrap();
+        }
+        let mut positions: Vec<Position> = fens.iter().map(|fen| parse(fen)).collect();
+        positions.push(shuffled);
+
+        let random = std::hash::RandomState::new();
+        for a in &positions {
+            for b in &positions {
+                if a == b {
+                    assert_eq!(random.hash_one(a), random.hash_one(b), "{} {}", a.fen(), b.fen());
+                    assert_eq!(BuildZobristHasher.hash_one(a), BuildZobristHasher.hash_one(b));
+                    assert_eq!(a.zobrist_key(), b.zobrist_key());
+                }
+                if a.full_key() == b.full_key() {
+                    assert_eq!(random.hash_one(a.full_key()), random.hash_one(b.full_key()));
+                    assert_eq!(a.fen(), b.fen());
+                }
+            }
+        }
+        // Only the clocks differ between the first two
+        assert_eq!(positions[0], positions[1]);
+        assert_ne!(positions[0].full_key(), positions[1].full_key());
+        // As between the start position and the shuffled one
+        assert_eq!(positions[5], positions[6]);
+        assert_ne!(positions[5].full_key(), positions[6].full_key());
+        assert_eq!(positions[5].full_key(), parse(fens[5]).full_key());
+    }
+
+    /// Asserts that the key after `uci` in `fen` is the same whether it's
+    /// predicted, updated incrementally or rebuilt, and returns the position
+    /// after it
+    fn assert_key_after(fen: &str, uci: &str) -> Position {
+        let mut p = parse(fen);
+        let m = p.parse_uci(uci).unwrap();
+        let predicted = p.key_after(m);
+        p.make_move(m);
+        assert_eq!(predicted, p.zobrist_key(), "{} in {}", uci, fen);
+        assert_eq!(p.zobrist_key(), parse(&p.fen()).zobrist_key(), "{} in {}", uci, fen);
+        p
+    }
+
+    #[test]
+    fn key_after_every_move() {
+        for fen in [
+            FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/Pp2P3/2N2Q1p/1PPBBPPP/R3K2R b KQkq a3 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+            "rk5r/8/8/8/8/8/8/RK5R w HAha - 0 1",
+        ] {
+            let p = parse(fen);
+            for m in p.generate() {
+                assert_key_after(fen, &p.uci(m));
+            }
+        }
+    }
+
+    #[test]
+    fn key_after_castling_rights() {
+        // A rook move loses its side's right
+        let p = assert_key_after(FEN, "a1b1");
+        assert_eq!(p.castling.to_string(), "Kkq");
+        // A king move loses both
+        let p = assert_key_after(FEN, "e1d1");
+        assert_eq!(p.castling.to_string(), "kq");
+        // As does castling
+        let p = assert_key_after(FEN, "e1g1");
+        assert_eq!(p.castling.to_string(), "kq");
+        // Capturing a rook loses its owner's right
+        let p = assert_key_after("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "a1a8");
+        assert_eq!(p.castling.to_string(), "Kk");
+    }
+
+    #[test]
+    fn key_after_en_passant() {
+        // A double push with an enemy pawn beside it sets the en passant square
+        let p = assert_key_after("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1", "e2e4");
+        assert_eq!(p.en_passant, Some(Square::new(20)));
+        // But not without one
+        let p = assert_key_after("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", "e2e4");
+        assert_eq!(p.en_passant, None);
+        // Any move clears it, whether capturing en passant or not
+        let p = assert_key_after(FEN, "e5d6");
+        assert_eq!(p.en_passant, None);
+        let p = assert_key_after(FEN, "h2h3");
+        assert_eq!(p.en_passant, None);
+        // Or replaces it, for another double push
+        let p = assert_key_after("4k3/2p5/8/1P6/3pP3/8/8/4K3 b - e3 0 1", "c7c5");
+        assert_eq!(p.en_passant, Some(Square::new(42)));
+    }
+}
+
+#[cfg(test)]
+mod fen_tests {
+    use crate::bits::{Rank, Square};
+    use crate::position::{Position, Color, FenError};
+    use crate::position::castling::CastlingSide;
+
+    fn parse(fen: &str) -> Result<Position, FenError> {
+        Position::from_fen_string(fen.to_string())
+    }
+
+    #[test]
+    fn six_fields() {
+        let p = parse("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 3 7").unwrap();
+        assert_eq!((p.halfmove, p.fullmove), (3, 7));
+    }
+
+    #[test]
+    fn five_fields() {
+        let p = parse("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 3").unwrap();
+        assert_eq!((p.halfmove, p.fullmove), (3, 1));
+    }
+
+    #[test]
+    fn four_fields() {
+        let p = parse("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3").unwrap();
+        assert_eq!((p.halfmove, p.fullmove), (0, 1));
+        // No black pawn can take on e3, so the square is dropped
+        assert_eq!(p.fen(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1");
+        let p = parse("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3").unwrap();
+        assert_eq!(p.fen(), "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+    }
+
+    #[test]
+    fn round_trip() {
+        for fen in [
+            "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 3 7",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        ] {
+            let p: Position = fen.parse().unwrap();
+            assert_eq!(p.fen(), fen);
+            assert_eq!(p.fen().parse::<Position>().unwrap(), p);
+        }
+        assert!(matches!("8/8/8 w - -".parse::<Position>(), Err(FenError::BadPlacement { .. })));
+
+        // An en passant square no pawn can take on doesn't survive the trip
+        let p: Position = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 3 7".parse().unwrap();
+        assert_eq!(p.fen(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 3 7");
+        assert_eq!(p.fen().parse::<Position>().unwrap(), p);
+    }
+
+    #[test]
+    fn write_fen() {
+        let mut buffer = String::from("left over");
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w Kq f6 0 3",
+            "8/8/8/8/8/8/8/K6k b - - 49 100",
+        ] {
+            let p = parse(fen).unwrap();
+            buffer.clear();
+            p.write_fen(&mut buffer);
+            assert_eq!(buffer, p.fen());
+            assert_eq!(buffer, fen);
+        }
+
+        // Without clearing, the FEN string is appended
+        let mut buffer = String::from("fen: ");
+        Position::default().write_fen(&mut buffer);
+        assert_eq!(buffer, format!("fen: {}", Position::default().fen()));
+    }
+
+    #[test]
+    fn getters() {
+        let mut p = parse("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w Kq f6 0 3").unwrap();
+        assert_eq!(p.turn(), Color::White);
+        assert!(p.castling().get(Color::White, CastlingSide::Kingside));
+        assert!(!p.castling().get(Color::White, CastlingSide::Queenside));
+        assert!(!p.castling().get(Color::Black, CastlingSide::Kingside));
+        assert!(p.castling().get(Color::Black, CastlingSide::Queenside));
+        assert_eq!(p.en_passant(), Some(Square::new(45)));
+        assert_eq!(p.halfmove_clock(), 0);
+        assert_eq!(p.fullmove_number(), 3);
+        assert_eq!(p.board().king_square(Color::Black), Square::new(60));
+
+        p.set_halfmove_clock(12);
+        p.set_fullmove_number(30);
+        assert_eq!((p.halfmove_clock(), p.fullmove_number()), (12, 30));
+    }
+
+    #[test]
+    fn too_few_fields() {
+        assert!(parse("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq").is_err());
+    }
+
+    #[test]
+    fn too_many_fields() {
+        assert_eq!(
+            parse("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1 0").unwrap_err(),
+            FenError::FieldCount(7)
+        );
+    }
+
+    #[test]
+    fn error_spans() {
+        // An `x` in the 3rd rank
+        assert_eq!(
+            parse("rnbqkbnr/pppppppp/8/8/4P3/2x5/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap_err(),
+            FenError::BadPlacement { rank: Rank::Third, byte: 27 }
+        );
+        // The 3rd rank has 9 squares
+        assert_eq!(
+            parse("rnbqkbnr/pppppppp/8/8/4P3/5P3/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap_err(),
+            FenError::BadPlacement { rank: Rank::Third, byte: 28 }
+        );
+        // The 6th rank has 7 squares
+        assert_eq!(
+            parse("rnbqkbnr/pppppppp/7/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap_err(),
+            FenError::BadPlacement { rank: Rank::Sixth, byte: 19 }
+        );
+        // Only 7 ranks
+        assert_eq!(
+            parse("rnbqkbnr/pppppppp/8/8/4P3/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap_err(),
+            FenError::BadPlacement { rank: Rank::Second, byte: 43 }
+        );
+        let start = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR";
+        assert_eq!(parse(&format!("{start} x KQkq - 0 1")).unwrap_err(), FenError::BadTurn { byte: 44 });
+        assert_eq!(parse(&format!("{start} w KQxq - 0 1")).unwrap_err(), FenError::BadCastling { byte: 48 });
+        assert_eq!(parse(&format!("{start} w KQkq e9 0 1")).unwrap_err(), FenError::BadEnPassant { byte: 51 });
+        assert_eq!(parse(&format!("{start} w KQkq - x 1")).unwrap_err(), FenError::BadHalfmove { byte: 53 });
+        assert_eq!(parse(&format!("{start} w KQkq - 0 -1")).unwrap_err(), FenError::BadFullmove { byte: 55 });
+    }
+
+    #[test]
+    fn read_fens() {
+        use crate::position::read_fens;
+        let dump = "\
+rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1
+
+  4k3/8/8/8/8/8/8/4K3 b - - 5 30\r
+rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1
+rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3
+";
+        let results: Vec<_> = read_fens(dump.as_bytes()).collect();
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0], Ok(Position::default()));
+        assert_eq!(results[1].as_ref().unwrap().fen(), "4k3/8/8/8/8/8/8/4K3 b - - 5 30");
+        assert_eq!(results[2], Err(FenError::BadTurn { byte: 44 }));
+        // No black pawn can take on e3, so the square is dropped
+        assert_eq!(results[3].as_ref().unwrap().fen(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1");
+    }
+
+    #[test]
+    fn fen_fields() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            "r3k2r/8/8/8/8/8/8/4K2R w Kq - 12 40",
+            "8/8/4k3/8/8/3K4/8/8 b - - 99 120",
+        ];
+        for fen in fens {
+            let p = parse(fen).unwrap();
+            assert_eq!(p.fen_fields().join(" "), p.fen());
+        }
+        let p = parse(fens[1]).unwrap();
+        assert_eq!(p.fen_fields(), ["rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR", "b", "KQkq", "-", "0", "1"]);
+        let p = parse(fens[2]).unwrap();
+        assert_eq!(p.fen_fields(), ["rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR", "b", "KQkq", "e3", "0", "1"]);
+    }
+}
+
+#[cfg(test)]
+mod make_tests {
+    use crate::bits::Square;
+    use crate::movegen::Move;
+    use crate::position::{Position, Role};
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    /// Checks that every legal move (and every reply) keeps the Zobrist key in
+    /// sync and is undone exactly
+    fn check_round_trips(fen: &str) {
+        let mut p = parse(fen);
+        let before = p.clone();
+        for m in p.generate() {
+            let undo = p.make_move(m);
+            let mut rebuilt = p.clone();
+            rebuilt.rebuild_zobrist();
+            assert_eq!(p.zobrist_key(), rebuilt.zobrist_key(), "{:?}", m);
+            for reply in p.generate() {
+                let undo = p.make_move(reply);
+                let mut rebuilt = p.clone();
+                rebuilt.rebuild_zobrist();
+                assert_eq!(p.zobrist_key(), rebuilt.zobrist_key(), "{:?} {:?}", m, reply);
+                p.unmake_move(undo);
+            }
+            p.unmake_move(undo);
+            assert_eq!(p.fen(), before.fen(), "{:?}", m);
+            assert_eq!(p.zobrist_key(), before.zobrist_key(), "{:?}", m);
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        check_round_trips("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        check_round_trips("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1");
+        check_round_trips("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9");
+        check_round_trips("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+    }
+
+    #[test]
+    fn fields() {
+        let mut p = parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let e4 = Move::DoublePawnPush { from: Square::new(12), to: Square::new(28) };
+        p.make_move(e4);
+        // No black pawn can capture on e3, so no en passant square is set
+        assert_eq!(p.fen(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1");
+
+        let d5 = Move::DoublePawnPush { from: Square::new(51), to: Square::new(35) };
+        p.make_move(d5);
+        let exd5 = Move::PawnMove { from: Square::new(28), to: Square::new(35), promotion: None, en_passant: false, capture: Some(Role::Pawn) };
+        p.make_move(exd5);
+        let kd7 = Move::Normal { role: Role::King, from: Square::new(60), to: Square::new(51), capture: None };
+        p.make_move(kd7);
+        assert_eq!(p.fen(), "rnbq1bnr/pppkpppp/8/3P4/8/8/PPPP1PPP/RNBQKBNR w KQ - 1 3");
+    }
+
+    #[test]
+    fn unmake_counters() {
+        let mut p = parse("4k3/8/8/8/8/8/4P3/4K3 w - - 7 12");
+        let kd1 = Move::Normal { role: Role::King, from: Square::new(4), to: Square::new(3), capture: None };
+        let white = p.make_move(kd1);
+        assert_eq!((p.halfmove_clock(), p.fullmove_number()), (8, 12));
+
+        // Black's move ends the fullmove, and the pawn move resets the clock
+        let kd8 = Move::Normal { role: Role::King, from: Square::new(60), to: Square::new(59), capture: None };
+        let black = p.make_move(kd8);
+        assert_eq!((p.halfmove_clock(), p.fullmove_number()), (9, 13));
+        let e4 = Move::DoublePawnPush { from: Square::new(12), to: Square::new(28) };
+        let pawn = p.make_move(e4);
+        assert_eq!((p.halfmove_clock(), p.fullmove_number()), (0, 13));
+
+        p.unmake_move(pawn);
+        assert_eq!((p.halfmove_clock(), p.fullmove_number()), (9, 13));
+        p.unmake_move(black);
+        assert_eq!((p.halfmove_clock(), p.fullmove_number()), (8, 12));
+        p.unmake_move(white);
+        assert_eq!((p.halfmove_clock(), p.fullmove_number()), (7, 12));
+        assert_eq!(p.fen(), "4k3/8/8/8/8/8/4P3/4K3 w - - 7 12");
+    }
+
+    #[test]
+    fn with_moves() {
+        let mut p = parse("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        let before = p.clone();
+        let first = p.generate()[0];
+        let mut after = p.clone();
+        after.make_move(first);
+        let line = [first, after.generate()[3]];
+        let fen = p.with_moves(&line, |after| after.fen());
+        assert_ne!(fen, before.fen());
+        assert_eq!(p, before);
+        assert_eq!(p.zobrist_key(), before.zobrist_key());
+        assert_eq!(p.history_len(), before.history_len());
+
+        // The moves are unmade while unwinding
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            p.with_moves(&line, |_| panic!("analysis failed"))
+        }));
+        assert!(result.is_err());
+        assert_eq!(p.fen(), before.fen());
+        assert_eq!(p.zobrist_key(), before.zobrist_key());
+    }
+
+    #[test]
+    fn apply_moves_checked() {
+        let mut p = Position::default();
+        let mut line = Vec::new();
+        let mut expected = Position::default();
+        for uci in ["e2e4", "e7e5", "g1f3"] {
+            line.push(expected.push_uci(uci).unwrap());
+        }
+        p.apply_moves_checked(&line).unwrap();
+        assert_eq!(p, expected);
+
+        // After 3...Nf6, white's Nf3 can't be played again from g1
+        let nf6 = Move::Normal { role: Role::Knight, from: Square::new(62), to: Square::new(45), capture: None };
+        let mut corrupt = line[..2].to_vec();
+        corrupt.extend([line[2], nf6, line[2]]);
+        let mut p = Position::default();
+        let (index, reached) = p.apply_moves_checked(&corrupt).unwrap_err();
+        assert_eq!(index, 4);
+        let mut after_nf6 = expected.clone();
+        after_nf6.make_move(nf6);
+        assert_eq!(reached, after_nf6);
+        assert_eq!(p, reached);
+        assert_eq!(reached.history_len(), 5);
+    }
+}
+
+#[cfg(test)]
+mod piece_tests {
+    use crate::position::{Color, Role, Piece};
+
+    #[test]
+    fn color_u8() {
+        for i in 0..2u8 {
+            let c = Color::try_from(i).unwrap();
+            assert_eq!(u8::from(c), i);
+            assert_eq!(c.index(), i as usize);
+        }
+        assert_eq!(Color::try_from(2), Err(2));
+    }
+
+    #[test]
+    fn color_pawn_ranks() {
+        use crate::bits::Rank;
+        use crate::bits::Direction;
+        assert_eq!(Color::White.forward(), Direction::North);
+        assert_eq!(Color::Black.forward(), Direction::South);
+        assert_eq!(Color::White.pawn_start_rank(), Rank::Second);
+        assert_eq!(Color::White.promotion_rank(), Rank::Eighth);
+        assert_eq!(Color::Black.promotion_rank(), Rank::First);
+        assert_eq!(Color::White.en_passant_rank(), Rank::Sixth);
+        assert_eq!(Color::Black.en_passant_rank(), Rank::Third);
+        assert_eq!(Color::White.back_rank(), Rank::First);
+        assert_eq!(Color::Black.back_rank(), Rank::Eighth);
+    }
+
+    #[test]
+    fn role_u8() {
+        for i in 0..6u8 {
+            let r = Role::try_from(i).unwrap();
+            assert_eq!(u8::from(r), i);
+            assert_eq!(r.index(), i as usize);
+        }
+        assert_eq!(Role::try_from(6), Err(6));
+    }
+
+    #[test]
+    fn piece_u8() {
+        for i in 0..12u8 {
+            let p = Piece::try_from(i).unwrap();
+            assert_eq!(u8::from(p), i);
+            assert_eq!(p.index(), i as usize);
+        }
+        assert_eq!(Piece::try_from(0), Ok(Piece(Color::White, Role::Pawn)));
+        assert_eq!(Piece::try_from(11), Ok(Piece(Color::Black, Role::King)));
+        assert_eq!(Piece::try_from(12), Err(12));
+        assert_eq!(Piece::try_from(u8::MAX), Err(u8::MAX));
+    }
+}
+
+#[cfg(test)]
+mod en_passant_tests {
+    use crate::bits::Square;
+    use crate::position::Position;
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    #[test]
+    fn set_valid() {
+        let mut p = parse("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1");
+        let d6 = Square::new(43);
+        assert_eq!(p.set_en_passant(Some(d6)), Ok(()));
+        assert_eq!(p, parse("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1"));
+        assert_eq!(p.zobrist_key(), parse("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").zobrist_key());
+
+        assert_eq!(p.set_en_passant(None), Ok(()));
+        assert_eq!(p.en_passant, None);
+        assert_eq!(p.zobrist_key(), parse("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1").zobrist_key());
+
+        let mut p = parse("4k3/8/8/8/3Pp3/8/8/4K3 b - - 0 1");
+        assert_eq!(p.set_en_passant(Some(Square::new(19))), Ok(()));
+    }
+
+    #[test]
+    fn set_turn() {
+        use crate::position::Color;
+        let mut p = parse("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+        p.set_turn(Color::White);
+        assert_eq!(p.en_passant, Some(Square::new(43)));
+
+        p.set_turn(Color::Black);
+        let expected = parse("4k3/8/8/3pP3/8/8/8/4K3 b - - 0 1");
+        assert_eq!(p.fen(), expected.fen());
+        assert_eq!(p.zobrist_key(), expected.zobrist_key());
+
+        p.set_turn(Color::White);
+        let expected = parse("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1");
+        assert_eq!(p.fen(), expected.fen());
+        assert_eq!(p.zobrist_key(), expected.zobrist_key());
+    }
+
+    #[test]
+    fn set_invalid() {
+        let mut p = parse("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1");
+        let before = p.zobrist_key();
+        // No pawn in front, the wrong rank for the side to move, and an
+        // occupied square
+        assert!(p.set_en_passant(Some(Square::new(42))).is_err());
+        assert!(p.set_en_passant(Some(Square::new(19))).is_err());
+        let mut q = parse("4k3/3n4/8/3pP3/8/8/8/4K3 w - - 0 1");
+        assert!(q.set_en_passant(Some(Square::new(43))).is_err());
+        // No white pawn can take on d6
+        let mut q = parse("4k3/8/8/3p3P/8/8/8/4K3 w - - 0 1");
+        assert!(q.set_en_passant(Some(Square::new(43))).is_err());
+        assert_eq!(p.en_passant, None);
+        assert_eq!(p.zobrist_key(), before);
+    }
+
+    #[test]
+    fn fen_normalizes() {
+        let p = parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e3 0 1");
+        assert_eq!(p.en_passant, None);
+        assert_eq!(p.fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let p = parse("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+        assert_eq!(p.en_passant, Some(Square::new(43)));
+    }
+
+    #[test]
+    fn fen_matches_played() {
+        // No black pawn can take on e3, so neither parsing nor playing e4
+        // keeps the square
+        let parsed = parse("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+        let mut played = Position::default();
+        played.push_uci("e2e4").unwrap();
+        assert_eq!(parsed.en_passant, None);
+        assert_eq!(parsed, played);
+        assert_eq!(parsed.zobrist_key(), played.zobrist_key());
+    }
+}
+
+#[cfg(all(test, feature = "arbitrary"))]
+mod arbitrary_tests {
+    use arbitrary::{Arbitrary, Unstructured};
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    use crate::bits::{Bitboard, Square};
+    use crate::position::{Color, Piece, Position, Role};
+
+    #[test]
+    fn primitives() {
+        let bytes = [0xFF; 64];
+        let mut u = Unstructured::new(&bytes);
+        assert!(usize::from(Square::arbitrary(&mut u).unwrap()) < 64);
+        assert_eq!(Bitboard::arbitrary(&mut u).unwrap(), Bitboard::FULL);
+        Color::arbitrary(&mut u).unwrap();
+        Role::arbitrary(&mut u).unwrap();
+        Piece::arbitrary(&mut u).unwrap();
+    }
+
+    #[test]
+    fn legal_positions() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut bytes = vec![0u8; 256];
+        for _ in 0..500 {
+            rng.fill(&mut bytes[..]);
+            let mut u = Unstructured::new(&bytes);
+            let p = Position::arbitrary(&mut u).unwrap();
+            assert_eq!(p.validate(), Ok(()), "{}", p.fen());
+
+            let fen = p.fen();
+            let q = Position::from_fen_string(fen.clone()).unwrap();
+            assert_eq!(p, q, "{}", fen);
+            assert_eq!(q.fen(), fen);
+            assert_eq!(p.zobrist_key(), q.zobrist_key(), "{}", fen);
+        }
+    }
+}
+
+#[cfg(test)]
+mod validate_tests {
+    use crate::position::Position;
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    #[test]
+    fn valid() {
+        assert_eq!(parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").validate(), Ok(()));
+        assert_eq!(parse("bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9").validate(), Ok(()));
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(parse("8/8/8/3kK3/8/8/8/8 w - - 0 1").validate().is_err());
+        assert!(parse("4k2P/8/8/8/8/8/8/4K3 w - - 0 1").validate().is_err());
+        assert!(parse("4k3/8/8/8/8/8/8/4KR1r w - - 0 1").validate().is_ok());
+        // Black is in check with white to move
+        assert!(parse("4k3/4R3/8/8/8/8/8/4K3 w - - 0 1").validate().is_err());
+        // No rook on h1
+        assert!(parse("4k3/8/8/8/8/8/8/4K3 w K - 0 1").validate().is_err());
+    }
+
+    #[test]
+    fn piece_counts() {
+        // 9 white pawns
+        assert!(parse("4k3/8/8/8/8/P7/PPPPPPPP/4K3 w - - 0 1").validate().is_err());
+        // A third rook, with all 8 pawns still on the board
+        assert!(parse("4k3/8/8/8/8/R7/PPPPPPPP/R3K2R w - - 0 1").validate().is_err());
+        // A third rook and a second queen, with 2 pawns missing
+        assert!(parse("4k3/8/8/8/8/RQ6/PPPPPP2/R2QK2R w - - 0 1").validate().is_ok());
+        assert!(parse("4k3/8/8/8/8/RQ6/PPPPPPP1/R2QK2R w - - 0 1").validate().is_err());
+        // 9 queens for black, promoted from all 8 pawns
+        assert!(parse("qqqqkqqq/8/8/8/8/8/8/qq2K3 w - - 0 1").validate().is_ok());
+        assert!(parse("qqqqkqqq/8/8/8/8/8/q7/qq2K3 w - - 0 1").validate().is_err());
+    }
+}
+
+#[cfg(test)]
+mod repetition_tests {
+    use crate::bits::Square;
+    use crate::movegen::Move;
+    use crate::position::{Position, Role};
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    fn knight_move(from: Square, to: Square) -> Move {
+        Move::Normal { role: Role::Knight, from, to, capture: None }
+    }
+
+    /// Shuffles both kingside knights out and back
+    fn shuffle(p: &mut Position) {
+        p.make_move(knight_move(Square::new(6), Square::new(21)));
+        p.make_move(knight_move(Square::new(62), Square::new(45)));
+        p.make_move(knight_move(Square::new(21), Square::new(6)));
+        p.make_move(knight_move(Square::new(45), Square::new(62)));
+    }
+
+    #[test]
+    fn counts_repeats() {
+        let mut p = parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(p.repetition_count(), 1);
+        p.make_move(knight_move(Square::new(6), Square::new(21)));
+        assert_eq!(p.repetition_count(), 1);
+        p.make_move(knight_move(Square::new(62), Square::new(45)));
+        p.make_move(knight_move(Square::new(21), Square::new(6)));
+        p.make_move(knight_move(Square::new(45), Square::new(62)));
+        assert_eq!(p.repetition_count(), 2);
+        shuffle(&mut p);
+        assert_eq!(p.repetition_count(), 3);
+    }
+
+    #[test]
+    fn unmake_forgets_repeats() {
+        let mut p = parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        shuffle(&mut p);
+        p.make_move(knight_move(Square::new(6), Square::new(21)));
+        p.make_move(knight_move(Square::new(62), Square::new(45)));
+        p.make_move(knight_move(Square::new(21), Square::new(6)));
+        let undo = p.make_move(knight_move(Square::new(45), Square::new(62)));
+        assert_eq!(p.repetition_count(), 3);
+        p.unmake_move(undo);
+        p.make_move(knight_move(Square::new(45), Square::new(39)));
+        assert_eq!(p.repetition_count(), 1);
+    }
+
+    #[test]
+    fn irreversible_moves_reset() {
+        let mut p = parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        shuffle(&mut p);
+        p.make_move(Move::PawnMove {
+            from: Square::new(8),
+            to: Square::new(16),
+            promotion: None,
+            en_passant: false,
+            capture: None,
+        });
+        p.make_move(Move::PawnMove {
+            from: Square::new(48),
+            to: Square::new(40),
+            promotion: None,
+            en_passant: false,
+            capture: None,
+        });
+        assert_eq!(p.repetition_count(), 1);
+        shuffle(&mut p);
+        assert_eq!(p.repetition_count(), 2);
+    }
+
+    #[test]
+    fn clone_fresh() {
+        let mut p = parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        shuffle(&mut p);
+        assert_eq!(p.history_len(), 5);
+        assert_eq!(p.clone().history_len(), 5);
+        assert_eq!(p.clone().repetition_count(), 2);
+
+        let fresh = p.clone_fresh();
+        assert_eq!(fresh, p);
+        assert_eq!(fresh.zobrist_key(), p.zobrist_key());
+        assert_eq!(fresh.fen(), p.fen());
+        assert_eq!(fresh.history_len(), 1);
+        assert_eq!(fresh.repetition_count(), 1);
+    }
+}
+
+#[cfg(test)]
+mod fragment_tests {
+    use crate::bits::{Bitboard, Square};
+    use crate::position::{Color, Piece, Role};
+    use crate::position::board::Board;
+    use crate::position::fragment::BoardFragment;
+    use crate::position::util::*;
+
+    #[test]
+    fn without_kings() {
+        // A rook on a1 and pawns on b7 and c6, as in a puzzle diagram
+        let mut pm = [None; 64];
+        pm[0] = Some(WHITE_ROOK);
+        pm[49] = Some(BLACK_PAWN);
+        pm[42] = Some(BLACK_PAWN);
+        let fragment = BoardFragment::from_placement(pm).unwrap();
+        assert_eq!(fragment.king_square(Color::White), None);
+        assert_eq!(fragment.king_bitboard(Color::Black), Bitboard::EMPTY);
+        assert_eq!(fragment.get(Square::new(0)), Some(WHITE_ROOK));
+        assert_eq!(fragment.piece(BLACK_PAWN).count(), 2);
+        assert_eq!(fragment.color(Color::Black), fragment.role(Role::Pawn));
+        assert_eq!(fragment.all().count(), 3);
+        assert_eq!(fragment.to_board(), None);
+    }
+
+    #[test]
+    fn kings() {
+        let mut fragment = BoardFragment::new();
+        assert!(fragment.place(Square::new(6), WHITE_KING));
+        assert_eq!(fragment.king_square(Color::White), Some(Square::new(6)));
+        // A second white king, or a piece on an occupied square
+        assert!(!fragment.place(Square::new(7), WHITE_KING));
+        assert!(!fragment.place(Square::new(6), BLACK_QUEEN));
+        assert!(fragment.to_board().is_none());
+
+        assert!(fragment.place(Square::new(62), BLACK_KING));
+        let board = fragment.to_board().unwrap();
+        assert_eq!(board.king_square(Color::Black), Square::new(62));
+        assert_eq!(BoardFragment::from(board), fragment);
+
+        assert_eq!(fragment.remove(Square::new(6)), Some(Piece(Color::White, Role::King)));
+        assert_eq!(fragment.king_square(Color::White), None);
+
+        let mut pm = [None; 64];
+        pm[0] = Some(BLACK_KING);
+        pm[63] = Some(BLACK_KING);
+        assert!(BoardFragment::from_placement(pm).is_err());
+    }
+
+    #[test]
+    fn from_board() {
+        let fragment = BoardFragment::from(Board::default());
+        assert_eq!(fragment.all().count(), 32);
+        assert_eq!(fragment.king_square(Color::White), Some(Square::new(4)));
+        assert_eq!(fragment.to_board(), Some(Board::default()));
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod flip_tests {
+    use crate::bits::{Flippable, Square};
+    use crate::position::{Color, Piece, Position};
+
+    /// Mirrors the ranks and swaps the colors of the position in `fen`, 
+    /// working on the FEN string alone
+    fn mirror_fen(fen: &str) -> String {
+        let fields: Vec<&str> = fen.split(' ').collect();
+        let swap_case = |ch: char| {
+            if ch.is_ascii_uppercase() { ch.to_ascii_lowercase() } else { ch.to_ascii_uppercase() }
+        };
+
+        let placement = fields[0].split('/').rev().collect::<Vec<_>>().join("/");
+        let placement: String = placement.chars().map(swap_case).collect();
+        let turn = if fields[1] == "w" { "b" } else { "w" };
+        let castling = if fields[2] == "-" {
+            "-".to_string()
+        } else {
+            let swapped: Vec<char> = fields[2].chars().map(swap_case).collect();
+            let upper = swapped.iter().filter(|ch| ch.is_ascii_uppercase());
+            let lower = swapped.iter().filter(|ch| ch.is_ascii_lowercase());
+            upper.chain(lower).collect()
+        };
+        let en_passant = match fields[3].as_bytes() {
+            [f, r] => format!("{}{}", *f as char, (b'1' + b'8' - r) as char),
+            _ => "-".to_string(),
+        };
+        format!("{} {} {} {} {} {}", placement, turn, castling, en_passant, fields[4], fields[5])
+    }
+
+    /// Asserts that flipping the position in `fen` mirrors its ranks and 
+    /// swaps its colors, that flipping it twice gives it back, and that the
+    /// flipped position's Zobrist key is the same as if it had been parsed
+    ///
+    /// The squares and bitboards of the flipped position are those of the
+    /// position [flipped](Flippable::flipped()) in turn.
+    pub(crate) fn assert_flip_symmetric(fen: &str) {
+        let p: Position = fen.parse().unwrap();
+        // Mirrored as parsed, since an en passant square may have been dropped
+        let mirrored = mirror_fen(&p.fen());
+        let flipped = p.flipped();
+        for c in [Color::White, Color::Black] {
+            assert_eq!(flipped.board.color(!c), p.board.color(c).flipped(), "{}", fen);
+            assert_eq!(flipped.board.king_square(!c), p.board.king_square(c).flipped(), "{}", fen);
+        }
+        for s in Square::iter() {
+            let piece = p.board.get(s).map(|Piece(c, r)| Piece(!c, r));
+            assert_eq!(flipped.board.get(s.flipped()), piece, "{}", fen);
+        }
+        assert_eq!(flipped.en_passant, p.en_passant.map(|s| s.flipped()), "{}", fen);
+        assert_eq!(flipped.fen(), mirrored, "{}", fen);
+        assert_eq!(flipped.flipped(), p, "{}", fen);
+        assert_eq!(flipped.flipped().fen(), p.fen());
+
+        let parsed: Position = mirrored.parse().unwrap();
+        assert_eq!(flipped, parsed, "{}", fen);
+        assert_eq!(flipped.zobrist_key(), parsed.zobrist_key(), "{}", fen);
+        assert_eq!(flipped.flipped().zobrist_key(), p.zobrist_key(), "{}", fen);
+        assert_eq!(flipped.validate(), p.validate(), "{}", fen);
+    }
+
+    #[test]
+    fn representative_positions() {
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w Kq f6 0 3",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+            "4k3/1P6/8/8/8/8/6p1/4K3 b - - 0 60",
+            "8/8/8/8/8/8/8/K6k w - - 50 100",
+            "4k2r/8/8/8/8/8/8/R3K3 b Qk - 3 20",
+        ] {
+            assert_flip_symmetric(fen);
+        }
+    }
+}
+
+#[cfg(test)]
+mod map_tests {
+    use crate::position::{Color, Piece, Role};
+    use crate::position::map::*;
+
+    const COLORS: [Color; 2] = [Color::White, Color::Black];
+    const ROLES: [Role; 6] = [Role::Pawn, Role::Knight, Role::Bishop, Role::Rook, Role::Queen, Role::King];
+
+    #[test]
+    fn color_map() {
+        let mut map = ColorMap::new(['w', 'b']);
+        for c in COLORS {
+            assert_eq!(map[c], char::from(c));
+            map[c] = map[c].to_ascii_uppercase();
+        }
+        assert_eq!(map, ColorMap::new(['W', 'B']));
+        assert_eq!(map.iter().collect::<String>(), "WB");
+    }
+
+    #[test]
+    fn role_map() {
+        let mut map = RoleMap::new([0; 6]);
+        for r in ROLES {
+            map[r] += r.index();
+        }
+        for r in ROLES {
+            assert_eq!(map[r], r.index());
+        }
+        assert_eq!(map.iter().sum::<usize>(), 15);
+    }
+
+    #[test]
+    fn piece_map() {
+        let mut map: PieceMap<Option<Piece>> = PieceMap::default();
+        for c in COLORS {
+            for r in ROLES {
+                assert_eq!(map[Piece(c, r)], None);
+                map[Piece(c, r)] = Some(Piece(c, r));
+            }
+        }
+        for c in COLORS {
+            for r in ROLES {
+                assert_eq!(map[Piece(c, r)], Some(Piece(c, r)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod draw_tests {
+    use crate::position::Position;
+    use crate::position::draw::DrawReason;
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    /// Shuffles both kingside knights out and back
+    fn shuffle(p: &mut Position) {
+        for uci in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+            p.push_uci(uci).unwrap();
+        }
+    }
+
+    #[test]
+    fn repetition() {
+        let mut p = Position::default();
+        assert_eq!(p.claimable_draw(), None);
+        shuffle(&mut p);
+        assert_eq!(p.claimable_draw(), None);
+        shuffle(&mut p);
+        // Threefold may be claimed, but the game goes on
+        assert_eq!(p.claimable_draw(), Some(DrawReason::ThreefoldRepetition));
+        assert_eq!(p.automatic_draw(), None);
+        shuffle(&mut p);
+        assert_eq!(p.automatic_draw(), None);
+        shuffle(&mut p);
+        // Fivefold ends the game
+        assert_eq!(p.repetition_count(), 5);
+        assert_eq!(p.automatic_draw(), Some(DrawReason::FivefoldRepetition));
+        assert_eq!(p.claimable_draw(), Some(DrawReason::ThreefoldRepetition));
+    }
+
+    #[test]
+    fn move_rules() {
+        let p = parse("4k3/8/8/8/8/8/4P3/R3K3 w - - 99 80");
+        assert_eq!(p.claimable_draw(), None);
+        let p = parse("4k3/8/8/8/8/8/4P3/R3K3 w - - 100 80");
+        assert_eq!(p.claimable_draw(), Some(DrawReason::FiftyMoves));
+        assert_eq!(p.automatic_draw(), None);
+        let p = parse("4k3/8/8/8/8/8/4P3/R3K3 w - - 150 100");
+        assert_eq!(p.automatic_draw(), Some(DrawReason::SeventyFiveMoves));
+        // Unless the last move checkmated
+        let p = parse("R3k3/8/4K3/8/8/8/4P3/8 b - - 150 100");
+        assert_eq!(p.claimable_draw(), None);
+        assert_eq!(p.automatic_draw(), None);
+    }
+
+    #[test]
+    fn stalemate() {
+        let p = parse("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1");
+        assert_eq!(p.automatic_draw(), Some(DrawReason::Stalemate));
+        assert_eq!(p.claimable_draw(), None);
+    }
+
+    #[test]
+    fn insufficient_material() {
+        for fen in [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/2N1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+            // Bishops all on dark squares
+            "4kb2/8/8/8/8/4B3/8/2B1K3 w - - 0 1",
+        ] {
+            assert_eq!(parse(fen).automatic_draw(), Some(DrawReason::InsufficientMaterial), "{}", fen);
+        }
+        for fen in [
+            "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/1NN1K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/2BBK3 w - - 0 1",
+            // Bishops on opposite colors can still mate (with help)
+            "2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1",
+        ] {
+            assert!(!parse(fen).is_insufficient_material(), "{}", fen);
+        }
+    }
+}
+
+#[cfg(test)]
+mod symmetry_tests {
+    use crate::bits::Flippable;
+    use crate::position::Position;
+
+    fn canonical(fen: &str) -> String {
+        let p: Position = fen.parse().unwrap();
+        p.canonical_symmetry().fen()
+    }
+
+    #[test]
+    fn pawnless() {
+        // KR v K, and its images mirrored, turned and with the colors swapped
+        let fens = [
+            "8/8/8/8/8/2k5/8/R3K3 w - - 0 1",
+            "8/8/8/8/8/5k2/8/3K3R w - - 0 1",
+            "8/8/8/K7/8/2k5/8/R7 w - - 0 1",
+            "r3k3/8/2K5/8/8/8/8/8 b - - 0 1",
+            "3k3r/8/5K2/8/8/8/8/8 b - - 0 1",
+        ];
+        let expected = canonical(fens[0]);
+        for fen in fens {
+            assert_eq!(canonical(fen), expected, "{}", fen);
+        }
+        // The canonical form is its own canonical form
+        assert_eq!(canonical(&expected), expected);
+    }
+
+    #[test]
+    fn pawns() {
+        // Mirroring the files keeps pawns moving forwards
+        let expected = canonical("4k3/8/8/8/8/8/P7/4K3 w - - 0 1");
+        assert_eq!(canonical("3k4/8/8/8/8/8/7P/3K4 w - - 0 1"), expected);
+        assert_eq!(canonical("4k3/p7/8/8/8/8/8/4K3 b - - 0 1"), expected);
+        // But turning the board doesn't
+        assert_ne!(canonical("4k3/8/8/8/8/8/1P6/4K3 w - - 0 1"), canonical("8/8/8/K6k/8/8/1P6/8 w - - 0 1"));
+        // En passant squares are mirrored too
+        let p: Position = "4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 1".parse().unwrap();
+        let mirrored: Position = "3k4/8/8/3pP3/8/8/8/3K4 w - d6 0 1".parse().unwrap();
+        assert_eq!(p.canonical_symmetry(), mirrored.canonical_symmetry());
+        assert!(p.canonical_symmetry().en_passant.is_some());
+    }
+
+    #[test]
+    fn castling() {
+        // With castling rights, only swapping the colors applies
+        let p: Position = "r3k2r/8/8/8/8/8/8/R3K2R w Kq - 0 1".parse().unwrap();
+        let canonical = p.canonical_symmetry();
+        assert!(canonical == p || canonical == p.flipped());
+        assert_eq!(canonical.castling.to_string().len(), 2);
+    }
+}
@@ -8,7 +8,7 @@ use crate::{
         board::Board,
         castling::Castling,
     },
-    bits::File,
+    bits::{File, Square},
 };
 
 use std::hash::{Hash, Hasher, BuildHasher};
@@ -34,15 +34,32 @@ impl ZobristPosition {
 
 impl From<Position> for ZobristPosition {
     fn from(p: Position) -> Self {
-        let mut h = ZobristHasher::new();
-        p.hash(&mut h);
         ZobristPosition { 
-            zobrist: h.finish(),
+            zobrist: p.zobrist_key(),
             position: p,
         }
     }
 }
 
+impl Position {
+    /// Gets the Zobrist key of the position
+    #[inline]
+    pub fn zobrist_key(&self) -> u64 {
+        self.zobrist
+    }
+
+    /// Recomputes the Zobrist key of the position from scratch
+    /// 
+    /// The key is kept up to date by the methods of [`Position`], but it must 
+    /// be resynced after mutating the position's fields directly (e.g. when 
+    /// editing the board)
+    pub fn rebuild_zobrist(&mut self) {
+        let mut h = ZobristHasher::new();
+        self.hash(&mut h);
+        self.zobrist = h.finish();
+    }
+}
+
 /// A ZobristHasher factory
 pub struct BuildZobristHasher;
 
@@ -95,6 +112,38 @@ impl Hasher for ZobristHasher {
             shamt = (shamt + 1) & 7;
         }
     }
+
+    fn write_u64(&mut self, i: u64) {
+        self.0 ^= i;
+    }
+}
+
+// Zobrist key components, for updating the key of a position incrementally
+
+/// Gets the Zobrist key component of piece `p` on square `s`
+#[inline]
+pub(crate) fn piece_key(p: Piece, s: Square) -> u64 {
+    Board::ZOBRIST_PRNS[usize::from(s)][p.0 as usize][p.1 as usize]
+}
+
+/// Gets the Zobrist key component of black being the side to move
+#[inline]
+pub(crate) fn turn_key() -> u64 {
+    Color::ZOBRIST_PRN
+}
+
+/// Gets the Zobrist key component of the castling rights
+#[inline]
+pub(crate) fn castling_key(castling: Castling) -> u64 {
+    let mut h = ZobristHasher::new();
+    castling.hash(&mut h);
+    h.finish()
+}
+
+/// Gets the Zobrist key component of the en passant square
+#[inline]
+pub(crate) fn en_passant_key(en_passant: Option<Square>) -> u64 {
+    en_passant.map_or(0, |s| File::ZOBRIST_PRNS[s.file() as usize])
 }
 
 
@@ -138,6 +187,8 @@ impl Color {
     const ZOBRIST_PRN: u64 = const_random!(u64);
 }
 
+// Colors and files hash by their Zobrist keys rather than deriving `Hash`,
+// which also suits hash maps (and the derived `Hash` of `Piece` and `Coords`)
 impl Hash for Color {
     fn hash<H: Hasher>(&self, state: &mut H) {
         if *self == Color::Black { state.write_u64(Self::ZOBRIST_PRN) }
@@ -145,7 +196,10 @@ impl Hash for Color {
 }
 
 impl Castling {
-    const ZOBRIST_PRNS: [[u64; 2]; 2] = [[const_random!(u64); 2]; 2];
+    const ZOBRIST_PRNS: [[u64; 2]; 2] = [
+        [const_random!(u64), const_random!(u64)], 
+        [const_random!(u64), const_random!(u64)],
+    ];
 }
 
 impl Hash for Castling {
@@ -158,7 +212,12 @@ impl Hash for Castling {
 }
 
 impl File {
-    const ZOBRIST_PRNS: [u64; 8] = [const_random!(u64); 8];
+    const ZOBRIST_PRNS: [u64; 8] = [
+        const_random!(u64), const_random!(u64), 
+        const_random!(u64), const_random!(u64),
+        const_random!(u64), const_random!(u64), 
+        const_random!(u64), const_random!(u64),
+    ];
 }
 
 impl Hash for File {
@@ -0,0 +1,191 @@
+//! Searching the moves of a position for its best score
+//!
+//! Scores are in centipawns, from the side to move's point of view.
+
+pub mod selfplay;
+mod tests;
+
+use crate::{
+    bits::Square,
+    eval::PieceValues,
+    movegen::Move,
+    ordering::OrderContext,
+    position::{
+        Color,
+        Position,
+    },
+};
+
+/// The score of being checkmated, below every other score
+pub const MATE_SCORE: i32 = 30_000;
+
+/// The most plies a [quiescence search](Position::qsearch()) goes, past which
+/// positions are scored by their static eval, even in check
+pub const QSEARCH_MAX_PLY: u32 = 32;
+
+/// # Search methods
+impl Position {
+    /// Gets the static [score](crate::eval::evaluate()) of the position, 
+    /// with the pieces valued by `values`, from the side to move's point of
+    /// view
+    pub fn static_eval(&self, values: &PieceValues) -> i32 {
+        match self.turn {
+            Color::White => self.evaluate(values),
+            Color::Black => -self.evaluate(values),
+        }
+    }
+
+    /// Returns `true` if color `c` has any pieces besides its king and pawns
+    pub fn has_non_pawn_material(&self, c: Color) -> bool {
+        (self.board.minors(c) | self.board.majors(c)).is_any()
+    }
+
+    /// Returns `true` if a search may try a
+    /// [null move](https://www.chessprogramming.org/Null_Move_Pruning), which
+    /// isn't when the side to move is in check, or has only its king and pawns
+    /// and so is prone to zugzwang
+    pub fn null_move_ok(&self) -> bool {
+        !self.is_in_check() && self.has_non_pawn_material(self.turn)
+    }
+
+    /// Returns `true` if move `m` captures on `prev_to`, the square the
+    /// opponent's last move went to, for
+    /// [recapture extensions](https://www.chessprogramming.org/Recapture_Extensions)
+    ///
+    /// En passant isn't a recapture, since it doesn't capture on the pawn's
+    /// destination.
+    pub fn is_recapture(&self, m: Move, prev_to: Square) -> bool {
+        match m {
+            Move::PawnMove { to, capture: Some(_), en_passant: false, .. } |
+            Move::Normal { to, capture: Some(_), .. } => {
+                to == prev_to
+            }
+            _ => false,
+        }
+    }
+
+    /// Searches the captures of the position until it is
+    /// [quiet](https://www.chessprogramming.org/Quiescence_Search), returning
+    /// its score within the window `alpha` to `beta`
+    ///
+    /// The side to move may stand pat on the [static eval](Self::static_eval())
+    /// or make a capture that doesn't lose material by [SEE](Self::see()),
+    /// best first. In check, every move is searched instead, and being 
+    /// checkmated scores [`-MATE_SCORE`](MATE_SCORE).
+    ///
+    /// Quiet evasions that give check can follow each other, so lines are cut
+    /// off after [`QSEARCH_MAX_PLY`] plies to make sure the search ends.
+    pub fn qsearch(&mut self, alpha: i32, beta: i32) -> i32 {
+        self.qsearch_at(0, alpha, beta)
+    }
+
+    /// Searches the position `ply` plies from the root of a
+    /// [`qsearch`](Self::qsearch())
+    fn qsearch_at(&mut self, ply: u32, mut alpha: i32, beta: i32) -> i32 {
+        let values = PieceValues::default();
+        if ply >= QSEARCH_MAX_PLY {
+            return self.static_eval(&values)
+        }
+        let moves: Vec<Move> = if self.is_in_check() {
+            let evasions = self.generate();
+            if evasions.is_empty() {
+                return -MATE_SCORE
+            }
+            evasions
+        } else {
+            let stand_pat = self.static_eval(&values);
+            if stand_pat >= beta {
+                return stand_pat
+            }
+            alpha = alpha.max(stand_pat);
+
+            let mut captures: Vec<(Move, i32)> = self.generate()
+                .into_iter()
+                .filter(|m| m.is_capture())
+                .map(|m| (m, self.see(m, &values)))
+                .filter(|&(_, see)| see >= 0)
+                .collect();
+            captures.sort_by_key(|&(_, see)| std::cmp::Reverse(see));
+            captures.into_iter().map(|(m, _)| m).collect()
+        };
+
+        for m in moves {
+            let undo = self.make_move(m);
+            let score = -self.qsearch_at(ply + 1, -beta, -alpha);
+            self.unmake_move(undo);
+            if score >= beta {
+                return score
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+
+    /// Searches the position `depth` plies deep by
+    /// [negamax](https://www.chessprogramming.org/Negamax) with alpha-beta
+    /// pruning, returning its score within the window `alpha` to `beta`
+    /// 
+    /// The leaves are searched by [`qsearch`](Self::qsearch()). A position 
+    /// that repeats one in the search path or the game history, or that is 
+    /// drawn by the fifty-move rule or stalemate, is scored as a draw: 
+    /// `-contempt` for the side to move at the root, and `contempt` for its
+    /// opponent, so a positive contempt avoids draws. Being checkmated scores
+    /// [`-MATE_SCORE`](MATE_SCORE), plus the number of plies from the root,
+    /// so that quicker mates score higher.
+    pub fn negamax(&mut self, depth: u32, alpha: i32, beta: i32, contempt: i32) -> i32 {
+        self.negamax_at(depth, 0, alpha, beta, contempt)
+    }
+
+    /// Searches the position `ply` plies from the root of a
+    /// [`negamax`](Self::negamax()) search
+    fn negamax_at(&mut self, depth: u32, ply: i32, mut alpha: i32, beta: i32, contempt: i32) -> i32 {
+        let draw = if ply % 2 == 0 { -contempt } else { contempt };
+        if ply > 0 && self.repetition_count() >= 2 {
+            return draw
+        }
+
+        let mut moves = self.generate();
+        if moves.is_empty() {
+            return if self.is_in_check() { -MATE_SCORE + ply } else { draw }
+        }
+        if ply > 0 && self.halfmove >= 100 {
+            return draw
+        }
+        if depth == 0 {
+            return self.qsearch(alpha, beta)
+        }
+
+        self.order_moves(&mut moves, &OrderContext::default());
+        let mut best = -MATE_SCORE;
+        for m in moves {
+            let undo = self.make_move(m);
+            let score = -self.negamax_at(depth - 1, ply + 1, -beta, -alpha, contempt);
+            self.unmake_move(undo);
+            best = best.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break
+            }
+        }
+        best
+    }
+
+    /// Gets the best legal move by a one-ply search, with each reply searched
+    /// by [`qsearch`](Self::qsearch()), or [`None`] if there are no moves
+    /// 
+    /// Moves with equal scores are chosen in the order they're generated.
+    pub fn best_move(&mut self) -> Option<Move> {
+        let mut best = None;
+        let mut alpha = -MATE_SCORE - 1;
+        for m in self.generate() {
+            let undo = self.make_move(m);
+            let score = -self.qsearch(-MATE_SCORE - 1, -alpha);
+            self.unmake_move(undo);
+            if score > alpha {
+                alpha = score;
+                best = Some(m);
+            }
+        }
+        best
+    }
+}
@@ -0,0 +1,122 @@
+//! Playing games of the engine against itself, recording each position with
+//! the move played and its score, as for generating training data
+
+use std::fmt::Display;
+
+use crate::{
+    movegen::Move,
+    ordering::OrderContext,
+    position::{
+        Color,
+        Position,
+        draw::DrawReason,
+    },
+};
+
+use super::MATE_SCORE;
+
+/// How a game ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    /// White checkmated black
+    WhiteWins,
+    /// Black checkmated white
+    BlackWins,
+    /// The game was drawn, or a draw was claimed, for the given reason
+    Draw(DrawReason),
+    /// The game was stopped before it ended
+    Unfinished,
+}
+
+impl Display for GameResult {
+    /// Writes the result as in PGN: `1-0`, `0-1`, `1/2-1/2` or `*`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameResult::WhiteWins => write!(f, "1-0"),
+            GameResult::BlackWins => write!(f, "0-1"),
+            GameResult::Draw(_) => write!(f, "1/2-1/2"),
+            GameResult::Unfinished => write!(f, "*"),
+        }
+    }
+}
+
+/// A position of a game, with the move played from it
+#[derive(Debug, Clone)]
+pub struct PlyRecord {
+    /// The position, without its game history
+    pub position: Position,
+    /// The move played
+    pub m: Move,
+    /// The score of the move by the search, from the side to move's point of
+    /// view
+    pub eval: i32,
+}
+
+/// The record of a game, ply by ply, with its result
+#[derive(Debug, Clone)]
+pub struct GameRecord {
+    /// The positions of the game and the moves played from them, in order
+    pub plies: Vec<PlyRecord>,
+    /// How the game ended
+    pub result: GameResult,
+}
+
+/// Plays a game from `start` with both sides playing the best move by a
+/// [`negamax`](Position::negamax()) search of `depth` plies (at least 1),
+/// until it ends or `max_plies` moves have been played
+///
+/// The game ends on checkmate, or on any draw either side
+/// [could claim](Position::claimable_draw()) or that
+/// [ends the game](Position::automatic_draw()), counting repetitions of the
+/// positions in `start`'s game history.
+pub fn self_play(start: &Position, depth: u32, max_plies: usize) -> GameRecord {
+    let mut p = start.clone();
+    let mut plies = Vec::new();
+    let result = loop {
+        if let Some(result) = game_result(&p) {
+            break result
+        }
+        if plies.len() >= max_plies {
+            break GameResult::Unfinished
+        }
+        // The game isn't over, so there is a move
+        let (m, eval) = search_root(&mut p, depth.max(1)).unwrap();
+        plies.push(PlyRecord { position: p.clone_fresh(), m, eval });
+        p.make_move(m);
+    };
+    GameRecord { plies, result }
+}
+
+/// Gets the result of the game if it's over
+fn game_result(p: &Position) -> Option<GameResult> {
+    if p.is_in_check() && p.generate().is_empty() {
+        return Some(match p.turn {
+            Color::White => GameResult::BlackWins,
+            Color::Black => GameResult::WhiteWins,
+        })
+    }
+    p.automatic_draw()
+        .or_else(|| p.claimable_draw())
+        .map(GameResult::Draw)
+}
+
+/// Gets the best move in the position by a search of `depth` plies, with
+/// its score, or [`None`] if there are no moves
+///
+/// Moves with equal scores are chosen in the order they're searched.
+fn search_root(p: &mut Position, depth: u32) -> Option<(Move, i32)> {
+    let mut moves = p.generate();
+    p.order_moves(&mut moves, &OrderContext::default());
+    let mut best = None;
+    let mut alpha = -MATE_SCORE - 1;
+    for m in moves {
+        let undo = p.make_move(m);
+        let score = -p.negamax_at(depth - 1, 1, -MATE_SCORE - 1, -alpha, 0);
+        p.unmake_move(undo);
+        if score > alpha {
+            alpha = score;
+            best = Some((m, score));
+        }
+    }
+    best
+}
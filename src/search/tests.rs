@@ -0,0 +1,272 @@
+#[cfg(test)]
+mod qsearch_tests {
+    use crate::eval::PieceValues;
+    use crate::position::Position;
+    use crate::search::*;
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    fn qsearch(fen: &str) -> i32 {
+        let mut p = parse(fen);
+        let before = p.clone();
+        let score = p.qsearch(-MATE_SCORE, MATE_SCORE);
+        assert_eq!(p, before);
+        score
+    }
+
+    #[test]
+    fn quiet() {
+        let p = Position::default();
+        assert_eq!(qsearch(&p.fen()), p.static_eval(&PieceValues::default()));
+    }
+
+    #[test]
+    fn free_piece() {
+        // The knights attack each other, so whoever is to move wins one (the
+        // pawns keep the lone knight left from being scaled down to a draw)
+        let white = qsearch("4k3/4p3/8/3n4/8/2N5/4P3/4K3 w - - 0 1");
+        assert!((250..=400).contains(&white), "{}", white);
+        let black = qsearch("4k3/4p3/8/3n4/8/2N5/4P3/4K3 b - - 0 1");
+        assert_eq!(black, white);
+    }
+
+    #[test]
+    fn defended_piece() {
+        // Rxd6 exd6 loses the exchange, so white stands pat
+        let fen = "4k3/4p3/3n4/8/8/8/8/3RK3 w - - 0 1";
+        let p = parse(fen);
+        assert_eq!(qsearch(fen), p.static_eval(&PieceValues::default()));
+    }
+
+    #[test]
+    fn recaptures() {
+        // Bxd5 wins a pawn, as Rxd5 would lose the rook to Rxd5
+        let bishop = qsearch("3rk3/8/8/3p4/8/8/6B1/3RK3 w - - 0 1");
+        let stand_pat = parse("3rk3/8/8/3p4/8/8/6B1/3RK3 w - - 0 1").static_eval(&PieceValues::default());
+        assert!(bishop > stand_pat);
+        assert!(bishop < stand_pat + 200);
+    }
+
+    #[test]
+    fn checkmated() {
+        // Back rank mate
+        assert_eq!(qsearch("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1"), -MATE_SCORE);
+    }
+
+    #[test]
+    fn terminates() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            // Queens and rooks attacking each other all over the board
+            "qqqqkqqq/8/8/8/8/8/8/QQQQKQQQ w - - 0 1",
+        ] {
+            let score = qsearch(fen);
+            assert!(score.abs() < MATE_SCORE, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn ply_cap() {
+        // In check, but past the cap the static eval is all that's left
+        let mut p = parse("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1");
+        let eval = p.static_eval(&PieceValues::default());
+        assert_eq!(p.qsearch_at(QSEARCH_MAX_PLY, -MATE_SCORE, MATE_SCORE), eval);
+        assert_eq!(p.qsearch_at(QSEARCH_MAX_PLY - 1, -MATE_SCORE, MATE_SCORE), -MATE_SCORE);
+    }
+}
+
+#[cfg(test)]
+mod null_move_tests {
+    use crate::position::{Color, Position};
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    #[test]
+    fn pawn_endgame() {
+        let p = parse("8/5k2/3p4/3P4/4K3/8/8/8 w - - 0 1");
+        assert!(!p.has_non_pawn_material(Color::White));
+        assert!(!p.null_move_ok());
+        // A lone minor piece is enough
+        let p = parse("8/5k2/3p4/3P4/4K3/8/8/6B1 w - - 0 1");
+        assert!(p.null_move_ok());
+        assert!(!p.has_non_pawn_material(Color::Black));
+    }
+
+    #[test]
+    fn middlegame() {
+        let p = parse("r1bq1rk1/pppp1ppp/2n2n2/2b1p3/2B1P3/3P1N2/PPP2PPP/RNBQ1RK1 w - - 0 1");
+        assert!(p.has_non_pawn_material(Color::White));
+        assert!(p.null_move_ok());
+        // But not in check
+        let p = parse("4k3/8/8/8/8/8/3q4/R3K2R w KQ - 0 1");
+        assert!(!p.null_move_ok());
+    }
+}
+
+#[cfg(test)]
+mod best_move_tests {
+    use crate::position::Position;
+
+    fn best_uci(fen: &str) -> Option<String> {
+        let mut p = Position::from_fen_string(fen.to_string()).unwrap();
+        let before = p.clone();
+        let best = p.best_move();
+        assert_eq!(p, before);
+        best.map(|m| p.uci(m))
+    }
+
+    #[test]
+    fn free_piece() {
+        assert_eq!(best_uci("4k3/8/8/3q4/8/2N5/8/4K3 w - - 0 1").as_deref(), Some("c3d5"));
+    }
+
+    #[test]
+    fn mate_in_one() {
+        assert_eq!(best_uci("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").as_deref(), Some("a1a8"));
+    }
+
+    #[test]
+    fn no_moves() {
+        assert_eq!(best_uci("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"), None);
+    }
+}
+
+#[cfg(test)]
+mod recapture_tests {
+    use crate::bits::Square;
+    use crate::position::Position;
+
+    #[test]
+    fn recapture() {
+        // After 1. e4 d5 2. exd5, black can recapture on d5
+        let mut p = Position::default();
+        for uci in ["e2e4", "d7d5", "e4d5"] {
+            p.push_uci(uci).unwrap();
+        }
+        let d5 = Square::new(35);
+        let moves = p.generate();
+        let recaptures: Vec<String> = moves.iter()
+            .filter(|&&m| p.is_recapture(m, d5))
+            .map(|&m| p.uci(m))
+            .collect();
+        assert_eq!(recaptures, ["d8d5"]);
+
+        // Captures elsewhere aren't recaptures
+        let p: Position = "4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1".parse().unwrap();
+        let exd5 = p.parse_uci("e4d5").unwrap();
+        assert!(p.is_recapture(exd5, d5));
+        assert!(!p.is_recapture(exd5, Square::new(36)));
+        let quiet = p.parse_uci("e4e5").unwrap();
+        assert!(!p.is_recapture(quiet, Square::new(36)));
+    }
+
+    #[test]
+    fn en_passant_is_not_recapture() {
+        let p: Position = "4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 1".parse().unwrap();
+        let exd6 = p.parse_uci("d5e6").unwrap();
+        assert!(exd6.is_en_passant());
+        assert!(!p.is_recapture(exd6, Square::new(36)));
+        assert!(!p.is_recapture(exd6, Square::new(44)));
+    }
+}
+
+#[cfg(test)]
+mod negamax_tests {
+    use crate::position::Position;
+    use crate::search::*;
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    fn negamax(fen: &str, depth: u32, contempt: i32) -> i32 {
+        let mut p = parse(fen);
+        let before = p.clone();
+        let score = p.negamax(depth, -MATE_SCORE, MATE_SCORE, contempt);
+        assert_eq!(p, before);
+        assert_eq!(p.history_len(), before.history_len());
+        score
+    }
+
+    #[test]
+    fn perpetual_check() {
+        // White is a rook down, but Qe8+ Kh7 Qh5+ Kg8 repeats
+        let fen = "6k1/6p1/8/7Q/8/r7/q5PP/7K w - - 0 1";
+        // Too shallow to see the repetition
+        assert!(negamax(fen, 3, 0) < -300);
+        assert_eq!(negamax(fen, 4, 0), 0);
+    }
+
+    #[test]
+    fn game_history() {
+        // Black's only move, Kg8, repeats the position before Qe8+, which is
+        // only in the game history
+        let mut p = parse("6k1/6p1/8/7Q/8/r7/q5PP/7K w - - 0 1");
+        for uci in ["h5e8", "g8h7", "e8h5"] {
+            p.push_uci(uci).unwrap();
+        }
+        assert_eq!(p.negamax(1, -MATE_SCORE, MATE_SCORE, 0), 0);
+        assert!(p.clone_fresh().negamax(1, -MATE_SCORE, MATE_SCORE, 0) > 300);
+    }
+
+    #[test]
+    fn mate() {
+        // Back rank mate in one scores as mate, one ply from the root
+        assert_eq!(negamax("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 2, 0), MATE_SCORE - 1);
+        // Stalemate is a draw, which is worth less than 0 to the root side 
+        // with contempt
+        assert_eq!(negamax("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", 3, 25), -25);
+    }
+}
+
+#[cfg(test)]
+mod selfplay_tests {
+    use crate::position::Position;
+    use crate::position::draw::DrawReason;
+    use crate::search::MATE_SCORE;
+    use crate::search::selfplay::*;
+
+    fn parse(fen: &str) -> Position {
+        Position::from_fen_string(fen.to_string()).unwrap()
+    }
+
+    #[test]
+    fn mate_in_two() {
+        // Ra7 and then Rb8 (or the like) mates
+        let start = parse("7k/8/8/8/8/8/R7/1R4K1 w - - 0 1");
+        let record = self_play(&start, 3, 10);
+        assert_eq!(record.result, GameResult::WhiteWins);
+        assert_eq!(record.plies.len(), 3);
+        assert_eq!(record.plies[0].position, start);
+        assert_eq!(record.plies[0].eval, MATE_SCORE - 3);
+        assert_eq!(record.plies[2].eval, MATE_SCORE - 1);
+
+        // Each position follows from the last by the move played
+        for pair in record.plies.windows(2) {
+            let mut p = pair[0].position.clone();
+            p.make_move(pair[0].m);
+            assert_eq!(p, pair[1].position);
+        }
+    }
+
+    #[test]
+    fn draws_and_ply_cap() {
+        let record = self_play(&parse("8/8/4k3/8/8/3K4/8/8 w - - 0 1"), 2, 10);
+        assert_eq!(record.result, GameResult::Draw(DrawReason::InsufficientMaterial));
+        assert!(record.plies.is_empty());
+
+        let record = self_play(&parse("4k2r/8/8/8/8/8/8/R3K3 w - - 99 80"), 1, 10);
+        assert_eq!(record.result, GameResult::Draw(DrawReason::FiftyMoves));
+        assert_eq!(record.plies.len(), 1);
+
+        let record = self_play(&Position::default(), 1, 4);
+        assert_eq!(record.result, GameResult::Unfinished);
+        assert_eq!(record.plies.len(), 4);
+        assert_eq!(record.result.to_string(), "*");
+    }
+}
@@ -0,0 +1,101 @@
+//! Playing as an engine over the
+//! [xboard protocol](https://www.gnu.org/software/xboard/engine-intf.html)
+//! (CECP), for GUIs that don't speak UCI
+//!
+//! Only the commands needed to play a game are understood: `new`, `setboard`,
+//! `usermove`, `go`, `force` and `quit`, with `xboard` and `protover` for the
+//! handshake. Moves are read and written in coordinate notation (see
+//! [`Position::parse_uci`] and [`Position::uci`]), and chosen by
+//! [`Position::best_move`].
+
+use std::io::{
+    self,
+    BufRead,
+    Write,
+};
+
+use crate::position::{
+    Color,
+    Position,
+};
+
+/// The state of an xboard session
+struct Session {
+    /// The position of the game
+    position: Position,
+    /// Whether the engine only makes the moves it's given, without replying
+    force: bool,
+}
+
+impl Session {
+    /// Makes the engine's move in the position and writes it to `w`, or the
+    /// result of the game if it's over
+    fn play<W: Write>(&mut self, w: &mut W) -> io::Result<()> {
+        match self.position.best_move() {
+            Some(m) => {
+                writeln!(w, "move {}", self.position.uci(m))?;
+                self.position.make_move(m);
+            }
+            None if self.position.is_in_check() => match self.position.turn {
+                Color::White => writeln!(w, "0-1 {{Black mates}}")?,
+                Color::Black => writeln!(w, "1-0 {{White mates}}")?,
+            },
+            None => writeln!(w, "1/2-1/2 {{Stalemate}}")?,
+        }
+        Ok(())
+    }
+
+    /// Makes the move `uci` given by the GUI, replying to it unless in force
+    /// mode
+    fn user_move<W: Write>(&mut self, uci: &str, w: &mut W) -> io::Result<()> {
+        if self.position.push_uci(uci).is_err() {
+            return writeln!(w, "Illegal move: {}", uci)
+        }
+        if !self.force {
+            self.play(w)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs an xboard session, reading commands from `r` and writing responses
+/// to `w`, until `quit` or the end of the input
+///
+/// A `new` game starts with the engine playing black. Commands that aren't
+/// understood are answered with an error and otherwise ignored.
+pub fn run_xboard<R: BufRead, W: Write>(r: R, mut w: W) -> io::Result<()> {
+    let mut session = Session { position: Position::default(), force: false };
+    for line in r.lines() {
+        let line = line?;
+        let (command, args) = match line.trim().split_once(' ') {
+            Some((command, args)) => (command, args.trim()),
+            None => (line.trim(), ""),
+        };
+        match command {
+            "" | "xboard" | "accepted" | "rejected" | "random" | "post" |
+            "nopost" | "hard" | "easy" | "computer" | "level" | "st" | "sd" |
+            "time" | "otim" => {}
+            "protover" => {
+                writeln!(w, "feature myname=\"patroclus\" setboard=1 usermove=1 sigint=0 sigterm=0 done=1")?;
+            }
+            "new" => {
+                session.position = Position::default();
+                session.force = false;
+            }
+            "setboard" => match args.parse() {
+                Ok(p) => session.position = p,
+                Err(_) => writeln!(w, "tellusererror Illegal position")?,
+            },
+            "usermove" => session.user_move(args, &mut w)?,
+            "force" => session.force = true,
+            "go" => {
+                session.force = false;
+                session.play(&mut w)?;
+            }
+            "quit" => break,
+            _ => writeln!(w, "Error (unknown command): {}", command)?,
+        }
+        w.flush()?;
+    }
+    Ok(())
+}
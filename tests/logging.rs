@@ -0,0 +1,58 @@
+#![cfg(feature = "log-positions")]
+
+use std::sync::Mutex;
+
+use patroclus::{log_move, log_position};
+use patroclus::position::Position;
+
+/// A logger that keeps the messages logged from this file, and not those of
+/// the library itself
+struct Capture(Mutex<Vec<(log::Level, String)>>);
+
+impl log::Log for Capture {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.target() == module_path!()
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return
+        }
+        let message = record.args().to_string();
+        self.0.lock().unwrap().push((record.level(), message));
+    }
+
+    fn flush(&self) {}
+}
+
+static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+
+#[test]
+fn logs_moves_and_positions() {
+    log::set_logger(&CAPTURE).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    let mut p = Position::default();
+    let e4 = p.parse_uci("e2e4").unwrap();
+    log_move!(e4);
+    log_move!("best", e4);
+    p.make_move(e4);
+    log_position!(p);
+
+    let logged = CAPTURE.0.lock().unwrap();
+    assert_eq!(logged[0], (log::Level::Trace, "move e2e4".to_string()));
+    assert_eq!(logged[1], (log::Level::Trace, "best: move e2e4".to_string()));
+    let (level, summary) = &logged[2];
+    assert_eq!(*level, log::Level::Debug);
+    assert!(summary.starts_with(&p.fen()), "{}", summary);
+    assert!(summary.contains("eval "), "{}", summary);
+}
+
+#[test]
+fn move_display() {
+    let p: Position = "r3k3/1P6/8/8/8/8/8/R3K2R w KQ - 0 1".parse().unwrap();
+    for (uci, shown) in [("b7a8q", "b7a8q"), ("e1g1", "O-O"), ("e1c1", "O-O-O"), ("a1a8", "a1a8")] {
+        let m = p.parse_uci(uci).unwrap();
+        assert_eq!(m.to_string(), shown);
+    }
+}
@@ -0,0 +1,80 @@
+//! Differential tests of move generation against the `shakmaty` crate, on
+//! random legal positions from the `Arbitrary` implementations
+#![cfg(feature = "arbitrary")]
+
+use std::collections::BTreeSet;
+
+use arbitrary::{Arbitrary, Unstructured};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use shakmaty::{CastlingMode, Chess, Position as _, fen::Fen};
+
+use patroclus::position::Position;
+
+/// Converts a position to a `shakmaty` position through its FEN string
+///
+/// Checks that `shakmaty` can't tell apart from a real game's are allowed,
+/// as random positions have them.
+fn to_shakmaty(p: &Position) -> Chess {
+    let fen: Fen = p.fen().parse().unwrap();
+    let chess = match fen.into_position(CastlingMode::Standard) {
+        Ok(chess) => Ok(chess),
+        Err(e) => e.ignore_impossible_check(),
+    };
+    chess.unwrap_or_else(|e| panic!("{} for {}", e, p.fen()))
+}
+
+/// Gets the legal moves of a position in UCI notation
+fn legal_moves(p: &Position) -> BTreeSet<String> {
+    p.generate().into_iter().map(|m| p.uci(m)).collect()
+}
+
+/// Gets the legal moves of a `shakmaty` position in UCI notation
+fn shakmaty_legal_moves(p: &Chess) -> BTreeSet<String> {
+    p.legal_moves()
+        .iter()
+        .map(|m| m.to_uci(CastlingMode::Standard).to_string())
+        .collect()
+}
+
+/// Asserts that both crates generate the same moves in the position, and in
+/// each position after one of them
+fn assert_agrees(p: &mut Position) {
+    let chess = to_shakmaty(p);
+    assert_eq!(legal_moves(p), shakmaty_legal_moves(&chess), "{}", p.fen());
+    assert_eq!(p.perft(1), chess.legal_moves().len() as u64, "{}", p.fen());
+
+    for m in p.generate() {
+        let undo = p.make_move(m);
+        let chess = to_shakmaty(p);
+        assert_eq!(legal_moves(p), shakmaty_legal_moves(&chess), "{}", p.fen());
+        p.unmake_move(undo);
+    }
+}
+
+#[test]
+fn random_positions() {
+    let mut rng = StdRng::seed_from_u64(0x70a7_0c1e);
+    let mut bytes = [0u8; 256];
+    for _ in 0..200 {
+        rng.fill(&mut bytes[..]);
+        let mut u = Unstructured::new(&bytes);
+        let Ok(mut p) = Position::arbitrary(&mut u) else { continue };
+        assert_agrees(&mut p);
+    }
+}
+
+#[test]
+fn standard_positions() {
+    // Positions with castling and en passant, which random positions lack
+    for fen in [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/Pp2P3/2N2Q1p/1PPBBPPP/R3K2R b KQkq a3 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    ] {
+        let mut p: Position = fen.parse().unwrap();
+        assert_agrees(&mut p);
+    }
+}
@@ -0,0 +1,50 @@
+#![cfg(feature = "xboard")]
+
+use patroclus::xboard::run_xboard;
+
+fn session(input: &str) -> String {
+    let mut output = Vec::new();
+    run_xboard(input.as_bytes(), &mut output).unwrap();
+    String::from_utf8(output).unwrap()
+}
+
+#[test]
+fn replies_to_usermove() {
+    let output = session("xboard\nprotover 2\nnew\nusermove e2e4\nquit\n");
+    assert!(output.contains("feature "), "{}", output);
+    let reply = output.lines().find(|l| l.starts_with("move ")).expect(&output);
+    assert_eq!(reply.split(' ').count(), 2, "{}", reply);
+}
+
+#[test]
+fn force_and_go() {
+    let output = session("new\nforce\nusermove e2e4\nusermove e7e5\nquit\n");
+    assert!(!output.contains("move "), "{}", output);
+
+    let output = session("new\nforce\nusermove e2e4\ngo\nquit\n");
+    assert_eq!(output.lines().filter(|l| l.starts_with("move ")).count(), 1, "{}", output);
+}
+
+#[test]
+fn setboard() {
+    // White takes the free queen
+    let output = session("force\nsetboard 4k3/8/8/3q4/8/2N5/8/4K3 w - - 0 1\ngo\nquit\n");
+    assert!(output.contains("move c3d5"), "{}", output);
+
+    let output = session("setboard not a fen\nquit\n");
+    assert!(output.contains("tellusererror"), "{}", output);
+}
+
+#[test]
+fn errors() {
+    let output = session("new\nusermove e2e5\nfoo\nquit\nusermove e2e4\n");
+    assert!(output.contains("Illegal move: e2e5"), "{}", output);
+    assert!(output.contains("Error (unknown command): foo"), "{}", output);
+    assert!(!output.contains("move "), "{}", output);
+}
+
+#[test]
+fn game_over() {
+    let output = session("setboard 7k/5Q2/6K1/8/8/8/8/8 b - - 0 1\ngo\nquit\n");
+    assert!(output.contains("1/2-1/2"), "{}", output);
+}